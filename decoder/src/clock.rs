@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+/// Estimates how the target's timestamp ticks relate to host (wall-clock) time.
+///
+/// Every sample pairs the raw timestamp of a frame with the host time at which the frame was
+/// received. A least-squares line through the samples yields the host time at which the target
+/// clock read zero (the offset) and the actual length of a tick (which gives the drift relative to
+/// the nominal tick length).
+///
+/// Transport latency is assumed to be roughly constant; it shows up as part of the offset.
+#[derive(Debug)]
+pub struct ClockSync {
+    nominal_tick: Option<Duration>,
+    /// First sample; all other samples are stored relative to it to preserve precision.
+    origin: Option<(u64, i128)>,
+    count: f64,
+    mean_ticks: f64,
+    mean_nanos: f64,
+    /// Running sum of squared deviations of the ticks.
+    m2_ticks: f64,
+    /// Running sum of the products of the tick and nanosecond deviations.
+    co_moment: f64,
+}
+
+impl ClockSync {
+    /// Creates a new, empty estimator.
+    ///
+    /// `nominal_tick` is the tick length the target claims to use (see
+    /// [`Table::timestamp_tick_duration`](crate::Table::timestamp_tick_duration)). It is used
+    /// until enough samples are available to measure the tick length.
+    pub fn new(nominal_tick: Option<Duration>) -> Self {
+        Self {
+            nominal_tick,
+            origin: None,
+            count: 0.0,
+            mean_ticks: 0.0,
+            mean_nanos: 0.0,
+            m2_ticks: 0.0,
+            co_moment: 0.0,
+        }
+    }
+
+    /// Records that a frame carrying `ticks` was received at `host_time`.
+    pub fn add_sample(&mut self, ticks: u64, host_time: OffsetDateTime) {
+        let nanos = host_time.unix_timestamp_nanos();
+        let (origin_ticks, origin_nanos) = *self.origin.get_or_insert((ticks, nanos));
+        let x = ticks as f64 - origin_ticks as f64;
+        let y = (nanos - origin_nanos) as f64;
+
+        // Welford's online algorithm; avoids the cancellation of the naive sum-of-squares approach
+        self.count += 1.0;
+        let dx = x - self.mean_ticks;
+        self.mean_ticks += dx / self.count;
+        self.mean_nanos += (y - self.mean_nanos) / self.count;
+        self.m2_ticks += dx * (x - self.mean_ticks);
+        self.co_moment += dx * (y - self.mean_nanos);
+    }
+
+    /// Number of samples recorded so far.
+    pub fn samples(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Returns the current estimate, or `None` if there's not enough data yet.
+    ///
+    /// A single sample is enough if the nominal tick length is known; otherwise the tick length
+    /// has to be measured from at least two samples with different timestamps.
+    pub fn estimate(&self) -> Option<ClockEstimate> {
+        let (origin_ticks, origin_nanos) = self.origin?;
+
+        let nanos_per_tick = if self.m2_ticks > 0.0 {
+            self.co_moment / self.m2_ticks
+        } else {
+            self.nominal_tick?.as_nanos() as f64
+        };
+
+        // host time (relative to the origin) at which the target clock read `origin_ticks`
+        let intercept = self.mean_nanos - nanos_per_tick * self.mean_ticks;
+
+        Some(ClockEstimate {
+            nominal_tick: self.nominal_tick,
+            nanos_per_tick,
+            origin_ticks,
+            origin_nanos: origin_nanos + intercept.round() as i128,
+        })
+    }
+}
+
+/// The relation between target ticks and host time, as estimated by [`ClockSync`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockEstimate {
+    nominal_tick: Option<Duration>,
+    nanos_per_tick: f64,
+    origin_ticks: u64,
+    /// Unix timestamp in nanoseconds corresponding to `origin_ticks`.
+    origin_nanos: i128,
+}
+
+impl ClockEstimate {
+    /// The measured length of one target tick, in nanoseconds.
+    pub fn nanos_per_tick(&self) -> f64 {
+        self.nanos_per_tick
+    }
+
+    /// Deviation of the measured tick length from the nominal one, in parts per million.
+    ///
+    /// Positive values mean that the target clock runs slow. Returns `None` if the nominal tick
+    /// length is unknown.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let nominal = self.nominal_tick?.as_nanos() as f64;
+        Some((self.nanos_per_tick / nominal - 1.0) * 1e6)
+    }
+
+    /// Host time at which the target clock read zero.
+    pub fn offset(&self) -> OffsetDateTime {
+        self.host_time(0)
+    }
+
+    /// Converts a target timestamp into the corresponding host time.
+    pub fn host_time(&self, ticks: u64) -> OffsetDateTime {
+        let elapsed = (ticks as f64 - self.origin_ticks as f64) * self.nanos_per_tick;
+        let nanos = self.origin_nanos + elapsed.round() as i128;
+        OffsetDateTime::from_unix_timestamp_nanos(nanos).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(nanos: i128) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_000_000_000 + nanos).unwrap()
+    }
+
+    #[test]
+    fn no_samples() {
+        let sync = ClockSync::new(Some(Duration::from_micros(1)));
+        assert_eq!(sync.estimate(), None);
+    }
+
+    #[test]
+    fn single_sample_needs_nominal_tick() {
+        let mut sync = ClockSync::new(None);
+        sync.add_sample(1_000, at(0));
+        assert_eq!(sync.estimate(), None);
+
+        let mut sync = ClockSync::new(Some(Duration::from_micros(1)));
+        sync.add_sample(1_000, at(0));
+        let estimate = sync.estimate().unwrap();
+        assert_eq!(estimate.host_time(2_000), at(1_000_000));
+        assert_eq!(estimate.offset(), at(-1_000_000));
+        assert_eq!(estimate.drift_ppm(), Some(0.0));
+    }
+
+    #[test]
+    fn measures_drift() {
+        // target clock runs 100 ppm slow: every microsecond tick takes 1.0001 µs of host time
+        let mut sync = ClockSync::new(Some(Duration::from_micros(1)));
+        for i in 0..100u64 {
+            let ticks = 5_000 + i * 10_000;
+            sync.add_sample(ticks, at(i as i128 * 10_001_000));
+        }
+
+        let estimate = sync.estimate().unwrap();
+        assert!((estimate.nanos_per_tick() - 1000.1).abs() < 1e-6);
+        assert!((estimate.drift_ppm().unwrap() - 100.0).abs() < 1e-3);
+        assert_eq!(estimate.host_time(5_000), at(0));
+        assert_eq!(estimate.host_time(1_005_000), at(1_000_100_000));
+    }
+
+    #[test]
+    fn averages_jitter() {
+        let mut sync = ClockSync::new(None);
+        for (i, jitter) in [0, 400, -400, 200, -200]
+            .iter()
+            .cycle()
+            .take(50)
+            .enumerate()
+        {
+            let ticks = i as u64 * 1_000;
+            sync.add_sample(ticks, at(ticks as i128 * 1_000 + jitter));
+        }
+
+        let estimate = sync.estimate().unwrap();
+        assert_eq!(sync.samples(), 50);
+        assert!((estimate.nanos_per_tick() - 1000.0).abs() < 0.01);
+        assert_eq!(estimate.drift_ppm(), None);
+    }
+}
@@ -3,9 +3,10 @@ use std::{
     ops::Range,
 };
 
-use crate::{Arg, DecodeError, FormatSliceElement, Table};
+use crate::{Arg, DecodeError, FormatSliceElement, MaxFrameSize, Table};
 use byteorder::{ReadBytesExt, LE};
-use defmt_parser::{get_max_bitfield_range, Fragment, Parameter, Type};
+use defmt_parser::{get_max_bitfield_range, FormatSpec, Fragment, Parameter, Type};
+use half::{bf16, f16};
 
 pub(crate) struct Decoder<'t, 'b> {
     table: &'t Table,
@@ -17,19 +18,58 @@ impl<'t, 'b> Decoder<'t, 'b> {
         Self { table, bytes }
     }
 
-    /// Sort and deduplicate `params` so that they can be interpreted correctly during decoding
-    fn prepare_params(&self, params: &mut Vec<Parameter>) {
-        // deduplicate bitfields by merging them by index
-        merge_bitfields(params);
+    /// Reads an unsigned integer that is `size` bytes wide; it is LEB128-encoded if the table
+    /// uses varint integers.
+    pub(crate) fn read_uint(&mut self, size: usize) -> Result<u128, DecodeError> {
+        match self.table.varint {
+            true => self.read_leb128(size),
+            false => Ok(self.bytes.read_uint128::<LE>(size)?),
+        }
+    }
+
+    /// Reads the index of an interned string, which is LEB128-encoded if the indices don't fit
+    /// into 16 bits.
+    pub(crate) fn read_index(&mut self) -> Result<usize, DecodeError> {
+        let index = if self.table.stable_ids {
+            self.bytes.read_u32::<LE>()?.into()
+        } else if self.table.wide_indices {
+            self.read_leb128(4)?
+        } else {
+            self.read_uint(2)?
+        };
+        Ok(index as usize)
+    }
+
+    /// Reads a LEB128-encoded integer that fits into `size` bytes.
+    pub(crate) fn read_leb128(&mut self, size: usize) -> Result<u128, DecodeError> {
+        let mut value = 0;
+        for shift in (0..size as u32 * 8).step_by(7) {
+            let byte = self.bytes.read_u8()?;
+            value |= u128::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return match value.checked_shr(size as u32 * 8) {
+                    Some(0) | None => Ok(value),
+                    Some(_) => Err(DecodeError::Malformed),
+                };
+            }
+        }
+        Err(DecodeError::Malformed)
+    }
 
-        // sort & dedup to ensure that format string args can be addressed by index too
-        params.sort_by(|a, b| a.index.cmp(&b.index));
-        params.dedup_by(|a, b| a.index == b.index);
+    /// Reads a signed integer that is `size` bytes wide; it is zigzag- and LEB128-encoded if the
+    /// table uses varint integers.
+    pub(crate) fn read_int(&mut self, size: usize) -> Result<i128, DecodeError> {
+        if !self.table.varint {
+            return Ok(self.bytes.read_int128::<LE>(size)?);
+        }
+
+        let zigzag = self.read_uint(size)?;
+        Ok((zigzag >> 1) as i128 ^ -((zigzag & 1) as i128))
     }
 
     /// Gets a format string from `bytes` and `table`
     fn get_format(&mut self) -> Result<&'t str, DecodeError> {
-        let index = self.bytes.read_u16::<LE>()? as usize;
+        let index = self.read_index()?;
         let format = self
             .table
             .get_without_level(index)
@@ -47,15 +87,13 @@ impl<'t, 'b> Decoder<'t, 'b> {
         let discriminant: usize = if u8::try_from(num_variants).is_ok() {
             self.bytes.read_u8()?.into()
         } else if u16::try_from(num_variants).is_ok() {
-            self.bytes.read_u16::<LE>()?.into()
+            self.read_uint(2)? as usize
         } else if u32::try_from(num_variants).is_ok() {
-            self.bytes
-                .read_u32::<LE>()?
+            self.read_uint(4)?
                 .try_into()
                 .map_err(|_| DecodeError::Malformed)?
         } else if u64::try_from(num_variants).is_ok() {
-            self.bytes
-                .read_u64::<LE>()?
+            self.read_uint(8)?
                 .try_into()
                 .map_err(|_| DecodeError::Malformed)?
         } else {
@@ -73,22 +111,44 @@ impl<'t, 'b> Decoder<'t, 'b> {
         num_elements: usize,
     ) -> Result<Vec<FormatSliceElement<'t>>, DecodeError> {
         let format = self.get_format()?;
-        let is_enum = format.contains('|');
 
         let mut elements = Vec::with_capacity(num_elements);
         for _i in 0..num_elements {
-            let format = if is_enum {
-                self.get_variant(format)?
-            } else {
-                format
-            };
-            let args = self.decode_format(format)?;
-            elements.push(FormatSliceElement { format, args });
+            elements.push(self.decode_element(format)?);
         }
 
         Ok(elements)
     }
 
+    fn decode_format_map(
+        &mut self,
+        num_entries: usize,
+    ) -> Result<Vec<(FormatSliceElement<'t>, FormatSliceElement<'t>)>, DecodeError> {
+        let key_format = self.get_format()?;
+        let value_format = self.get_format()?;
+
+        let mut entries = Vec::with_capacity(num_entries);
+        for _i in 0..num_entries {
+            let key = self.decode_element(key_format)?;
+            let value = self.decode_element(value_format)?;
+            entries.push((key, value));
+        }
+
+        Ok(entries)
+    }
+
+    /// Decodes an element of a slice or map, which all share the same `format`.
+    fn decode_element(&mut self, format: &'t str) -> Result<FormatSliceElement<'t>, DecodeError> {
+        // if the format string is an enum, `format` will be the variant
+        let format = if format.contains('|') {
+            self.get_variant(format)?
+        } else {
+            format
+        };
+        let args = self.decode_format(format)?;
+        Ok(FormatSliceElement { format, args })
+    }
+
     /// Decodes arguments from the stream, according to `format`.
     pub fn decode_format(&mut self, format: &str) -> Result<Vec<Arg<'t>>, DecodeError> {
         let mut args = vec![]; // will contain the deserialized arguments on return
@@ -101,22 +161,26 @@ impl<'t, 'b> Decoder<'t, 'b> {
             })
             .collect::<Vec<_>>();
 
-        self.prepare_params(&mut params);
+        prepare_params(&mut params);
 
         for param in &params {
             match &param.ty {
                 Type::I8 => args.push(Arg::Ixx(self.bytes.read_i8()? as i128)),
-                Type::I16 => args.push(Arg::Ixx(self.bytes.read_i16::<LE>()? as i128)),
-                Type::I32 => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
-                Type::I64 => args.push(Arg::Ixx(self.bytes.read_i64::<LE>()? as i128)),
-                Type::I128 => args.push(Arg::Ixx(self.bytes.read_i128::<LE>()?)),
-                Type::Isize => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
+                Type::I16 => args.push(Arg::Ixx(self.read_int(2)?)),
+                Type::I24 => args.push(Arg::Ixx(self.bytes.read_i24::<LE>()? as i128)),
+                Type::I32 => args.push(Arg::Ixx(self.read_int(4)?)),
+                Type::I64 => args.push(Arg::Ixx(self.read_int(8)?)),
+                Type::I128 => args.push(Arg::Ixx(self.read_int(16)?)),
+                Type::Isize => args.push(Arg::Ixx(self.read_int(4)?)),
                 Type::U8 => args.push(Arg::Uxx(self.bytes.read_u8()? as u128)),
-                Type::U16 => args.push(Arg::Uxx(self.bytes.read_u16::<LE>()? as u128)),
-                Type::U32 => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
-                Type::U64 => args.push(Arg::Uxx(self.bytes.read_u64::<LE>()? as u128)),
-                Type::U128 => args.push(Arg::Uxx(self.bytes.read_u128::<LE>()?)),
-                Type::Usize => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
+                Type::U16 => args.push(Arg::Uxx(self.read_uint(2)?)),
+                Type::U24 => args.push(Arg::Uxx(self.bytes.read_u24::<LE>()? as u128)),
+                Type::U32 => args.push(Arg::Uxx(self.read_uint(4)?)),
+                Type::U64 => args.push(Arg::Uxx(self.read_uint(8)?)),
+                Type::U128 => args.push(Arg::Uxx(self.read_uint(16)?)),
+                Type::Usize => args.push(Arg::Uxx(self.read_uint(4)?)),
+                Type::F16 => args.push(Arg::F16(f16::from_bits(self.bytes.read_u16::<LE>()?))),
+                Type::Bf16 => args.push(Arg::Bf16(bf16::from_bits(self.bytes.read_u16::<LE>()?))),
                 Type::F32 => args.push(Arg::F32(f32::from_bits(self.bytes.read_u32::<LE>()?))),
                 Type::F64 => args.push(Arg::F64(f64::from_bits(self.bytes.read_u64::<LE>()?))),
                 Type::Bool => args.push(Arg::Bool(match self.bytes.read_u8()? {
@@ -125,10 +189,15 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     _ => return Err(DecodeError::Malformed),
                 })),
                 Type::FormatSlice => {
-                    let num_elements = self.bytes.read_u32::<LE>()? as usize;
+                    let num_elements = self.read_uint(4)? as usize;
                     let elements = self.decode_format_slice(num_elements)?;
                     args.push(Arg::FormatSlice { elements });
                 }
+                Type::FormatMap => {
+                    let num_entries = self.read_uint(4)? as usize;
+                    let entries = self.decode_format_map(num_entries)?;
+                    args.push(Arg::FormatMap { entries });
+                }
                 Type::Format => {
                     let format = self.get_format()?;
 
@@ -155,10 +224,10 @@ impl<'t, 'b> Decoder<'t, 'b> {
 
                     let mut data = match size_after_truncation {
                         1 => self.bytes.read_u8()? as u128,
-                        2 => self.bytes.read_u16::<LE>()? as u128,
-                        3..=4 => self.bytes.read_u32::<LE>()? as u128,
-                        5..=8 => self.bytes.read_u64::<LE>()? as u128,
-                        9..=16 => self.bytes.read_u128::<LE>()?,
+                        2 => self.read_uint(2)?,
+                        3..=4 => self.read_uint(4)?,
+                        5..=8 => self.read_uint(8)?,
+                        9..=16 => self.read_uint(16)?,
                         _ => unreachable!(),
                     };
 
@@ -167,7 +236,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     args.push(Arg::Uxx(data));
                 }
                 Type::Str => {
-                    let str_len = self.bytes.read_u32::<LE>()? as usize;
+                    let str_len = self.read_uint(4)? as usize;
                     let mut arg_str_bytes = vec![];
 
                     // note: went for the suboptimal but simple solution; optimize if necessary
@@ -182,7 +251,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     args.push(Arg::Str(arg_str));
                 }
                 Type::IStr => {
-                    let str_index = self.bytes.read_u16::<LE>()? as usize;
+                    let str_index = self.read_index()?;
 
                     let string = self
                         .table
@@ -193,7 +262,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                 }
                 Type::U8Slice => {
                     // only supports byte slices
-                    let num_elements = self.bytes.read_u32::<LE>()? as usize;
+                    let num_elements = self.read_uint(4)? as usize;
                     let mut arg_slice = vec![];
 
                     // note: went for the suboptimal but simple solution; optimize if necessary
@@ -236,7 +305,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                 Type::FormatSequence => {
                     let mut seq_args = Vec::new();
                     loop {
-                        let index = self.bytes.read_u16::<LE>()? as usize;
+                        let index = self.read_index()?;
                         if index == 0 {
                             break;
                         }
@@ -246,6 +315,13 @@ impl<'t, 'b> Decoder<'t, 'b> {
                             .get_without_level(index)
                             .map_err(|_| DecodeError::Malformed)?;
 
+                        // `&dyn DynFormat` emits the tag of a derived enum here; a `|` in a
+                        // `write!` string is a literal
+                        let format = if self.table.is_derived(index) && format.contains('|') {
+                            self.get_variant(format)?
+                        } else {
+                            format
+                        };
                         let inner_args = self.decode_format(format)?;
                         seq_args.push(Arg::Format {
                             format,
@@ -261,6 +337,97 @@ impl<'t, 'b> Decoder<'t, 'b> {
     }
 }
 
+/// Sort and deduplicate `params` so that they can be interpreted correctly during decoding
+fn prepare_params(params: &mut Vec<Parameter>) {
+    // deduplicate bitfields by merging them by index
+    merge_bitfields(params);
+
+    // sort & dedup to ensure that format string args can be addressed by index too
+    params.sort_by_key(|a| a.index);
+    params.dedup_by(|a, b| a.index == b.index);
+}
+
+/// Computes how many bytes the arguments of `format` take up on the wire, at most.
+pub(crate) fn max_format_size(format: &str, table: &Table) -> Result<MaxFrameSize, DecodeError> {
+    let mut params = defmt_parser::parse(format, defmt_parser::ParserMode::ForwardsCompatible)
+        .map_err(|_| DecodeError::Malformed)?
+        .into_iter()
+        .filter_map(|frag| match frag {
+            Fragment::Parameter(param) => Some(param),
+            Fragment::Literal(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    prepare_params(&mut params);
+
+    let mut size = MaxFrameSize {
+        fixed: 0,
+        unbounded: false,
+    };
+    // integers wider than a byte take up to one more byte per 7 bits as varints
+    let leb128 = |size: usize| (size * 8).div_ceil(7);
+    let int = |size| if table.varint { leb128(size) } else { size };
+    let index = if table.stable_ids {
+        4
+    } else if table.wide_indices {
+        leb128(4)
+    } else {
+        int(2)
+    };
+    for param in &params {
+        let fixed = match &param.ty {
+            Type::I8 | Type::U8 | Type::Bool => 1,
+            Type::F16 | Type::Bf16 => 2,
+            Type::I24 | Type::U24 => 3,
+            Type::F32 | Type::Char => 4,
+            Type::F64 => 8,
+            Type::I16 | Type::U16 => int(2),
+            Type::I32 | Type::U32 | Type::Isize | Type::Usize => int(4),
+            Type::I64 | Type::U64 => int(8),
+            Type::I128 | Type::U128 => int(16),
+            Type::BitField(range) => match (range.end - 1) / 8 - range.start / 8 + 1 {
+                1 => 1,
+                2 => int(2),
+                3..=4 => int(4),
+                5..=8 => int(8),
+                _ => int(16),
+            },
+            Type::IStr => index,
+            Type::U8Array(len) => *len,
+            // the format string index of the elements is always present
+            Type::FormatArray(_) | Type::Format => {
+                size.unbounded = true;
+                index
+            }
+            // length prefix
+            Type::Str | Type::U8Slice => {
+                size.unbounded = true;
+                int(4)
+            }
+            Type::FormatSlice => {
+                size.unbounded = true;
+                int(4) + index
+            }
+            Type::FormatMap => {
+                size.unbounded = true;
+                int(4) + index + index
+            }
+            // terminator
+            Type::Debug | Type::Display => {
+                size.unbounded = true;
+                1
+            }
+            Type::FormatSequence => {
+                size.unbounded = true;
+                index
+            }
+        };
+        size.fixed += fixed;
+    }
+
+    Ok(size)
+}
+
 /// Note that this will not change the Bitfield params in place, i.e. if `params` was sorted before
 /// a call to this function, it won't be afterwards.
 fn merge_bitfields(params: &mut Vec<Parameter>) {
@@ -291,6 +458,7 @@ fn merge_bitfields(params: &mut Vec<Parameter>) {
                     end: largest,
                 }),
                 hint: None, // don't care
+                spec: FormatSpec::default(),
             });
 
             // remove old bitfields with this index
@@ -298,12 +466,8 @@ fn merge_bitfields(params: &mut Vec<Parameter>) {
             let mut i = 0;
             while i != params.len() {
                 match &params[i].ty {
-                    Type::BitField(_) => {
-                        if params[i].index == index {
-                            params.remove(i);
-                        } else {
-                            i += 1; // we haven't removed a bitfield -> move i forward
-                        }
+                    Type::BitField(_) if params[i].index == index => {
+                        params.remove(i);
                     }
                     _ => {
                         i += 1; // we haven't removed a bitfield -> move i forward
@@ -328,11 +492,13 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(0..3),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 0,
                 ty: Type::BitField(4..7),
                 hint: None,
+                spec: FormatSpec::default(),
             },
         ];
 
@@ -343,6 +509,7 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(0..7),
                 hint: None,
+                spec: FormatSpec::default(),
             }]
         );
     }
@@ -354,11 +521,13 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(1..3),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 0,
                 ty: Type::BitField(2..5),
                 hint: None,
+                spec: FormatSpec::default(),
             },
         ];
 
@@ -369,6 +538,7 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(1..5),
                 hint: None,
+                spec: FormatSpec::default(),
             }]
         );
     }
@@ -380,16 +550,19 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(0..3),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 1,
                 ty: Type::BitField(1..3),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 1,
                 ty: Type::BitField(4..5),
                 hint: None,
+                spec: FormatSpec::default(),
             },
         ];
 
@@ -401,11 +574,13 @@ mod tests {
                     index: 0,
                     ty: Type::BitField(0..3),
                     hint: None,
+                    spec: FormatSpec::default(),
                 },
                 Parameter {
                     index: 1,
                     ty: Type::BitField(1..5),
                     hint: None,
+                    spec: FormatSpec::default(),
                 }
             ]
         );
@@ -418,21 +593,25 @@ mod tests {
                 index: 0,
                 ty: Type::BitField(0..3),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 1,
                 ty: Type::U8,
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 2,
                 ty: Type::BitField(1..4),
                 hint: None,
+                spec: FormatSpec::default(),
             },
             Parameter {
                 index: 2,
                 ty: Type::BitField(4..5),
                 hint: None,
+                spec: FormatSpec::default(),
             },
         ];
 
@@ -445,16 +624,19 @@ mod tests {
                     index: 1,
                     ty: Type::U8,
                     hint: None,
+                    spec: FormatSpec::default(),
                 },
                 Parameter {
                     index: 0,
                     ty: Type::BitField(0..3),
                     hint: None,
+                    spec: FormatSpec::default(),
                 },
                 Parameter {
                     index: 2,
                     ty: Type::BitField(1..5),
                     hint: None,
+                    spec: FormatSpec::default(),
                 }
             ]
         );
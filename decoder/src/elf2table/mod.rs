@@ -22,6 +22,8 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
     // first pass to extract the `_defmt_version`
     let mut version = None;
     let mut encoding = None;
+    // firmware that predates `__DEFMT_INDEX_BITS` always uses 16-bit indices
+    let mut wide_indices = false;
 
     // Note that we check for a quoted and unquoted version symbol, since LLD has a bug that
     // makes it keep the quotes from the linker script.
@@ -74,6 +76,11 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
             }
             encoding = Some(new_encoding);
         }
+
+        // An absolute symbol defined by the linker script; its address is its value.
+        if name == "__DEFMT_INDEX_BITS" {
+            wide_indices = entry.address() > 16;
+        }
     }
 
     // NOTE: We need to make sure to return `Ok(None)`, not `Err`, when defmt is not in use.
@@ -98,13 +105,25 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
         self::check_version(&version).map_err(anyhow::Error::msg)?;
     }
 
-    let encoding = match encoding {
-        Some(e) => e.parse()?,
-        None => bail!("No defmt encoding specified. This is a bug."),
+    // the features of `defmt` that change the wire format are appended to the framing, e.g.
+    // `rzcobs+varint+timestamp-delta`
+    let Some(encoding) = encoding else {
+        bail!("No defmt encoding specified. This is a bug.");
     };
+    let mut options = encoding.split('+');
+    let encoding = options.next().unwrap_or_default().parse()?;
+    let (mut varint, mut timestamp_delta, mut stable_ids) = (false, false, false);
+    for option in options {
+        match option {
+            "varint" => varint = true,
+            "timestamp-delta" => timestamp_delta = true,
+            "stable-ids" => stable_ids = true,
+            _ => bail!("Unknown defmt encoding option '{}' specified.", option),
+        }
+    }
 
     // second pass to demangle symbols
-    let mut map = BTreeMap::new();
+    let mut map = BTreeMap::<usize, TableEntry>::new();
     let mut bitflags_map = HashMap::new();
     let mut timestamp = None;
     for entry in elf.symbols() {
@@ -179,13 +198,29 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
                     ));
                 }
                 symbol::SymbolTag::Defmt(tag) => {
-                    map.insert(
-                        entry.address() as usize,
-                        TableEntry::new(
-                            StringEntry::new(tag, sym.data().to_string()),
-                            name.to_string(),
+                    let string = StringEntry::new(tag, sym.data().to_string());
+                    let index = match (stable_ids, sym.stable_id()) {
+                        (false, _) => entry.address() as usize,
+                        (true, Some(index)) => index as usize,
+                        (true, None) => bail!(
+                            "defmt symbol `{}` has no crate name to derive its index from",
+                            name
                         ),
-                    );
+                    };
+
+                    match map.get(&index) {
+                        // identical strings of the same crate share their stable index
+                        Some(other) if stable_ids => ensure!(
+                            *other.string() == string,
+                            "stable index collision: {:#010x} is used by both {:?} and {:?}; change one of the strings",
+                            index,
+                            other.string().string(),
+                            sym.data()
+                        ),
+                        _ => {
+                            map.insert(index, TableEntry::new(string, name.to_string()));
+                        }
+                    }
                 }
                 symbol::SymbolTag::Custom(_) => {}
             }
@@ -213,6 +248,13 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
         timestamp,
         bitflags,
         encoding,
+        hints: Default::default(),
+        hexdump_multiline: true,
+        varint,
+        wide_indices,
+        timestamp_delta,
+        stable_ids,
+        build_id: elf.build_id()?.map(<[u8]>::to_vec),
     }))
 }
 
@@ -361,8 +403,17 @@ pub fn get_locations(elf: &[u8], table: &Table) -> Result<Locations, anyhow::Err
                     let linkage_name = core::str::from_utf8(&linkage_name_slice)?;
 
                     if name == "DEFMT_LOG_STATEMENT" {
-                        if table.raw_symbols().any(|i| i == linkage_name) {
-                            let addr = exprloc2address(unit.encoding(), &loc)?;
+                        let index = table
+                            .entries()
+                            .find(|(_, entry)| entry.raw_symbol() == linkage_name)
+                            .map(|(index, _)| index);
+                        if let Some(index) = index {
+                            // stable indices aren't addresses; of identical log statements, which
+                            // share an index, only the first one is in the table
+                            let addr = match table.stable_ids {
+                                true => index as u64,
+                                false => exprloc2address(unit.encoding(), &loc)?,
+                            };
                             let file = file_index_to_path(file_index, &unit, &dwarf)?;
                             let module = segments.join("::");
 
@@ -444,3 +495,64 @@ fn exprloc2address<R: gimli::read::Reader<Offset = usize>>(
 
     Err(anyhow!("`Operation::Address` not found"))
 }
+
+#[cfg(test)]
+mod tests {
+    use object::{
+        write::{Object, Symbol, SymbolSection},
+        Architecture, BinaryFormat, Endianness, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+    };
+
+    use super::*;
+
+    /// Builds an ELF file with a `.defmt` section that holds one log statement at `index`, and
+    /// the `__DEFMT_INDEX_BITS` symbol if `index_bits` is given.
+    fn elf(index: u64, index_bits: Option<u64>) -> Vec<u8> {
+        let mut obj = Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+        let defmt = obj.add_section(vec![], b".defmt".to_vec(), SectionKind::ReadOnlyData);
+        obj.append_section_data(defmt, &[0], 1);
+
+        let mut add_symbol = |name: &str, value, section| {
+            obj.add_symbol(Symbol {
+                name: name.as_bytes().to_vec(),
+                value,
+                size: 0,
+                kind: SymbolKind::Data,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section,
+                flags: SymbolFlags::None,
+            });
+        };
+        add_symbol("_defmt_version_ = 4", 0, SymbolSection::Absolute);
+        add_symbol(
+            "_defmt_encoding_ = rzcobs",
+            0,
+            SymbolSection::Section(defmt),
+        );
+        add_symbol(
+            r#"{"package":"app","tag":"defmt_info","data":"hello","disambiguator":"1","crate_name":"app"}"#,
+            index,
+            SymbolSection::Section(defmt),
+        );
+        if let Some(bits) = index_bits {
+            add_symbol("__DEFMT_INDEX_BITS", bits, SymbolSection::Absolute);
+        }
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn index_bits() {
+        let table = Table::parse(&elf(1, Some(16))).unwrap().unwrap();
+        assert!(!table.wide_indices());
+        assert_eq!(table.indices().collect::<Vec<_>>(), [1]);
+
+        let table = Table::parse(&elf(70_000, Some(32))).unwrap().unwrap();
+        assert!(table.wide_indices());
+        assert_eq!(table.indices().collect::<Vec<_>>(), [70_000]);
+
+        // firmware that predates the symbol
+        let table = Table::parse(&elf(1, None)).unwrap().unwrap();
+        assert!(!table.wide_indices());
+    }
+}
@@ -16,6 +16,9 @@ pub struct Symbol {
     /// * `defmt_fmt`, `defmt_str` for interned format strings and string literals.
     /// * `defmt_trace`, `defmt_debug`, `defmt_info`, `defmt_warn`, `defmt_error` for logging
     ///   messages used at the different log levels.
+    /// * `defmt_span_enter`, `defmt_span_exit` for the frames marking the start and end of a span.
+    /// * `defmt_counter`, `defmt_gauge`, `defmt_histogram` for metric updates; the data is the name
+    ///   of the metric.
     /// * Anything starting with `defmt_` is reserved for use by defmt, other prefixes are free for
     ///   use by third-party apps (but they all should use a prefix!).
     tag: String,
@@ -32,6 +35,7 @@ pub enum SymbolTag<'a> {
     Defmt(Tag),
 
     /// Non-`defmt_*` tag for custom tooling.
+    #[allow(dead_code)]
     Custom(&'a str),
 }
 
@@ -42,21 +46,9 @@ impl Symbol {
     }
 
     pub fn tag(&self) -> SymbolTag<'_> {
-        match &*self.tag {
-            "defmt_prim" => SymbolTag::Defmt(Tag::Prim),
-            "defmt_derived" => SymbolTag::Defmt(Tag::Derived),
-            "defmt_bitflags" => SymbolTag::Defmt(Tag::Bitflags),
-            "defmt_write" => SymbolTag::Defmt(Tag::Write),
-            "defmt_timestamp" => SymbolTag::Defmt(Tag::Timestamp),
-            "defmt_bitflags_value" => SymbolTag::Defmt(Tag::BitflagsValue),
-            "defmt_str" => SymbolTag::Defmt(Tag::Str),
-            "defmt_println" => SymbolTag::Defmt(Tag::Println),
-            "defmt_trace" => SymbolTag::Defmt(Tag::Trace),
-            "defmt_debug" => SymbolTag::Defmt(Tag::Debug),
-            "defmt_info" => SymbolTag::Defmt(Tag::Info),
-            "defmt_warn" => SymbolTag::Defmt(Tag::Warn),
-            "defmt_error" => SymbolTag::Defmt(Tag::Error),
-            _ => SymbolTag::Custom(&self.tag),
+        match Tag::from_symbol_tag(&self.tag) {
+            Some(tag) => SymbolTag::Defmt(tag),
+            None => SymbolTag::Custom(&self.tag),
         }
     }
 
@@ -75,4 +67,11 @@ impl Symbol {
     pub fn crate_name(&self) -> Option<&str> {
         self.crate_name.as_deref()
     }
+
+    /// Returns the index of the symbol with the `stable-ids` encoding option, which is derived from
+    /// its tag, crate and data.
+    pub fn stable_id(&self) -> Option<u32> {
+        let crate_name = self.crate_name.as_deref()?;
+        Some(defmt_parser::stable_id(&self.tag, crate_name, &self.data))
+    }
 }
@@ -1,12 +1,16 @@
 use std::{
+    collections::BTreeMap,
     convert::TryFrom,
     fmt::{self, Write as _},
     mem,
 };
 
-use crate::{Arg, BitflagsKey, Table};
+use crate::{Arg, BitflagsKey, HintValue, MetricKind, MetricSample, Table};
 use colored::Colorize;
-use defmt_parser::{DisplayHint, Fragment, Level, ParserMode, TimePrecision, Type};
+use defmt_parser::{
+    Alignment, DisplayHint, FormatSpec, Fragment, Level, Parameter, ParserMode, TimePrecision, Type,
+};
+use half::{bf16, f16};
 use time::{macros::format_description, OffsetDateTime};
 
 /// Used to convert a `i128` value into right target type in hex
@@ -17,6 +21,7 @@ impl std::fmt::LowerHex for I128Hex {
         match self.1 {
             Type::I8 => fmt::LowerHex::fmt(&(self.0 as i8), f),
             Type::I16 => fmt::LowerHex::fmt(&(self.0 as i16), f),
+            Type::I24 => fmt::LowerHex::fmt(&(self.0 as u32 & 0xff_ffff), f),
             Type::I32 => fmt::LowerHex::fmt(&(self.0 as i32), f),
             Type::I64 => fmt::LowerHex::fmt(&(self.0 as i64), f),
             Type::I128 => fmt::LowerHex::fmt(&self.0, f),
@@ -30,6 +35,7 @@ impl std::fmt::UpperHex for I128Hex {
         match self.1 {
             Type::I8 => fmt::UpperHex::fmt(&(self.0 as i8), f),
             Type::I16 => fmt::UpperHex::fmt(&(self.0 as i16), f),
+            Type::I24 => fmt::UpperHex::fmt(&(self.0 as u32 & 0xff_ffff), f),
             Type::I32 => fmt::UpperHex::fmt(&(self.0 as i32), f),
             Type::I64 => fmt::UpperHex::fmt(&(self.0 as i64), f),
             Type::I128 => fmt::UpperHex::fmt(&self.0, f),
@@ -38,6 +44,33 @@ impl std::fmt::UpperHex for I128Hex {
     }
 }
 
+fn numeric_value(arg: &Arg) -> Option<f64> {
+    match arg {
+        Arg::Bool(value) => Some(f64::from(u8::from(*value))),
+        // go through the shortest representation, so that e.g. `0.1f32` becomes `0.1f64`
+        Arg::F16(value) => shortest_f32(value.to_f32(), round_f16)
+            .to_string()
+            .parse()
+            .ok(),
+        Arg::Bf16(value) => shortest_f32(value.to_f32(), round_bf16)
+            .to_string()
+            .parse()
+            .ok(),
+        Arg::F32(value) => value.to_string().parse().ok(),
+        Arg::F64(value) => Some(*value),
+        Arg::Uxx(value) => Some(*value as f64),
+        Arg::Ixx(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+/// Marks a frame as the start or end of a span, identified by its ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanEvent {
+    Enter(u32),
+    Exit(u32),
+}
+
 /// A log frame
 #[derive(Debug, PartialEq)]
 pub struct Frame<'t> {
@@ -49,6 +82,197 @@ pub struct Frame<'t> {
     // Format string
     format: &'t str,
     args: Vec<Arg<'t>>,
+    span: Option<SpanEvent>,
+    metric: Option<MetricKind>,
+    /// The timestamp as the difference to the previous frame, until it's resolved.
+    timestamp_delta: Option<u64>,
+}
+
+/// Formats a float in the shortest form that round-trips, unless `spec` has a precision.
+fn format_float<F>(
+    x: F,
+    hint: Option<&DisplayHint>,
+    spec: &FormatSpec,
+    buf: &mut String,
+) -> Result<(), fmt::Error>
+where
+    F: ryu::Float + fmt::Display,
+{
+    let zero_pad = match hint {
+        Some(DisplayHint::NoHint { zero_pad }) => *zero_pad,
+        _ => 0,
+    };
+    match (spec.precision, spec.sign_plus) {
+        (Some(precision), false) => write!(buf, "{x:0zero_pad$.precision$}"),
+        (Some(precision), true) => write!(buf, "{x:+0zero_pad$.precision$}"),
+        (None, sign_plus) => {
+            let mut ryu = ryu::Buffer::new();
+            let formatted = ryu.format(x);
+            let (sign, digits) = match formatted.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None if sign_plus => ("+", formatted),
+                None => ("", formatted),
+            };
+            let zeros = zero_pad.saturating_sub(sign.len() + digits.len());
+            write!(buf, "{sign}{:0>zeros$}{digits}", "")
+        }
+    }
+}
+
+/// Formats a half-precision value `x` that was converted to `f32`.
+///
+/// Without a precision, the shortest decimal number that `round`s to `x` is shown, e.g. `0.1`
+/// instead of `0.099975586`.
+fn format_half(
+    x: f32,
+    round: fn(f32) -> f32,
+    hint: Option<&DisplayHint>,
+    spec: &FormatSpec,
+    buf: &mut String,
+) -> Result<(), fmt::Error> {
+    let x = match spec.precision {
+        Some(_) => x,
+        None => shortest_f32(x, round),
+    };
+    format_float(x, hint, spec, buf)
+}
+
+/// Returns the `f32` with the fewest significant digits that `round`s to the same value as `x`.
+fn shortest_f32(x: f32, round: fn(f32) -> f32) -> f32 {
+    if !x.is_finite() {
+        return x;
+    }
+    (0..9)
+        .filter_map(|digits| format!("{x:.digits$e}").parse::<f32>().ok())
+        .find(|candidate| round(*candidate).to_bits() == x.to_bits())
+        .unwrap_or(x)
+}
+
+fn round_f16(x: f32) -> f32 {
+    f16::from_f32(x).to_f32()
+}
+
+fn round_bf16(x: f32) -> f32 {
+    bf16::from_f32(x).to_f32()
+}
+
+/// Formats `magnitude / 2^fractional_bits` as a decimal number.
+///
+/// Without a precision, as many decimal places are shown as are needed to tell apart adjacent
+/// fixed-point values, and trailing zeros are removed.
+fn format_fixed_point(
+    negative: bool,
+    magnitude: u128,
+    fractional_bits: u8,
+    spec: &FormatSpec,
+    buf: &mut String,
+) -> Result<(), fmt::Error> {
+    // limit the fraction so that multiplying it by 10 can't overflow; this only drops digits far
+    // beyond what is shown
+    let dropped_bits = u32::from(fractional_bits).saturating_sub(120);
+    let bits = u32::from(fractional_bits) - dropped_bits;
+    let magnitude = magnitude >> dropped_bits;
+    let mask = (1u128 << bits) - 1;
+
+    let mut integer = magnitude >> bits;
+    let mut fraction = magnitude & mask;
+    // 10^-places <= 2^-bits
+    let places = spec
+        .precision
+        .unwrap_or_else(|| (f64::from(bits) * std::f64::consts::LOG10_2).ceil() as usize);
+    let mut digits = Vec::with_capacity(places);
+    for _ in 0..places {
+        fraction *= 10;
+        digits.push((fraction >> bits) as u8);
+        fraction &= mask;
+    }
+
+    // round half away from zero
+    if bits > 0 && fraction >> (bits - 1) == 1 {
+        let carry = digits.iter_mut().rev().all(|digit| {
+            *digit = (*digit + 1) % 10;
+            *digit == 0
+        });
+        if carry {
+            integer += 1;
+        }
+    }
+    if spec.precision.is_none() {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+    }
+
+    let is_zero = integer == 0 && digits.iter().all(|digit| *digit == 0);
+    match (negative && !is_zero, spec.sign_plus) {
+        (true, _) => buf.push('-'),
+        (false, true) => buf.push('+'),
+        (false, false) => {}
+    }
+    write!(buf, "{integer}")?;
+    if !digits.is_empty() {
+        buf.push('.');
+        buf.extend(digits.iter().map(|digit| char::from(b'0' + digit)));
+    }
+    Ok(())
+}
+
+/// Returns the first `precision` characters of `s`.
+fn truncate(s: &str, precision: Option<usize>) -> &str {
+    match precision.and_then(|precision| s.char_indices().nth(precision)) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Writes `bytes` like `hexdump -C`, with every line of 16 bytes preceded by a line break:
+///
+/// ```text
+/// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|
+/// 00000010  01 02                                             |..|
+/// ```
+fn format_hexdump(bytes: &[u8], buf: &mut String) -> Result<(), fmt::Error> {
+    if bytes.is_empty() {
+        return buf.write_str("[]");
+    }
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        write!(buf, "\n{:08x} ", line * 16)?;
+        for i in 0..16 {
+            if i == 8 {
+                buf.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => write!(buf, " {byte:02x}")?,
+                None => buf.push_str("   "),
+            }
+        }
+        buf.push_str("  |");
+        for byte in chunk {
+            buf.push(match byte.is_ascii_graphic() || *byte == b' ' {
+                true => *byte as char,
+                false => '.',
+            });
+        }
+        buf.push('|');
+    }
+    Ok(())
+}
+
+/// Pads the text that was written to `buf` after `start` to the width of `spec`.
+fn pad(buf: &mut String, start: usize, spec: &FormatSpec, align: Alignment) {
+    let len = buf[start..].chars().count();
+    let padding = match spec.width {
+        Some(width) if width > len => width - len,
+        _ => return,
+    };
+    let (left, right) = match align {
+        Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    let fill = |n| spec.fill.to_string().repeat(n);
+    buf.insert_str(start, &fill(left));
+    buf.push_str(&fill(right));
 }
 
 impl<'t> Frame<'t> {
@@ -69,6 +293,50 @@ impl<'t> Frame<'t> {
             timestamp_args,
             format,
             args,
+            span: None,
+            metric: None,
+            timestamp_delta: None,
+        }
+    }
+
+    pub(crate) fn with_span(mut self, span: Option<SpanEvent>) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub(crate) fn with_metric(mut self, metric: Option<MetricKind>) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub(crate) fn with_timestamp_delta(mut self, delta: Option<u64>) -> Self {
+        self.timestamp_delta = delta;
+        self
+    }
+
+    /// Turns a delta timestamp into an absolute one, given the timestamp of the previous frame
+    /// in `last`, and stores the timestamp of this frame in `last`.
+    ///
+    /// Without a previous timestamp, e.g. after a lost frame, the frame keeps having no timestamp
+    /// until the next keyframe. The build ID handshake has no timestamp and leaves `last` as is,
+    /// because the target doesn't count it as a frame either.
+    pub(crate) fn resolve_timestamp(&mut self, last: &mut Option<u64>) {
+        if self.build_id().is_some() {
+            return;
+        }
+        match (self.timestamp_delta, *last) {
+            (None, _) => *last = self.timestamp_ticks(),
+            (Some(delta), Some(previous)) => {
+                let ticks = previous.wrapping_add(delta);
+                self.timestamp_format = self
+                    .table
+                    .timestamp_entry()
+                    .map(|entry| &*entry.string.string);
+                self.timestamp_args = vec![Arg::Uxx(ticks.into())];
+                self.timestamp_delta = None;
+                *last = Some(ticks);
+            }
+            (Some(_), None) => {}
         }
     }
 
@@ -99,6 +367,90 @@ impl<'t> Frame<'t> {
         self.index
     }
 
+    /// Returns the build ID the firmware sent, if this frame is the build ID handshake.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        match (self.index, self.args.as_slice()) {
+            (0, [Arg::Slice(id)]) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the difference of the timestamp to the one of the previous frame, if the timestamp
+    /// was sent as a delta and a [`StreamDecoder`](crate::StreamDecoder) couldn't resolve it.
+    pub fn timestamp_delta(&self) -> Option<u64> {
+        self.timestamp_delta
+    }
+
+    /// Returns the raw value of the timestamp, if it consists of a single unsigned integer.
+    ///
+    /// The unit of the value can be obtained from [`Table::timestamp_tick_duration`].
+    pub fn timestamp_ticks(&self) -> Option<u64> {
+        match self.timestamp_args.as_slice() {
+            [Arg::Uxx(ticks)] => u64::try_from(*ticks).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this frame marks the start or end of a span, if it does.
+    pub fn span(&self) -> Option<SpanEvent> {
+        self.span
+    }
+
+    /// Returns the metric update carried by this frame, if it was produced by `defmt::counter!`,
+    /// `defmt::gauge!` or `defmt::histogram!`.
+    pub fn metric(&self) -> Option<MetricSample<'t>> {
+        Some(MetricSample {
+            kind: self.metric?,
+            name: self.format,
+            value: numeric_value(self.args.first()?)?,
+        })
+    }
+
+    /// Returns the value of each argument of the log statement as a number, in the order they were
+    /// passed.
+    ///
+    /// Integers, floats and booleans are converted to `f64`; all other arguments are `None`.
+    pub fn numeric_args(&self) -> Vec<Option<f64>> {
+        self.args.iter().map(numeric_value).collect()
+    }
+
+    /// Returns the format string of the log statement that produced this frame.
+    pub fn format(&self) -> &'t str {
+        self.format
+    }
+
+    /// Formats each argument of the log statement on its own, in the order they were passed.
+    ///
+    /// An argument that is used more than once in the format string is formatted according to its
+    /// first occurrence.
+    pub fn display_args(&self) -> Vec<String> {
+        if let Some(SpanEvent::Exit(_)) = self.span {
+            // the arguments are only sent when entering the span
+            return Vec::new();
+        }
+        if let Some(metric) = self.metric() {
+            return vec![metric.value.to_string()];
+        }
+        let fragments = defmt_parser::parse(self.format, ParserMode::ForwardsCompatible).unwrap();
+        let mut values = BTreeMap::new();
+        for fragment in fragments {
+            if let Fragment::Parameter(param) = fragment {
+                values.entry(param.index).or_insert_with(|| {
+                    let mut buf = String::new();
+                    self.format_arg(&param, &self.args[param.index], None, &mut buf)
+                        .unwrap(); // cannot fail, we only write to a `String`
+                    buf
+                });
+            }
+        }
+        values.into_values().collect()
+    }
+
+    /// Formats the message without any span marker.
+    pub(crate) fn message(&self) -> String {
+        self.format_args(self.format, &self.args, None)
+    }
+
     fn format_args(&self, format: &str, args: &[Arg], parent_hint: Option<&DisplayHint>) -> String {
         self.format_args_real(format, args, parent_hint).unwrap() // cannot fail, we only write to a `String`
     }
@@ -117,111 +469,212 @@ impl<'t> Frame<'t> {
                     buf.push_str(&lit);
                 }
                 Fragment::Parameter(param) => {
-                    let hint = param.hint.as_ref().or(parent_hint);
-
-                    match &args[param.index] {
-                        Arg::Bool(x) => write!(buf, "{x}")?,
-                        Arg::F32(x) => write!(buf, "{}", ryu::Buffer::new().format(*x))?,
-                        Arg::F64(x) => write!(buf, "{}", ryu::Buffer::new().format(*x))?,
-                        Arg::Uxx(x) => {
-                            match param.ty {
-                                Type::BitField(range) => {
-                                    let left_zeroes =
-                                        mem::size_of::<u128>() * 8 - range.end as usize;
-                                    let right_zeroes = left_zeroes + range.start as usize;
-                                    // isolate the desired bitfields
-                                    let bitfields = (*x << left_zeroes) >> right_zeroes;
-
-                                    if let Some(DisplayHint::Ascii) = hint {
-                                        let bstr = bitfields
-                                            .to_be_bytes()
-                                            .iter()
-                                            .skip(right_zeroes / 8)
-                                            .copied()
-                                            .collect::<Vec<u8>>();
-                                        self.format_bytes(&bstr, hint, &mut buf)?
-                                    } else {
-                                        self.format_u128(bitfields, hint, &mut buf)?;
-                                    }
-                                }
-                                _ => match hint {
-                                    Some(DisplayHint::ISO8601(precision)) => {
-                                        self.format_iso8601(*x as u64, precision, &mut buf)?
-                                    }
-                                    Some(DisplayHint::Debug) => {
-                                        self.format_u128(*x, parent_hint, &mut buf)?
-                                    }
-                                    _ => self.format_u128(*x, hint, &mut buf)?,
-                                },
-                            }
+                    self.format_arg(&param, &args[param.index], parent_hint, &mut buf)?
+                }
+            }
+        }
+        Ok(buf)
+    }
+
+    fn format_arg(
+        &self,
+        param: &Parameter,
+        arg: &Arg,
+        parent_hint: Option<&DisplayHint>,
+        buf: &mut String,
+    ) -> Result<(), fmt::Error> {
+        let start = buf.len();
+        self.format_value(param, arg, parent_hint, buf)?;
+
+        // like `core::fmt`, numbers are aligned to the right and everything else to the left
+        let is_number = matches!(
+            arg,
+            Arg::F16(_) | Arg::Bf16(_) | Arg::F32(_) | Arg::F64(_) | Arg::Uxx(_) | Arg::Ixx(_)
+        );
+        let align = param.spec.align.unwrap_or(match is_number {
+            true => Alignment::Right,
+            false => Alignment::Left,
+        });
+        pad(buf, start, &param.spec, align);
+        Ok(())
+    }
+
+    fn format_value(
+        &self,
+        param: &Parameter,
+        arg: &Arg,
+        parent_hint: Option<&DisplayHint>,
+        buf: &mut String,
+    ) -> Result<(), fmt::Error> {
+        let hint = param.hint.as_ref().or(parent_hint);
+
+        if let Some(DisplayHint::Custom(name) | DisplayHint::Unknown(name)) = hint {
+            if let Some(formatted) = self.format_custom(name, &param.ty, arg) {
+                buf.push_str(&formatted);
+                return Ok(());
+            }
+        }
+
+        // the `+` flag only applies to numbers that are displayed in decimal
+        if param.spec.sign_plus && !matches!(param.ty, Type::BitField(_)) {
+            let zero_pad = match hint {
+                None => Some(0),
+                Some(DisplayHint::NoHint { zero_pad }) => Some(*zero_pad),
+                _ => None,
+            };
+            match (arg, zero_pad) {
+                (Arg::Uxx(x), Some(zero_pad)) => return write!(buf, "{x:+0zero_pad$}"),
+                (Arg::Ixx(x), Some(zero_pad)) => return write!(buf, "{x:+0zero_pad$}"),
+                _ => {}
+            }
+        }
+
+        match arg {
+            Arg::Bool(x) => write!(buf, "{x}")?,
+            Arg::F16(x) => format_half(x.to_f32(), round_f16, hint, &param.spec, buf)?,
+            Arg::Bf16(x) => format_half(x.to_f32(), round_bf16, hint, &param.spec, buf)?,
+            Arg::F32(x) => format_float(*x, hint, &param.spec, buf)?,
+            Arg::F64(x) => format_float(*x, hint, &param.spec, buf)?,
+            Arg::Uxx(x) => {
+                match &param.ty {
+                    Type::BitField(range) => {
+                        let left_zeroes = mem::size_of::<u128>() * 8 - range.end as usize;
+                        let right_zeroes = left_zeroes + range.start as usize;
+                        // isolate the desired bitfields
+                        let bitfields = (*x << left_zeroes) >> right_zeroes;
+
+                        if let Some(DisplayHint::Ascii) = hint {
+                            let bstr = bitfields
+                                .to_be_bytes()
+                                .iter()
+                                .skip(right_zeroes / 8)
+                                .copied()
+                                .collect::<Vec<u8>>();
+                            self.format_bytes(&bstr, hint, buf)?
+                        } else {
+                            self.format_u128(bitfields, hint, &param.spec, buf)?;
                         }
-                        Arg::Ixx(x) => self.format_i128(*x, param.ty, hint, &mut buf)?,
-                        Arg::Str(x) | Arg::Preformatted(x) => self.format_str(x, hint, &mut buf)?,
-                        Arg::IStr(x) => self.format_str(x, hint, &mut buf)?,
-                        Arg::Format { format, args } => match parent_hint {
-                            Some(DisplayHint::Ascii) => {
-                                buf.push_str(&self.format_args(format, args, parent_hint));
-                            }
-                            _ => buf.push_str(&self.format_args(format, args, hint)),
-                        },
-                        Arg::FormatSequence { args } => {
-                            for arg in args {
-                                buf.push_str(&self.format_args("{=?}", &[arg.clone()], hint))
-                            }
+                    }
+                    _ => match hint {
+                        Some(DisplayHint::ISO8601(precision)) => {
+                            self.format_iso8601(*x as u64, precision, buf)?
                         }
-                        Arg::FormatSlice { elements } => {
-                            match hint {
-                                // Filter Ascii Hints, which contains u8 byte slices
-                                Some(DisplayHint::Ascii)
-                                    if elements.iter().filter(|e| e.format == "{=u8}").count()
-                                        != 0 =>
-                                {
-                                    let vals = elements
-                                        .iter()
-                                        .map(|e| match e.args.as_slice() {
-                                            [Arg::Uxx(v)] => u8::try_from(*v)
-                                                .expect("the value must be in u8 range"),
-                                            _ => panic!(
-                                                "FormatSlice should only contain one argument"
-                                            ),
-                                        })
-                                        .collect::<Vec<u8>>();
-                                    self.format_bytes(&vals, hint, &mut buf)?
-                                }
-                                _ => {
-                                    buf.write_str("[")?;
-                                    let mut is_first = true;
-                                    for element in elements {
-                                        if !is_first {
-                                            buf.write_str(", ")?;
-                                        }
-                                        is_first = false;
-                                        buf.write_str(&self.format_args(
-                                            element.format,
-                                            &element.args,
-                                            hint,
-                                        ))?;
-                                    }
-                                    buf.write_str("]")?;
+                        Some(DisplayHint::Debug) => {
+                            self.format_u128(*x, parent_hint, &param.spec, buf)?
+                        }
+                        _ => self.format_u128(*x, hint, &param.spec, buf)?,
+                    },
+                }
+            }
+            Arg::Ixx(x) => self.format_i128(*x, param.ty.clone(), hint, &param.spec, buf)?,
+            Arg::Str(x) | Arg::Preformatted(x) => {
+                self.format_str(truncate(x, param.spec.precision), hint, buf)?
+            }
+            Arg::IStr(x) => self.format_str(truncate(x, param.spec.precision), hint, buf)?,
+            Arg::Format { format, args } => match parent_hint {
+                Some(DisplayHint::Ascii) => {
+                    buf.push_str(&self.format_args(format, args, parent_hint));
+                }
+                _ => buf.push_str(&self.format_args(format, args, hint)),
+            },
+            Arg::FormatSequence { args } => {
+                for arg in args {
+                    buf.push_str(&self.format_args("{=?}", std::slice::from_ref(arg), hint))
+                }
+            }
+            Arg::FormatSlice { elements } => {
+                match hint {
+                    // Filter Ascii and Hexdump Hints, which contains u8 byte slices
+                    Some(DisplayHint::Ascii | DisplayHint::Hexdump)
+                        if elements.iter().filter(|e| e.format == "{=u8}").count() != 0 =>
+                    {
+                        let vals = elements
+                            .iter()
+                            .map(|e| match e.args.as_slice() {
+                                [Arg::Uxx(v)] => {
+                                    u8::try_from(*v).expect("the value must be in u8 range")
                                 }
+                                _ => panic!("FormatSlice should only contain one argument"),
+                            })
+                            .collect::<Vec<u8>>();
+                        self.format_bytes(&vals, hint, buf)?
+                    }
+                    _ => {
+                        buf.write_str("[")?;
+                        let mut is_first = true;
+                        for element in elements {
+                            if !is_first {
+                                buf.write_str(", ")?;
                             }
+                            is_first = false;
+                            buf.write_str(&self.format_args(element.format, &element.args, hint))?;
                         }
-                        Arg::Slice(x) => self.format_bytes(x, hint, &mut buf)?,
-                        Arg::Char(c) => write!(buf, "{c}")?,
+                        buf.write_str("]")?;
+                    }
+                }
+            }
+            Arg::FormatMap { entries } => {
+                buf.write_str("{")?;
+                let mut is_first = true;
+                for (key, value) in entries {
+                    if !is_first {
+                        buf.write_str(", ")?;
                     }
+                    is_first = false;
+                    buf.write_str(&self.format_args(key.format, &key.args, hint))?;
+                    buf.write_str(": ")?;
+                    buf.write_str(&self.format_args(value.format, &value.args, hint))?;
                 }
+                buf.write_str("}")?;
             }
+            Arg::Slice(x) => self.format_bytes(x, hint, buf)?,
+            Arg::Char(c) => write!(buf, "{c}")?,
         }
-        Ok(buf)
+        Ok(())
+    }
+
+    /// Formats `arg` with the handler the table has for the user-defined display hint `name`.
+    fn format_custom(&self, name: &str, ty: &Type, arg: &Arg) -> Option<String> {
+        let handler = self.table.hints.get(name)?;
+        let value = match arg {
+            Arg::Bool(x) => HintValue::Bool(*x),
+            Arg::F16(x) => HintValue::Float(f64::from(*x)),
+            Arg::Bf16(x) => HintValue::Float(f64::from(*x)),
+            Arg::F32(x) => HintValue::Float(f64::from(*x)),
+            Arg::F64(x) => HintValue::Float(*x),
+            Arg::Uxx(x) => match ty {
+                Type::BitField(range) => {
+                    let left_zeroes = mem::size_of::<u128>() * 8 - range.end as usize;
+                    let right_zeroes = left_zeroes + range.start as usize;
+                    HintValue::Unsigned((*x << left_zeroes) >> right_zeroes)
+                }
+                _ => HintValue::Unsigned(*x),
+            },
+            Arg::Ixx(x) => HintValue::Signed(*x),
+            Arg::Str(x) | Arg::Preformatted(x) => HintValue::Str(x),
+            Arg::IStr(x) => HintValue::Str(x),
+            Arg::Slice(x) => HintValue::Bytes(x),
+            Arg::Char(c) => HintValue::Char(*c),
+            // the hint propagates to the values these are made of
+            Arg::Format { .. }
+            | Arg::FormatSequence { .. }
+            | Arg::FormatSlice { .. }
+            | Arg::FormatMap { .. } => return None,
+        };
+        handler.format(value)
     }
 
     fn format_u128(
         &self,
         x: u128,
         hint: Option<&DisplayHint>,
+        spec: &FormatSpec,
         buf: &mut String,
     ) -> Result<(), fmt::Error> {
         match hint {
+            Some(DisplayHint::FixedPoint {
+                fractional_bits, ..
+            }) => format_fixed_point(false, x, *fractional_bits, spec, buf)?,
             Some(DisplayHint::NoHint { zero_pad }) => write!(buf, "{x:0zero_pad$}")?,
             Some(DisplayHint::Binary {
                 alternate,
@@ -294,9 +747,13 @@ impl<'t> Frame<'t> {
         x: i128,
         ty: Type,
         hint: Option<&DisplayHint>,
+        spec: &FormatSpec,
         buf: &mut String,
     ) -> Result<(), fmt::Error> {
         match hint {
+            Some(DisplayHint::FixedPoint {
+                fractional_bits, ..
+            }) => format_fixed_point(x < 0, x.unsigned_abs(), *fractional_bits, spec, buf)?,
             Some(DisplayHint::NoHint { zero_pad }) => write!(buf, "{x:0zero_pad$}")?,
             Some(DisplayHint::Binary {
                 alternate,
@@ -354,6 +811,9 @@ impl<'t> Frame<'t> {
                 }
                 buf.push('\"');
             }
+            Some(DisplayHint::Hexdump) if self.table.hexdump_multiline => {
+                format_hexdump(bytes, buf)?
+            }
             Some(DisplayHint::Hexadecimal { .. }) | Some(DisplayHint::Binary { .. }) => {
                 // `core::write!` doesn't quite produce the output we want, for example
                 // `write!("{:#04x?}", bytes)` produces a multi-line output
@@ -365,7 +825,7 @@ impl<'t> Frame<'t> {
                         buf.push_str(", ");
                     }
                     is_first = false;
-                    self.format_u128(*byte as u128, hint, buf)?;
+                    self.format_u128(*byte as u128, hint, &FormatSpec::default(), buf)?;
                 }
                 buf.push(']');
             }
@@ -432,6 +892,26 @@ pub struct DisplayMessage<'t> {
 
 impl fmt::Display for DisplayMessage<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(metric) = self.frame.metric() {
+            return fmt::Display::fmt(&metric, f);
+        }
+        match self.frame.span {
+            None => {}
+            Some(SpanEvent::Enter(_)) => f.write_str("→ ")?,
+            Some(SpanEvent::Exit(_)) => {
+                // the arguments are not available, so only print the literal parts
+                let fragments =
+                    defmt_parser::parse(self.frame.format, ParserMode::ForwardsCompatible).unwrap();
+                let literals = fragments
+                    .iter()
+                    .filter_map(|fragment| match fragment {
+                        Fragment::Literal(literal) => Some(&**literal),
+                        Fragment::Parameter(_) => None,
+                    })
+                    .collect::<String>();
+                return write!(f, "← {}", literals.trim());
+            }
+        }
         let args = self
             .frame
             .format_args(self.frame.format, &self.frame.args, None);
@@ -483,9 +963,7 @@ impl fmt::Display for DisplayFrame<'_> {
             })
             .unwrap_or_default();
 
-        let args = self
-            .frame
-            .format_args(self.frame.format, &self.frame.args, None);
+        let args = self.frame.display_message();
 
         write!(f, "{timestamp}{level}{args}")
     }
@@ -0,0 +1,113 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+/// A value that is formatted with a user-defined display hint, see [`HintHandler`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum HintValue<'a> {
+    Bool(bool),
+    Char(char),
+    Float(f64),
+    /// Any signed integer.
+    Signed(i128),
+    /// Any unsigned integer, including bitfields.
+    Unsigned(u128),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// Formats the values of the parameters that use a user-defined display hint, e.g. `{=u8:@name}`.
+///
+/// Handlers are registered with [`Table::register_hint`](crate::Table::register_hint). Closures
+/// of type `Fn(HintValue<'_>) -> Option<String>` implement this trait.
+pub trait HintHandler: Send + Sync {
+    /// Formats `value`, or returns `None` to fall back to the default formatting of the value.
+    fn format(&self, value: HintValue<'_>) -> Option<String>;
+}
+
+impl<F> HintHandler for F
+where
+    F: Fn(HintValue<'_>) -> Option<String> + Send + Sync,
+{
+    fn format(&self, value: HintValue<'_>) -> Option<String> {
+        self(value)
+    }
+}
+
+/// A [`HintHandler`] that displays integers as names, e.g. the states of a register field or the
+/// variants of an error code.
+///
+/// Values that have no name are displayed as numbers. `bool`s are looked up as `0` and `1`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValueNames {
+    names: BTreeMap<i128, String>,
+}
+
+impl ValueNames {
+    pub fn new(names: impl IntoIterator<Item = (i128, String)>) -> Self {
+        Self {
+            names: names.into_iter().collect(),
+        }
+    }
+
+    /// Returns the name of `value`, if it has one.
+    pub fn get(&self, value: i128) -> Option<&str> {
+        self.names.get(&value).map(String::as_str)
+    }
+}
+
+impl FromIterator<(i128, String)> for ValueNames {
+    fn from_iter<I: IntoIterator<Item = (i128, String)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl HintHandler for ValueNames {
+    fn format(&self, value: HintValue<'_>) -> Option<String> {
+        let value = match value {
+            HintValue::Bool(value) => i128::from(value),
+            HintValue::Signed(value) => value,
+            HintValue::Unsigned(value) => i128::try_from(value).ok()?,
+            _ => return None,
+        };
+        self.get(value).map(str::to_string)
+    }
+}
+
+/// The handlers registered for user-defined display hints, by name.
+#[derive(Clone, Default)]
+pub(crate) struct Hints(HashMap<String, Arc<dyn HintHandler>>);
+
+impl Hints {
+    pub(crate) fn insert(&mut self, name: String, handler: Arc<dyn HintHandler>) {
+        self.0.insert(name, handler);
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&dyn HintHandler> {
+        self.0.get(name).map(|handler| &**handler)
+    }
+}
+
+impl fmt::Debug for Hints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+// handlers can't be compared, so two sets of hints are only equal if they share their handlers
+impl PartialEq for Hints {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().all(|(name, handler)| {
+                other
+                    .0
+                    .get(name)
+                    .is_some_and(|other| Arc::ptr_eq(handler, other))
+            })
+    }
+}
+
+impl Eq for Hints {}
@@ -15,30 +15,46 @@ pub const DEFMT_VERSIONS: &[&str] = &["3", "4"];
 #[deprecated = "Please use DEFMT_VERSIONS instead"]
 pub const DEFMT_VERSION: &str = DEFMT_VERSIONS[1];
 
+/// How the build ID handshake, which has the reserved index 0, is displayed.
+const BUILD_ID_FORMAT: &str = "build ID {=[u8]:x}";
+
+mod clock;
 mod decoder;
 mod elf2table;
 mod frame;
+mod hints;
 pub mod log;
+mod metrics;
+mod span;
 mod stream;
+#[cfg(feature = "tracing")]
+pub mod tracing;
 
 use std::{
     collections::{BTreeMap, HashMap},
     error::Error,
-    fmt, io,
+    fmt, io, ops,
     str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
 
-use byteorder::{ReadBytesExt, LE};
 use decoder::Decoder;
-use defmt_parser::Level;
+use defmt_parser::{DisplayHint, Fragment, ParserMode, TimePrecision};
 use elf2table::parse_impl;
+use hints::Hints;
 
+pub use clock::{ClockEstimate, ClockSync};
+pub use defmt_parser::Level;
 pub use elf2table::{Location, Locations};
-pub use frame::Frame;
+pub use frame::{Frame, SpanEvent};
+pub use hints::{HintHandler, HintValue, ValueNames};
+pub use metrics::{MetricKind, MetricSample, Metrics, Series};
+pub use span::{ChromeTrace, CompletedSpan, SpanTracker};
 pub use stream::StreamDecoder;
 
 /// Specifies the origin of a format string
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Tag {
     /// Defmt-controlled format string for primitive types.
     Prim,
@@ -57,6 +73,16 @@ pub enum Tag {
     BitflagsValue,
     /// Format string created by `defmt::println!`.
     Println,
+    /// Format string of a `defmt::span!`, sent when the span is entered.
+    SpanEnter,
+    /// Format string of a `defmt::span!`, sent when the span is exited.
+    SpanExit,
+    /// Name of a metric updated by `defmt::counter!`.
+    Counter,
+    /// Name of a metric updated by `defmt::gauge!`.
+    Gauge,
+    /// Name of a metric updated by `defmt::histogram!`.
+    Histogram,
 
     Trace,
     Debug,
@@ -66,7 +92,32 @@ pub enum Tag {
 }
 
 impl Tag {
-    fn to_level(&self) -> Option<Level> {
+    /// Returns the tag for the `tag` field of a defmt symbol, e.g. `Tag::Info` for `defmt_info`.
+    pub fn from_symbol_tag(tag: &str) -> Option<Tag> {
+        Some(match tag {
+            "defmt_prim" => Tag::Prim,
+            "defmt_derived" => Tag::Derived,
+            "defmt_bitflags" => Tag::Bitflags,
+            "defmt_write" => Tag::Write,
+            "defmt_timestamp" => Tag::Timestamp,
+            "defmt_bitflags_value" => Tag::BitflagsValue,
+            "defmt_str" => Tag::Str,
+            "defmt_println" => Tag::Println,
+            "defmt_span_enter" => Tag::SpanEnter,
+            "defmt_span_exit" => Tag::SpanExit,
+            "defmt_counter" => Tag::Counter,
+            "defmt_gauge" => Tag::Gauge,
+            "defmt_histogram" => Tag::Histogram,
+            "defmt_trace" => Tag::Trace,
+            "defmt_debug" => Tag::Debug,
+            "defmt_info" => Tag::Info,
+            "defmt_warn" => Tag::Warn,
+            "defmt_error" => Tag::Error,
+            _ => return None,
+        })
+    }
+
+    fn to_level(self) -> Option<Level> {
         match self {
             Tag::Trace => Some(Level::Trace),
             Tag::Debug => Some(Level::Debug),
@@ -76,6 +127,15 @@ impl Tag {
             _ => None,
         }
     }
+
+    fn to_metric_kind(self) -> Option<MetricKind> {
+        match self {
+            Tag::Counter => Some(MetricKind::Counter),
+            Tag::Gauge => Some(MetricKind::Gauge),
+            Tag::Histogram => Some(MetricKind::Histogram),
+            _ => None,
+        }
+    }
 }
 
 /// Entry in [`Table`] combining a format string with its raw symbol
@@ -97,6 +157,14 @@ impl TableEntry {
             raw_symbol: "<unknown>".to_string(),
         }
     }
+
+    pub fn string(&self) -> &StringEntry {
+        &self.string
+    }
+
+    pub fn raw_symbol(&self) -> &str {
+        &self.raw_symbol
+    }
 }
 
 /// A format string and it's [`Tag`]
@@ -110,6 +178,19 @@ impl StringEntry {
     pub fn new(tag: Tag, string: String) -> Self {
         Self { tag, string }
     }
+
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    pub fn string(&self) -> &str {
+        &self.string
+    }
+
+    /// Returns the log level, if this is the format string of a logging statement.
+    pub fn level(&self) -> Option<Level> {
+        self.tag.to_level()
+    }
 }
 
 /// Data that uniquely identifies a `defmt::bitflags!` invocation.
@@ -148,6 +229,15 @@ impl Encoding {
             Encoding::Rzcobs => true,
         }
     }
+
+    /// Returns how many bytes a frame of `size` bytes takes up on the wire, at most.
+    pub const fn max_encoded_size(&self, size: usize) -> usize {
+        match self {
+            Encoding::Raw => size,
+            // one extra byte for every run of 134 non-zero bytes, plus the frame separator
+            Encoding::Rzcobs => size + size.div_ceil(134) + 1,
+        }
+    }
 }
 
 /// Internal table that holds log levels and maps format strings to indices
@@ -157,6 +247,18 @@ pub struct Table {
     entries: BTreeMap<usize, TableEntry>,
     bitflags: HashMap<BitflagsKey, Vec<(String, u128)>>,
     encoding: Encoding,
+    hints: Hints,
+    hexdump_multiline: bool,
+    varint: bool,
+    /// Whether the indices of interned strings are LEB128-encoded, because they don't fit into
+    /// 16 bits.
+    wide_indices: bool,
+    /// Whether timestamps are sent as deltas to the previous frame, with occasional keyframes.
+    timestamp_delta: bool,
+    /// Whether the indices of interned strings are hashes of their contents, sent as a `u32`.
+    stable_ids: bool,
+    /// The GNU build ID of the ELF file, which the firmware sends with the `build-id` feature.
+    build_id: Option<Vec<u8>>,
 }
 
 impl Table {
@@ -174,10 +276,131 @@ impl Table {
         parse_impl(elf, false)
     }
 
+    /// Creates a table from a list of entries, which are assigned consecutive indices starting at 0.
+    ///
+    /// Useful to test tools built on top of the decoder without having to build an ELF file.
+    pub fn new(entries: impl IntoIterator<Item = TableEntry>, encoding: Encoding) -> Table {
+        Table {
+            timestamp: None,
+            entries: entries.into_iter().enumerate().collect(),
+            bitflags: HashMap::new(),
+            encoding,
+            hints: Hints::default(),
+            hexdump_multiline: true,
+            varint: false,
+            wide_indices: false,
+            timestamp_delta: false,
+            stable_ids: false,
+            build_id: None,
+        }
+    }
+
+    /// Adds an entry at `index`, replacing the entry that was there before.
+    pub fn insert(&mut self, index: usize, entry: TableEntry) {
+        self.entries.insert(index, entry);
+    }
+
+    /// Registers `handler` for the user-defined display hint `:@name`, replacing the handler that
+    /// was registered for it before.
+    ///
+    /// The handler is also used for display hints this decoder doesn't know, e.g. `:name`.
+    pub fn register_hint(&mut self, name: impl Into<String>, handler: impl HintHandler + 'static) {
+        self.hints.insert(name.into(), Arc::new(handler));
+    }
+
+    /// Sets whether parameters with the `:hexdump` display hint are displayed as a multi-line dump
+    /// (the default), or as a plain byte array, e.g. for machine-readable output.
+    pub fn set_hexdump_multiline(&mut self, multiline: bool) {
+        self.hexdump_multiline = multiline;
+    }
+
+    /// Sets whether integers, lengths and string indices are LEB128-encoded, like the target does
+    /// with the `varint` feature of `defmt`.
+    ///
+    /// Tables parsed from an ELF file already know this.
+    pub fn set_varint(&mut self, varint: bool) {
+        self.varint = varint;
+    }
+
+    /// Returns whether integers, lengths and string indices are LEB128-encoded.
+    pub fn varint(&self) -> bool {
+        self.varint
+    }
+
+    /// Sets whether the indices of interned strings are LEB128-encoded, like the target does when
+    /// its `.defmt` section has too many strings for 16-bit indices.
+    ///
+    /// Tables parsed from an ELF file already know this.
+    pub fn set_wide_indices(&mut self, wide_indices: bool) {
+        self.wide_indices = wide_indices;
+    }
+
+    /// Returns whether the indices of interned strings are LEB128-encoded.
+    pub fn wide_indices(&self) -> bool {
+        self.wide_indices
+    }
+
+    /// Sets whether timestamps are sent as deltas to the previous frame, like the target does with
+    /// the `timestamp-delta` feature of `defmt`.
+    ///
+    /// Tables parsed from an ELF file already know this.
+    pub fn set_timestamp_delta(&mut self, timestamp_delta: bool) {
+        self.timestamp_delta = timestamp_delta;
+    }
+
+    /// Sets whether the indices of interned strings are hashes sent as a `u32`, like the target does
+    /// with the `stable-ids` feature of `defmt`.
+    ///
+    /// Tables parsed from an ELF file already know this.
+    pub fn set_stable_ids(&mut self, stable_ids: bool) {
+        self.stable_ids = stable_ids;
+    }
+
+    /// Returns whether the indices of interned strings are hashes of their contents.
+    pub fn stable_ids(&self) -> bool {
+        self.stable_ids
+    }
+
+    /// Sets the GNU build ID that the handshake of the target is checked against.
+    ///
+    /// Tables parsed from an ELF file already know this.
+    pub fn set_build_id(&mut self, build_id: Option<Vec<u8>>) {
+        self.build_id = build_id;
+    }
+
+    /// Returns the GNU build ID of the ELF file the table was parsed from, if it has one.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.build_id.as_deref()
+    }
+
     pub fn set_timestamp_entry(&mut self, timestamp: TableEntry) {
         self.timestamp = Some(timestamp);
     }
 
+    /// Returns the duration of one timestamp tick, if the timestamp format declares a time unit.
+    ///
+    /// This is the case for timestamps formatted with the `:us`, `:iso8601ms` or `:iso8601s`
+    /// display hints.
+    pub fn timestamp_tick_duration(&self) -> Option<Duration> {
+        let format = &self.timestamp.as_ref()?.string.string;
+        let fragments = defmt_parser::parse(format, ParserMode::ForwardsCompatible).ok()?;
+        let mut params = fragments.iter().filter_map(|fragment| match fragment {
+            Fragment::Parameter(param) => Some(param),
+            Fragment::Literal(_) => None,
+        });
+
+        let duration = match params.next()?.hint.as_ref()? {
+            DisplayHint::Microseconds => Duration::from_micros(1),
+            DisplayHint::ISO8601(TimePrecision::Millis) => Duration::from_millis(1),
+            DisplayHint::ISO8601(TimePrecision::Seconds) => Duration::from_secs(1),
+            _ => return None,
+        };
+        match params.next() {
+            None => Some(duration),
+            Some(_) => None,
+        }
+    }
+
     fn _get(&self, index: usize) -> Result<(Option<Level>, &str), ()> {
         let entry = self.entries.get(&index).ok_or(())?;
         Ok((entry.string.tag.to_level(), &entry.string.string))
@@ -196,9 +419,22 @@ impl Table {
         }
     }
 
+    /// Whether the string at `index` was interned by `#[derive(Format)]`.
+    fn is_derived(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Derived)
+    }
+
     pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
         self.entries.iter().filter_map(move |(idx, entry)| {
-            if entry.string.tag.to_level().is_some() || entry.string.tag == Tag::Println {
+            if entry.string.tag.to_level().is_some()
+                || entry.string.tag.to_metric_kind().is_some()
+                || matches!(
+                    entry.string.tag,
+                    Tag::Println | Tag::SpanEnter | Tag::SpanExit
+                )
+            {
                 Some(*idx)
             } else {
                 None
@@ -210,6 +446,55 @@ impl Table {
         self.entries.is_empty()
     }
 
+    /// Iterates over all table entries and their indices, in index order.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &TableEntry)> + '_ {
+        self.entries.iter().map(|(idx, entry)| (*idx, entry))
+    }
+
+    /// Returns the entry describing the format of the timestamp, if there is one.
+    pub fn timestamp_entry(&self) -> Option<&TableEntry> {
+        self.timestamp.as_ref()
+    }
+
+    /// Returns how many bytes a frame produced by the log statement at `index` can take up,
+    /// before encoding (see [`Encoding::max_encoded_size`]).
+    ///
+    /// Returns `None` if `index` is not in the table.
+    pub fn max_frame_size(&self, index: usize) -> Option<MaxFrameSize> {
+        let (_, format) = self.get_with_level(index).ok()?;
+
+        // the index of the format string, and the span ID
+        let mut size = decoder::max_format_size("{=istr}", self).ok()?;
+        let span_id = decoder::max_format_size("{=u32}", self).ok()?;
+        if let Some(entry) = &self.timestamp {
+            size += match self.timestamp_delta {
+                // a keyframe: a zero byte and a LEB128-encoded u64
+                true => MaxFrameSize {
+                    fixed: 1 + 10,
+                    unbounded: false,
+                },
+                false => decoder::max_format_size(&entry.string.string, self).ok()?,
+            };
+        }
+        let tag = self.entries[&index].string.tag;
+        match tag {
+            // the span ID, followed by the arguments
+            Tag::SpanEnter => size += span_id,
+            // only the span ID
+            Tag::SpanExit => {
+                size += span_id;
+                return Some(size);
+            }
+            _ => {}
+        }
+        // the format string of a metric is its name, followed by a value of a fixed type
+        let format = tag
+            .to_metric_kind()
+            .map_or(format, |kind| kind.value_format());
+        size += decoder::max_format_size(format, self).ok()?;
+        Some(size)
+    }
+
     /// Iterates over the raw symbols of the table entries
     pub fn raw_symbols(&self) -> impl Iterator<Item = &str> + '_ {
         self.entries.values().map(|s| &*s.raw_symbol)
@@ -226,26 +511,61 @@ impl Table {
     ///   * contains the [log string index, timestamp, optional fmt string args]
     pub fn decode<'t>(
         &'t self,
-        mut bytes: &[u8],
+        bytes: &[u8],
     ) -> Result<(Frame<'t>, /* consumed: */ usize), DecodeError> {
         let len = bytes.len();
-        let index = bytes.read_u16::<LE>()? as u64;
-
         let mut decoder = Decoder::new(self, bytes);
+        let index = decoder.read_index()? as u64;
+
+        // the reserved index 0 marks the build ID handshake, which has no timestamp; tables that
+        // weren't parsed from an ELF file may use it for a string
+        if index == 0 && !self.entries.contains_key(&0) {
+            let args = decoder.decode_format(BUILD_ID_FORMAT)?;
+            let frame = Frame::new(self, None, index, None, Vec::new(), BUILD_ID_FORMAT, args);
+            let consumed = len - decoder.bytes.len();
+            return Ok((frame, consumed));
+        }
 
         let mut timestamp_format = None;
         let mut timestamp_args = Vec::new();
+        let mut timestamp_delta = None;
         if let Some(entry) = self.timestamp.as_ref() {
             let format = &entry.string.string;
-            timestamp_format = Some(&**format);
-            timestamp_args = decoder.decode_format(format)?;
+            if !self.timestamp_delta {
+                timestamp_format = Some(&**format);
+                timestamp_args = decoder.decode_format(format)?;
+            } else {
+                // a keyframe starts with a zero byte, deltas are sent incremented by one
+                match decoder.read_leb128(8)? {
+                    0 => {
+                        timestamp_format = Some(&**format);
+                        timestamp_args = vec![Arg::Uxx(decoder.read_leb128(8)?)];
+                    }
+                    delta => timestamp_delta = Some(delta as u64 - 1),
+                }
+            }
         }
 
         let (level, format) = self
             .get_with_level(index as usize)
             .map_err(|_| DecodeError::Malformed)?;
+        let tag = self.entries[&(index as usize)].string.tag;
 
-        let args = decoder.decode_format(format)?;
+        // span frames carry the span ID between the timestamp and the arguments
+        let span = match tag {
+            Tag::SpanEnter => Some(SpanEvent::Enter(decoder.read_uint(4)? as u32)),
+            Tag::SpanExit => Some(SpanEvent::Exit(decoder.read_uint(4)? as u32)),
+            _ => None,
+        };
+
+        // the arguments of a span are only sent when entering it
+        let metric = tag.to_metric_kind();
+        let args = match (span, metric) {
+            (Some(SpanEvent::Exit(_)), _) => Vec::new(),
+            // the format string of a metric is its name, the value has a fixed type
+            (_, Some(kind)) => decoder.decode_format(kind.value_format())?,
+            _ => decoder.decode_format(format)?,
+        };
 
         let frame = Frame::new(
             self,
@@ -255,12 +575,27 @@ impl Table {
             timestamp_args,
             format,
             args,
-        );
+        )
+        .with_span(span)
+        .with_metric(metric)
+        .with_timestamp_delta(timestamp_delta);
 
         let consumed = len - decoder.bytes.len();
         Ok((frame, consumed))
     }
 
+    /// Checks that the build ID the firmware sent in `frame`, if it's the handshake, is the one of
+    /// the ELF file.
+    pub(crate) fn check_build_id(&self, frame: &Frame) -> Result<(), DecodeError> {
+        match (frame.build_id(), self.build_id()) {
+            (Some(firmware), Some(elf)) if firmware != elf => Err(DecodeError::BuildIdMismatch {
+                firmware: firmware.to_vec(),
+                elf: elf.to_vec(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     pub fn new_stream_decoder(&self) -> Box<dyn StreamDecoder + '_> {
         match self.encoding {
             Encoding::Raw => Box::new(stream::Raw::new(self)),
@@ -278,11 +613,13 @@ impl Table {
 enum Arg<'t> {
     /// Bool
     Bool(bool),
+    F16(half::f16),
+    Bf16(half::bf16),
     F32(f32),
     F64(f64),
-    /// U8, U16, U32, U64, U128
+    /// U8, U16, U24, U32, U64, U128
     Uxx(u128),
-    /// I8, I16, I32, I64, I128
+    /// I8, I16, I24, I32, I64, I128
     Ixx(i128),
     /// Str
     Str(String),
@@ -296,6 +633,10 @@ enum Arg<'t> {
     FormatSlice {
         elements: Vec<FormatSliceElement<'t>>,
     },
+    /// Key-value pairs
+    FormatMap {
+        entries: Vec<(FormatSliceElement<'t>, FormatSliceElement<'t>)>,
+    },
     FormatSequence {
         args: Vec<Arg<'t>>,
     },
@@ -316,12 +657,45 @@ struct FormatSliceElement<'t> {
     args: Vec<Arg<'t>>,
 }
 
+/// Upper bound of the size of a frame, as far as it can be determined from its format string.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaxFrameSize {
+    /// Number of bytes taken up by the fixed-size parts of the frame.
+    pub fixed: usize,
+    /// Whether the frame contains data of variable length, like strings, slices or nested
+    /// `Format` values.
+    pub unbounded: bool,
+}
+
+impl ops::AddAssign for MaxFrameSize {
+    fn add_assign(&mut self, rhs: Self) {
+        self.fixed += rhs.fixed;
+        self.unbounded |= rhs.unbounded;
+    }
+}
+
+impl fmt::Display for MaxFrameSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unbounded {
+            true => write!(f, "{}+", self.fixed),
+            false => write!(f, "{}", self.fixed),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum DecodeError {
     /// More data is needed to decode the next frame.
     UnexpectedEof,
 
     Malformed,
+
+    /// The firmware sent a different build ID than the one of the ELF file, so the frames can't be
+    /// decoded with this table.
+    BuildIdMismatch {
+        firmware: Vec<u8>,
+        elf: Vec<u8>,
+    },
 }
 
 impl From<io::Error> for DecodeError {
@@ -339,12 +713,22 @@ impl fmt::Display for DecodeError {
         match self {
             DecodeError::UnexpectedEof => f.write_str("unexpected end of stream"),
             DecodeError::Malformed => f.write_str("malformed data"),
+            DecodeError::BuildIdMismatch { firmware, elf } => write!(
+                f,
+                "the firmware has build ID {}, but the ELF file has build ID {}; decode with the ELF file of the running firmware",
+                hex(firmware),
+                hex(elf)
+            ),
         }
     }
 }
 
 impl Error for DecodeError {}
 
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,6 +738,13 @@ mod tests {
             timestamp: None,
             entries: entries.into_iter().enumerate().collect(),
             bitflags: Default::default(),
+            hints: Default::default(),
+            hexdump_multiline: true,
+            varint: false,
+            wide_indices: false,
+            timestamp_delta: false,
+            stable_ids: false,
+            build_id: None,
             encoding: Encoding::Raw,
         }
     }
@@ -369,6 +760,13 @@ mod tests {
             )),
             entries: entries.into_iter().enumerate().collect(),
             bitflags: Default::default(),
+            hints: Default::default(),
+            hexdump_multiline: true,
+            varint: false,
+            wide_indices: false,
+            timestamp_delta: false,
+            stable_ids: false,
+            build_id: None,
             encoding: Encoding::Raw,
         }
     }
@@ -392,6 +790,13 @@ mod tests {
                 "{=u8:us}".to_owned(),
             )),
             bitflags: Default::default(),
+            hints: Default::default(),
+            hexdump_multiline: true,
+            varint: false,
+            wide_indices: false,
+            timestamp_delta: false,
+            stable_ids: false,
+            build_id: None,
             encoding: Encoding::Raw,
         };
 
@@ -487,16 +892,16 @@ mod tests {
                     vec![],
                     FMT,
                     vec![
-                        Arg::Uxx(42),                      // u8
-                        Arg::Uxx(u16::max_value().into()), // u16
-                        Arg::Uxx(u32::max_value().into()), // u32
-                        Arg::Uxx(u64::max_value().into()), // u64
-                        Arg::Uxx(u128::max_value()),       // u128
-                        Arg::Ixx(-1),                      // i8
-                        Arg::Ixx(-1),                      // i16
-                        Arg::Ixx(-1),                      // i32
-                        Arg::Ixx(-1),                      // i64
-                        Arg::Ixx(-1),                      // i128
+                        Arg::Uxx(42),              // u8
+                        Arg::Uxx(u16::MAX.into()), // u16
+                        Arg::Uxx(u32::MAX.into()), // u32
+                        Arg::Uxx(u64::MAX.into()), // u64
+                        Arg::Uxx(u128::MAX),       // u128
+                        Arg::Ixx(-1),              // i8
+                        Arg::Ixx(-1),              // i16
+                        Arg::Ixx(-1),              // i32
+                        Arg::Ixx(-1),              // i64
+                        Arg::Ixx(-1),              // i128
                     ],
                 ),
                 bytes.len(),
@@ -600,7 +1005,7 @@ mod tests {
             TableEntry::new_without_symbol(Tag::Info, "{=__internal_FormatSequence}".to_owned()),
             TableEntry::new_without_symbol(Tag::Derived, "Foo".to_owned()),
             TableEntry::new_without_symbol(Tag::Derived, "Bar({=u8})".to_owned()),
-            TableEntry::new_without_symbol(Tag::Derived, "State {=u8}|".to_owned()),
+            TableEntry::new_without_symbol(Tag::Write, "State {=u8}|".to_owned()),
         ];
 
         let table = test_table(entries);
@@ -647,6 +1052,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn format_sequence_derived_enum() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "{=__internal_FormatSequence}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Derived, "Reset|Moved({=u8})".to_owned()),
+        ];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0, 0, // index
+            1, 0,  // index of the enum
+            1,  // discriminant of Moved
+            42, // Moved.0
+            0, 0, // terminator
+        ];
+
+        assert_eq!(
+            table.decode(&bytes),
+            Ok((
+                Frame::new(
+                    &table,
+                    Some(Level::Info),
+                    0,
+                    None,
+                    vec![],
+                    "{=__internal_FormatSequence}",
+                    vec![Arg::FormatSequence {
+                        args: vec![Arg::Format {
+                            format: "Moved({=u8})",
+                            args: vec![Arg::Uxx(42)]
+                        }]
+                    }],
+                ),
+                bytes.len(),
+            ))
+        );
+    }
+
     #[test]
     fn display() {
         let entries = vec![
@@ -688,6 +1132,252 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_24_bit_integers() {
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            0x56, 0x34, 0x12, // u24 0x123456
+            0xff, 0xff, 0xff, // i24 -1
+            0x00, 0x00, 0x80, // i24 -8388608
+        ];
+
+        decode_and_expect(
+            "{=u24:#x} {=i24} {1=i24:#x} {=i24}",
+            &bytes,
+            "0.000002 INFO 0x123456 -1 0xffffff -8388608",
+        );
+    }
+
+    #[test]
+    fn display_half_precision_floats() {
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            0x66, 0x2e, // f16 0.1
+            0x00, 0xc0, // f16 -2.0
+            0x00, 0x7c, // f16 infinity
+            0xcd, 0x3d, // bf16 0.1
+            0x49, 0x40, // bf16 3.140625
+        ];
+
+        decode_and_expect(
+            "{=f16} {=f16} {=f16} {=bf16} {=bf16} {0=f16:.3}",
+            &bytes,
+            "0.000002 INFO 0.1 -2.0 inf 0.1 3.14 0.100",
+        );
+    }
+
+    #[test]
+    fn display_format_spec() {
+        let bytes = [
+            0, 0,  // index
+            2,  // timestamp
+            42, // u8
+            0xfe, 0xff, 0xff, 0xff, // i32 -2
+            0x00, 0x00, 0xac, 0x41, // f32 21.5
+            5, 0, 0, 0, b'h', b'e', b'l', b'l', b'o', // str
+        ];
+
+        decode_and_expect(
+            "[{=u8:>4}] [{=i32:+}] [{=f32:.3}] [{=str:*^9}]",
+            &bytes,
+            "0.000002 INFO [  42] [-2] [21.500] [**hello**]",
+        );
+        decode_and_expect(
+            "[{=u8:<4x}] [{=i32:+05}] [{=f32:+08}] [{=str:.3}]",
+            &bytes,
+            "0.000002 INFO [2a  ] [-0002] [+00021.5] [hel]",
+        );
+        decode_and_expect(
+            "[{0=u8:+}] [{1=i32:6}] [{2=f32:>+8.1}] [{3=str:7?}]",
+            &bytes,
+            "0.000002 INFO [+42] [    -2] [   +21.5] [\"hello\"]",
+        );
+    }
+
+    #[test]
+    fn display_fixed_point() {
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            0x00, 0xc0, // i16 -0.5 in Q15
+            0x00, 0x80, 0x01, 0x00, // i32 1.5 in Q16.16
+            0x10, 0x27, // u16 10000
+        ];
+
+        decode_and_expect(
+            "{=i16:q15} {=i32:q16.16} {=u16:q15}",
+            &bytes,
+            "0.000002 INFO -0.5 1.5 0.30518",
+        );
+        decode_and_expect(
+            "{=i16:.3q15} {=i32:+q16} {=u16:>8.1q15} {2=u16:q0}",
+            &bytes,
+            "0.000002 INFO -0.500 +1.5      0.3 10000",
+        );
+        decode_and_expect(
+            "{0=i16:.0q15} {1=i32:.0q16} {2=u16:q2}",
+            &bytes,
+            "0.000002 INFO -1 2 2500",
+        );
+    }
+
+    #[test]
+    fn display_hexdump() {
+        let mut bytes = vec![
+            0, 0, // index
+            2, // timestamp
+            18, 0, 0, 0, // length of the slice
+        ];
+        bytes.extend_from_slice(b"Hello, world!\n\0\xff\x01\x02");
+        bytes.extend_from_slice(&[b'a', 0x7f, b' ']); // array
+
+        let expected = "0.000002 INFO rx:
+00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|
+00000010  01 02                                             |..|, array:
+00000000  61 7f 20                                          |a. |";
+        decode_and_expect(
+            "rx:{=[u8]:hexdump}, array:{=[u8; 3]:hexdump}",
+            &bytes,
+            expected,
+        );
+
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            0, 0, 0, 0, // length of the slice
+        ];
+        decode_and_expect("rx: {=[u8]:hexdump}", &bytes, "0.000002 INFO rx: []");
+    }
+
+    #[test]
+    fn display_hexdump_single_line() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "rx: {=[u8]:hexdump}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Info, "rx: {:hexdump}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=[?]}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=u8}".to_owned()),
+        ];
+        let mut table = test_table_with_timestamp(entries, "{=u8:us}");
+        table.set_hexdump_multiline(false);
+
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            2, 0, 0, 0, // length of the slice
+            23, 42, // slice content
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO rx: [23, 42]"
+        );
+
+        // `&[u8]` formatted with `{}`
+        let bytes = [
+            1, 0, // index
+            2, // timestamp
+            2, 0, // index of the slice
+            2, 0, 0, 0, // length of the slice
+            3, 0, // index of the elements
+            23, 42, // slice content
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO rx: [23, 42]"
+        );
+
+        table.set_hexdump_multiline(true);
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO rx: \n00000000  17 2a                                             |.*|",
+        );
+    }
+
+    #[test]
+    fn format_map() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "map: {=[?:?]:x}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=bool}".to_owned()),
+        ];
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            2, 0, 0, 0, // number of entries
+            1, 0, // index of the keys
+            2, 0, // index of the values
+            10, 1, // entry 0
+            11, 0, // entry 1
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO map: {a: true, b: false}"
+        );
+
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            0, 0, 0, 0, // number of entries
+            1, 0, // index of the keys
+            2, 0, // index of the values
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "0.000002 INFO map: {}");
+    }
+
+    #[test]
+    fn display_custom_hint() {
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Info,
+                "{=u8:@state} {=0..4:@state} {=i8:@state} {=str:@upper} {=u8:@none}".to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Info, "{:@state}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Derived, "S {{ x: {=u8} }}".to_owned()),
+        ];
+        let mut table = test_table_with_timestamp(entries, "{=u8:us}");
+        table.register_hint(
+            "state",
+            ValueNames::new([(1, "IDLE".to_string()), (-1, "ERROR".to_string())]),
+        );
+        table.register_hint("upper", |value: HintValue<'_>| match value {
+            HintValue::Str(s) => Some(s.to_uppercase()),
+            _ => None,
+        });
+
+        let bytes = [
+            0, 0,    // index
+            2,    // timestamp
+            1,    // u8
+            0xf1, // bitfield
+            0xff, // i8
+            2, 0, 0, 0, b'h', b'i', // str
+            1,    // u8, no handler
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO IDLE IDLE ERROR HI 1",
+        );
+
+        // values without a name fall back to the default formatting; the hint propagates
+        let bytes = [
+            1, 0, // index
+            2, // timestamp
+            2, 0, // index of the struct
+            3, // value
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "0.000002 INFO S { x: 3 }");
+    }
+
     #[test]
     fn display_use_inner_type_hint() {
         let entries = vec![
@@ -801,6 +1491,431 @@ mod tests {
         );
     }
 
+    #[test]
+    fn timestamp_ticks() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello".to_owned(),
+        )];
+
+        let table = test_table_with_timestamp(entries, "{=u32:us}");
+        assert_eq!(
+            table.timestamp_tick_duration(),
+            Some(Duration::from_micros(1))
+        );
+        let bytes = [
+            0, 0, // index
+            0x40, 0x42, 0x0f, 0x00, // timestamp
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.timestamp_ticks(), Some(1_000_000));
+
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello".to_owned(),
+        )];
+        let table = test_table_with_timestamp(entries, "{=u8}/{=u8}");
+        assert_eq!(table.timestamp_tick_duration(), None);
+        let bytes = [
+            0, 0, // index
+            1, 2, // timestamp
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.timestamp_ticks(), None);
+    }
+
+    #[test]
+    fn spans() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::SpanEnter, "read {=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::SpanExit, "read {=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Info, "value".to_owned()),
+        ];
+        let table = test_table_with_timestamp(entries, "{=u32:us}");
+
+        let enter = [
+            0, 0, // index
+            10, 0, 0, 0, // timestamp
+            7, 0, 0, 0, // span ID
+            3, // argument
+        ];
+        let info = [
+            2, 0, // index
+            11, 0, 0, 0, // timestamp
+        ];
+        let exit = [
+            1, 0, // index
+            35, 0, 0, 0, // timestamp
+            7, 0, 0, 0, // span ID
+        ];
+
+        let (frame, consumed) = table.decode(&enter).unwrap();
+        assert_eq!(consumed, enter.len());
+        assert_eq!(frame.span(), Some(SpanEvent::Enter(7)));
+        assert_eq!(frame.display(false).to_string(), "0.000010 → read 3");
+
+        let (frame, consumed) = table.decode(&exit).unwrap();
+        assert_eq!(consumed, exit.len());
+        assert_eq!(frame.span(), Some(SpanEvent::Exit(7)));
+        assert_eq!(frame.display(false).to_string(), "0.000035 ← read");
+        assert!(frame.display_args().is_empty());
+
+        let mut tracker = SpanTracker::new();
+        assert_eq!(tracker.process(&table.decode(&enter).unwrap().0), None);
+        assert_eq!(tracker.process(&table.decode(&info).unwrap().0), None);
+        assert_eq!(tracker.open_spans(), 1);
+        let span = tracker.process(&table.decode(&exit).unwrap().0).unwrap();
+        assert_eq!(span.name, "read 3");
+        assert_eq!(span.args, ["3"]);
+        assert_eq!(
+            span.duration(Duration::from_micros(1)),
+            Some(Duration::from_micros(25))
+        );
+        assert_eq!(tracker.open_spans(), 0);
+
+        let mut trace = ChromeTrace::new(Vec::new(), table.timestamp_tick_duration()).unwrap();
+        for bytes in [&enter[..], &info, &exit] {
+            trace.add(&table.decode(bytes).unwrap().0).unwrap();
+        }
+        let trace = trace.finish().unwrap();
+        let events: serde_json::Value = serde_json::from_slice(&trace).unwrap();
+        assert_eq!(
+            events,
+            serde_json::json!([
+                {"name": "value", "cat": "info", "ph": "i", "s": "t", "ts": 11.0, "pid": 0, "tid": 0},
+                {"name": "read 3", "cat": "span", "ph": "X", "ts": 10.0, "dur": 25.0, "pid": 0, "tid": 0, "args": {"0": "3"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Counter, "rx_bytes".to_owned()),
+            TableEntry::new_without_symbol(Tag::Gauge, "temperature".to_owned()),
+            TableEntry::new_without_symbol(Tag::Histogram, "latency_us".to_owned()),
+        ];
+        let table = test_table(entries);
+        assert_eq!(table.max_frame_size(0).unwrap().fixed, 6);
+
+        let frames: [&[u8]; 5] = [
+            &[0, 0, 44, 1, 0, 0],            // rx_bytes += 300
+            &[0, 0, 12, 0, 0, 0],            // rx_bytes += 12
+            &[1, 0, 0x00, 0x00, 0xa8, 0x41], // temperature = 21.0
+            &[2, 0, 0x00, 0x00, 0x48, 0x41], // latency_us: 12.5
+            &[2, 0, 0x00, 0x40, 0x1c, 0x46], // latency_us: 10000.0
+        ];
+        let frame = table.decode(frames[0]).unwrap().0;
+        assert_eq!(
+            frame.metric(),
+            Some(MetricSample {
+                kind: MetricKind::Counter,
+                name: "rx_bytes",
+                value: 300.0
+            })
+        );
+        assert_eq!(frame.display(false).to_string(), "rx_bytes += 300");
+        assert_eq!(frame.display_args(), ["300"]);
+        let frame = table.decode(frames[3]).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "latency_us: 12.5");
+
+        let mut metrics = Metrics::with_buckets(vec![100.0, 10.0]);
+        for bytes in frames {
+            assert!(metrics.record(&table.decode(bytes).unwrap().0));
+        }
+
+        let mut csv = Vec::new();
+        metrics.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "name,kind,count,sum,min,max,last,value
+latency_us,histogram,2,10012.5,12.5,10000,10000,10012.5
+rx_bytes,counter,2,312,12,300,12,312
+temperature,gauge,1,21,21,21,21,21
+"
+        );
+
+        let mut prometheus = Vec::new();
+        metrics.write_prometheus(&mut prometheus).unwrap();
+        assert_eq!(
+            String::from_utf8(prometheus).unwrap(),
+            "# TYPE latency_us histogram
+latency_us_bucket{le=\"10\"} 0
+latency_us_bucket{le=\"100\"} 1
+latency_us_bucket{le=\"+Inf\"} 2
+latency_us_sum 10012.5
+latency_us_count 2
+# TYPE rx_bytes counter
+rx_bytes 312
+# TYPE temperature gauge
+temperature 21
+"
+        );
+    }
+
+    #[test]
+    fn display_args() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "x={1=u8:x} y={0=bool} x={1=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Derived, "Point {{ x: {=i8} }}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Warn, "{=?} at {=str}".to_owned()),
+        ];
+        let table = test_table(entries);
+
+        let bytes = [
+            0, 0,    // index
+            1,    // y
+            0x2a, // x
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.format(), "x={1=u8:x} y={0=bool} x={1=u8}");
+        assert_eq!(frame.display_args(), ["true", "2a"]);
+        assert_eq!(frame.numeric_args(), [Some(1.0), Some(42.0)]);
+
+        let bytes = [
+            2, 0, // index
+            1, 0,    // index of the `Format` argument
+            0xff, // x
+            2, 0, 0, 0, // length of the string
+            b'h', b'i',
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display_args(), ["Point { x: -1 }", "hi"]);
+        assert_eq!(frame.numeric_args(), [None, None]);
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "x={=u8} y={=i64} {0=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Warn, "{0=0..4} {0=12..16} {1=[u8; 3]}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Error, "{=str} {=istr}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=u8}".to_owned()),
+        ];
+        let table = test_table_with_timestamp(entries, "{=u32:us}");
+
+        let size = |index| table.max_frame_size(index).map(|size| size.to_string());
+        assert_eq!(size(0).as_deref(), Some("15"));
+        assert_eq!(size(1).as_deref(), Some("11"));
+        assert_eq!(size(2).as_deref(), Some("12+"));
+        assert_eq!(size(4), None);
+
+        assert_eq!(Encoding::Raw.max_encoded_size(15), 15);
+        assert_eq!(Encoding::Rzcobs.max_encoded_size(15), 17);
+        assert_eq!(Encoding::Rzcobs.max_encoded_size(135), 138);
+    }
+
+    #[test]
+    fn varint() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u8} y={=i64} {0=u8}".to_owned(),
+        )];
+        let mut table = test_table_with_timestamp(entries, "{=u32:us}");
+        table.set_varint(true);
+        table.insert(
+            200,
+            TableEntry::new_without_symbol(Tag::Info, "{=u16} {=i32} {=str}".to_owned()),
+        );
+
+        let bytes = [
+            0xc8, 0x01, // index
+            0xac, 0x02, // timestamp
+            1,    // u16
+            5,    // i32, zigzag-encoded
+            2, b'h', b'i', // str
+        ];
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.display(false).to_string(), "0.000300 INFO 1 -3 hi");
+
+        // 0x1ffff doesn't fit into the u16
+        let bytes = [0xc8, 0x01, 0xac, 0x02, 0xff, 0xff, 0x07, 5, 0];
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+
+        let size = |index| table.max_frame_size(index).map(|size| size.to_string());
+        assert_eq!(size(0).as_deref(), Some("19"));
+        assert_eq!(size(200).as_deref(), Some("21+"));
+    }
+
+    #[test]
+    fn wide_indices() {
+        let mut table = test_table_with_timestamp(vec![], "{=u32:us}");
+        table.set_wide_indices(true);
+        table.insert(
+            70_000,
+            TableEntry::new_without_symbol(Tag::Info, "{=istr} {=u16}".to_owned()),
+        );
+        table.insert(
+            200,
+            TableEntry::new_without_symbol(Tag::Str, "hello".to_owned()),
+        );
+
+        let bytes = [
+            0xf0, 0xa2, 0x04, // index 70000
+            1, 0, 0, 0, // timestamp
+            0xc8, 0x01, // index 200
+            3, 0, // u16
+        ];
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.index(), 70_000);
+        assert_eq!(frame.display(false).to_string(), "0.000001 INFO hello 3");
+
+        let size = table.max_frame_size(70_000).map(|size| size.to_string());
+        assert_eq!(size.as_deref(), Some("16"));
+    }
+
+    #[test]
+    fn stable_ids() {
+        let mut table = test_table_with_timestamp(vec![], "{=u32:us}");
+        table.set_stable_ids(true);
+        // the index stays a fixed-width `u32`, while the other integers are LEB128-encoded
+        table.set_varint(true);
+        table.insert(
+            0x43fc_a4de,
+            TableEntry::new_without_symbol(Tag::Info, "{=istr} {=u16}".to_owned()),
+        );
+        table.insert(
+            0x0000_0200,
+            TableEntry::new_without_symbol(Tag::Str, "hello".to_owned()),
+        );
+
+        let bytes = [
+            0xde, 0xa4, 0xfc, 0x43, // index
+            1,    // timestamp
+            0, 2, 0, 0, // index of "hello"
+            3, // u16
+        ];
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.index(), 0x43fc_a4de);
+        assert_eq!(frame.display(false).to_string(), "0.000001 INFO hello 3");
+
+        let size = table
+            .max_frame_size(0x43fc_a4de)
+            .map(|size| size.to_string());
+        assert_eq!(size.as_deref(), Some("16"));
+    }
+
+    #[test]
+    fn build_id() {
+        let mut table = test_table_with_timestamp(vec![], "{=u8}");
+        table.build_id = Some(vec![0xde, 0xfe, 0xc7]);
+
+        // the handshake has the reserved index 0 and no timestamp
+        let handshake = [0, 0, 3, 0, 0, 0, 0xde, 0xfe, 0xc7];
+        let mut stream = table.new_stream_decoder();
+        stream.received(&handshake);
+        let frame = stream.decode().unwrap();
+        assert_eq!(frame.build_id(), Some(&[0xde, 0xfe, 0xc7][..]));
+        assert_eq!(frame.display(false).to_string(), "build ID [de, fe, c7]");
+
+        stream.received(&[0, 0, 2, 0, 0, 0, 0xde, 0xad]);
+        let error = stream.decode().unwrap_err();
+        assert_eq!(
+            error,
+            DecodeError::BuildIdMismatch {
+                firmware: vec![0xde, 0xad],
+                elf: vec![0xde, 0xfe, 0xc7],
+            }
+        );
+        assert!(error
+            .to_string()
+            .contains("build ID dead, but the ELF file has build ID defec7"));
+    }
+
+    #[test]
+    fn build_id_between_delta_timestamps() {
+        let mut table = test_table_with_timestamp(vec![], "{=u64:us}");
+        table.insert(
+            1,
+            TableEntry::new_without_symbol(Tag::Info, "x={=u8}".to_owned()),
+        );
+        table.set_timestamp_delta(true);
+        table.build_id = Some(vec![0xde, 0xfe, 0xc7]);
+
+        let keyframe = [1, 0, 0x00, 0xe8, 0x07, 5]; // 1000
+        let handshake = [0, 0, 3, 0, 0, 0, 0xde, 0xfe, 0xc7]; // `defmt::send_build_id`
+        let delta = [1, 0, 0xf5, 0x03, 6]; // 500 + 1
+
+        let mut stream = table.new_stream_decoder();
+        stream.received(&keyframe);
+        stream.received(&handshake);
+        stream.received(&delta);
+        let mut decoded = vec![];
+        while let Ok(frame) = stream.decode() {
+            decoded.push(frame.display(false).to_string());
+        }
+        assert_eq!(
+            decoded,
+            [
+                "0.001000 INFO x=5",
+                "build ID [de, fe, c7]",
+                "0.001500 INFO x=6",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_id_mismatch_frame_size() {
+        let mut table = test_table_with_timestamp(vec![], "{=u8}");
+        table.encoding = Encoding::Rzcobs;
+        table.build_id = Some(vec![0xde, 0xfe, 0xc7]);
+
+        // [0, 0, 2, 0, 0, 0, 0xde, 0xad], rzCOBS-encoded
+        let handshake = [0x02, 0xde, 0x3b, 0xad, 0x7e, 0x00];
+        let mut stream = table.new_stream_decoder();
+        stream.received(&handshake);
+        assert!(matches!(
+            stream.decode(),
+            Err(DecodeError::BuildIdMismatch { .. })
+        ));
+        assert_eq!(stream.last_frame_size(), handshake.len());
+    }
+
+    #[test]
+    fn timestamp_delta() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u8}".to_owned(),
+        )];
+        let mut table = test_table_with_timestamp(entries, "{=u64:us}");
+        table.set_timestamp_delta(true);
+
+        let keyframe = [0, 0, 0x00, 0xe8, 0x07, 5]; // 1000
+        let delta = [0, 0, 0xf5, 0x03, 6]; // 500 + 1
+        let zero_delta = [0, 0, 0x01, 7]; // 0 + 1
+
+        let mut stream = table.new_stream_decoder();
+        stream.received(&keyframe);
+        stream.received(&delta);
+        stream.received(&zero_delta);
+        let mut decoded = vec![];
+        while let Ok(frame) = stream.decode() {
+            decoded.push(frame.display(false).to_string());
+        }
+        assert_eq!(
+            decoded,
+            [
+                "0.001000 INFO x=5",
+                "0.001500 INFO x=6",
+                "0.001500 INFO x=7",
+            ]
+        );
+
+        // without the previous frame, the delta can't be resolved
+        let (mut frame, consumed) = table.decode(&delta).unwrap();
+        assert_eq!(consumed, delta.len());
+        assert_eq!(frame.timestamp_delta(), Some(500));
+        frame.resolve_timestamp(&mut None);
+        assert_eq!(frame.timestamp_ticks(), None);
+        assert_eq!(frame.display(false).to_string(), "INFO x=6");
+
+        let size = table.max_frame_size(0).map(|size| size.to_string());
+        assert_eq!(size.as_deref(), Some("14"));
+    }
+
     #[test]
     fn bools_simple() {
         let bytes = [
@@ -1092,6 +2207,13 @@ mod tests {
                 "{=u8:us}".to_owned(),
             )),
             bitflags: Default::default(),
+            hints: Default::default(),
+            hexdump_multiline: true,
+            varint: false,
+            wide_indices: false,
+            timestamp_delta: false,
+            stable_ids: false,
+            build_id: None,
             encoding: Encoding::Raw,
         };
 
@@ -31,6 +31,19 @@ pub fn log_defmt(
         .map(|ts| ts.to_string())
         .unwrap_or_default();
 
+    log_defmt_with_timestamp(frame, timestamp, file, line, module_path)
+}
+
+/// Like [`log_defmt`], but displays `timestamp` instead of the timestamp sent by the target.
+///
+/// This can be used to show the timestamp in a different form, e.g. as host wall-clock time.
+pub fn log_defmt_with_timestamp(
+    frame: &Frame<'_>,
+    timestamp: String,
+    file: Option<&str>,
+    line: Option<u32>,
+    module_path: Option<&str>,
+) {
     let level = frame.level().map(|level| match level {
         crate::Level::Trace => Level::Trace,
         crate::Level::Debug => Level::Debug,
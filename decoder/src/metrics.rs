@@ -0,0 +1,240 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+};
+
+use crate::Frame;
+
+/// The kind of a metric, determined by the macro that updates it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    /// `defmt::counter!`; every sample is an increment.
+    Counter,
+    /// `defmt::gauge!`; every sample replaces the current value.
+    Gauge,
+    /// `defmt::histogram!`; every sample is an observation.
+    Histogram,
+}
+
+impl MetricKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+
+    /// The format of the value that follows the header of a metric frame.
+    pub(crate) fn value_format(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "{=u32}",
+            MetricKind::Gauge | MetricKind::Histogram => "{=f32}",
+        }
+    }
+}
+
+/// A single metric update, see [`Frame::metric`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetricSample<'t> {
+    pub kind: MetricKind,
+    pub name: &'t str,
+    pub value: f64,
+}
+
+impl fmt::Display for MetricSample<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            MetricKind::Counter => write!(f, "{} += {}", self.name, self.value),
+            MetricKind::Gauge => write!(f, "{} = {}", self.name, self.value),
+            MetricKind::Histogram => write!(f, "{}: {}", self.name, self.value),
+        }
+    }
+}
+
+/// The aggregated samples of one metric.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    pub kind: MetricKind,
+    /// Number of samples.
+    pub count: u64,
+    /// Sum of all samples; for counters, this is the current value.
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    /// The most recent sample; for gauges, this is the current value.
+    pub last: f64,
+    /// For histograms, the number of samples that fall into each bucket of [`Metrics::buckets`]
+    /// (not cumulative).
+    buckets: Vec<u64>,
+}
+
+impl Series {
+    /// The current value of the metric: the total of a counter, the last value of a gauge and the
+    /// sum of all observations of a histogram.
+    pub fn value(&self) -> f64 {
+        match self.kind {
+            MetricKind::Counter | MetricKind::Histogram => self.sum,
+            MetricKind::Gauge => self.last,
+        }
+    }
+
+    fn add(&mut self, value: f64, bounds: &[f64]) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.last = value;
+        if self.kind == MetricKind::Histogram {
+            if let Some(bucket) = bounds.iter().position(|bound| value <= *bound) {
+                self.buckets[bucket] += 1;
+            }
+        }
+    }
+}
+
+/// Aggregates the metric frames of a stream, see [`Frame::metric`].
+///
+/// Metrics are identified by their name. Samples whose kind doesn't match the first sample of the
+/// same name are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    buckets: Vec<f64>,
+    series: BTreeMap<String, Series>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Upper bounds of the histogram buckets used by [`Metrics::new`].
+    pub const DEFAULT_BUCKETS: &'static [f64] = &[
+        1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+    ];
+
+    pub fn new() -> Self {
+        Self::with_buckets(Self::DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Creates an aggregator that sorts histogram samples into buckets with the given upper
+    /// bounds. An implicit `+Inf` bucket is always added.
+    pub fn with_buckets(mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|bound| bound.is_finite());
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+        Self {
+            buckets,
+            series: BTreeMap::new(),
+        }
+    }
+
+    /// Upper bounds of the histogram buckets, without the implicit `+Inf` bucket.
+    pub fn buckets(&self) -> &[f64] {
+        &self.buckets
+    }
+
+    /// Adds the sample carried by `frame`. Returns whether `frame` was a metric frame.
+    pub fn record(&mut self, frame: &Frame) -> bool {
+        match frame.metric() {
+            Some(sample) => {
+                self.record_sample(sample);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn record_sample(&mut self, sample: MetricSample) {
+        let bucket_count = match sample.kind {
+            MetricKind::Histogram => self.buckets.len(),
+            _ => 0,
+        };
+        let series = self
+            .series
+            .entry(sample.name.to_string())
+            .or_insert_with(|| Series {
+                kind: sample.kind,
+                count: 0,
+                sum: 0.0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+                last: 0.0,
+                buckets: vec![0; bucket_count],
+            });
+        if series.kind == sample.kind {
+            series.add(sample.value, &self.buckets);
+        }
+    }
+
+    /// Iterates over all metrics and their aggregated samples, sorted by name.
+    pub fn series(&self) -> impl Iterator<Item = (&str, &Series)> + '_ {
+        self.series.iter().map(|(name, series)| (&**name, series))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Writes one line per metric, with the columns `name`, `kind`, `count`, `sum`, `min`, `max`,
+    /// `last` and `value`.
+    pub fn write_csv(&self, mut sink: impl Write) -> io::Result<()> {
+        writeln!(sink, "name,kind,count,sum,min,max,last,value")?;
+        for (name, series) in self.series() {
+            writeln!(
+                sink,
+                "{},{},{},{},{},{},{},{}",
+                name,
+                series.kind.as_str(),
+                series.count,
+                series.sum,
+                series.min,
+                series.max,
+                series.last,
+                series.value(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes all metrics in the Prometheus text exposition format.
+    pub fn write_prometheus(&self, mut sink: impl Write) -> io::Result<()> {
+        for (name, series) in self.series() {
+            writeln!(sink, "# TYPE {} {}", name, series.kind.as_str())?;
+            match series.kind {
+                MetricKind::Counter | MetricKind::Gauge => {
+                    writeln!(sink, "{} {}", name, PromValue(series.value()))?
+                }
+                MetricKind::Histogram => {
+                    let mut cumulative = 0;
+                    for (bound, count) in self.buckets.iter().zip(&series.buckets) {
+                        cumulative += count;
+                        let le = PromValue(*bound);
+                        writeln!(sink, "{name}_bucket{{le=\"{le}\"}} {cumulative}")?;
+                    }
+                    writeln!(sink, "{}_bucket{{le=\"+Inf\"}} {}", name, series.count)?;
+                    writeln!(sink, "{}_sum {}", name, PromValue(series.sum))?;
+                    writeln!(sink, "{}_count {}", name, series.count)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a float the way Prometheus expects it.
+struct PromValue(f64);
+
+impl fmt::Display for PromValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            value if value.is_nan() => f.write_str("NaN"),
+            f64::INFINITY => f.write_str("+Inf"),
+            f64::NEG_INFINITY => f.write_str("-Inf"),
+            value => write!(f, "{value}"),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{Frame, SpanEvent};
+
+/// A span of which both the enter and the exit frame have been seen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletedSpan {
+    pub id: u32,
+    /// Index of the `span!` invocation in the table.
+    pub index: u64,
+    /// The formatted message of the enter frame.
+    pub name: String,
+    /// The formatted arguments of the enter frame.
+    pub args: Vec<String>,
+    /// Raw timestamp of the enter frame; see [`Frame::timestamp_ticks`].
+    pub start: Option<u64>,
+    /// Raw timestamp of the exit frame; see [`Frame::timestamp_ticks`].
+    pub end: Option<u64>,
+    /// Number of spans that were open when this one was entered.
+    pub depth: usize,
+}
+
+impl CompletedSpan {
+    /// Length of the span in timestamp ticks, if both frames carry a timestamp.
+    pub fn ticks(&self) -> Option<u64> {
+        self.end?.checked_sub(self.start?)
+    }
+
+    /// Length of the span, given the length of one timestamp tick.
+    ///
+    /// See [`Table::timestamp_tick_duration`](crate::Table::timestamp_tick_duration).
+    pub fn duration(&self, tick: Duration) -> Option<Duration> {
+        let nanos = tick.as_nanos() * u128::from(self.ticks()?);
+        Some(Duration::from_nanos(u64::try_from(nanos).ok()?))
+    }
+}
+
+/// Pairs the enter and exit frames of spans.
+#[derive(Debug, Default)]
+pub struct SpanTracker {
+    open: HashMap<u32, CompletedSpan>,
+}
+
+impl SpanTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes a frame and returns the span it completes, if any.
+    ///
+    /// Exit frames of spans that were entered before the tracker was created are ignored.
+    pub fn process(&mut self, frame: &Frame) -> Option<CompletedSpan> {
+        match frame.span()? {
+            SpanEvent::Enter(id) => {
+                let span = CompletedSpan {
+                    id,
+                    index: frame.index(),
+                    name: frame.message(),
+                    args: frame.display_args(),
+                    start: frame.timestamp_ticks(),
+                    end: None,
+                    depth: self.open.len(),
+                };
+                self.open.insert(id, span);
+                None
+            }
+            SpanEvent::Exit(id) => {
+                let mut span = self.open.remove(&id)?;
+                span.end = frame.timestamp_ticks();
+                Some(span)
+            }
+        }
+    }
+
+    /// Number of spans that were entered but not exited yet.
+    pub fn open_spans(&self) -> usize {
+        self.open.len()
+    }
+}
+
+/// Writes frames in the Chrome trace event format, which can be loaded into [Perfetto].
+///
+/// Completed spans become "complete" events and all other frames become "instant" events.
+///
+/// [Perfetto]: https://ui.perfetto.dev
+pub struct ChromeTrace<W: Write> {
+    sink: W,
+    tick: Duration,
+    tracker: SpanTracker,
+    is_first: bool,
+}
+
+impl<W: Write> ChromeTrace<W> {
+    /// Starts a new trace.
+    ///
+    /// `tick` is the length of one timestamp tick; if it's unknown, ticks are treated as
+    /// microseconds.
+    pub fn new(mut sink: W, tick: Option<Duration>) -> io::Result<Self> {
+        sink.write_all(b"[\n")?;
+        Ok(Self {
+            sink,
+            tick: tick.unwrap_or(Duration::from_micros(1)),
+            tracker: SpanTracker::new(),
+            is_first: true,
+        })
+    }
+
+    /// Adds a frame to the trace and returns the span it completes, if any.
+    pub fn add(&mut self, frame: &Frame) -> io::Result<Option<CompletedSpan>> {
+        if frame.span().is_none() {
+            let ts = self.micros(frame.timestamp_ticks().unwrap_or(0));
+            let level = frame.level().map_or("println", |level| level.as_str());
+            self.write_event(json!({
+                "name": frame.message(),
+                "cat": level,
+                "ph": "i",
+                "s": "t",
+                "ts": ts,
+                "pid": 0,
+                "tid": 0,
+            }))?;
+            return Ok(None);
+        }
+
+        let span = match self.tracker.process(frame) {
+            Some(span) => span,
+            None => return Ok(None),
+        };
+        let args = span
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| (i.to_string(), json!(arg)))
+            .collect::<serde_json::Map<_, _>>();
+        let start = span.start.unwrap_or(0);
+        self.write_event(json!({
+            "name": span.name,
+            "cat": "span",
+            "ph": "X",
+            "ts": self.micros(start),
+            "dur": self.micros(span.ticks().unwrap_or(0)),
+            "pid": 0,
+            "tid": 0,
+            "args": args,
+        }))?;
+        Ok(Some(span))
+    }
+
+    /// Flushes the underlying writer.
+    ///
+    /// A trace that is missing its end can still be loaded, so flushing regularly makes sure the
+    /// trace is usable even if the program is terminated.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    /// Terminates the trace and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.sink.write_all(b"\n]\n")?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+
+    fn micros(&self, ticks: u64) -> f64 {
+        self.tick.as_secs_f64() * 1e6 * ticks as f64
+    }
+
+    fn write_event(&mut self, event: serde_json::Value) -> io::Result<()> {
+        if !self.is_first {
+            self.sink.write_all(b",\n")?;
+        }
+        self.is_first = false;
+        serde_json::to_writer(&mut self.sink, &event)?;
+        Ok(())
+    }
+}
@@ -12,4 +12,12 @@ pub trait StreamDecoder {
     fn received(&mut self, data: &[u8]);
 
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError>;
+
+    /// Returns the number of bytes the last successfully decoded frame took up in the stream,
+    /// including any framing overhead of the encoding.
+    ///
+    /// Decoders which don't keep track of this return `0`.
+    fn last_frame_size(&self) -> usize {
+        0
+    }
 }
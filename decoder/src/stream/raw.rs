@@ -4,6 +4,9 @@ use crate::{DecodeError, Frame, Table};
 pub struct Raw<'a> {
     table: &'a Table,
     data: Vec<u8>,
+    last_frame_size: usize,
+    /// The timestamp of the previous frame, to resolve delta timestamps.
+    last_timestamp: Option<u64>,
 }
 
 impl<'a> Raw<'a> {
@@ -11,6 +14,8 @@ impl<'a> Raw<'a> {
         Self {
             table,
             data: Vec::new(),
+            last_frame_size: 0,
+            last_timestamp: None,
         }
     }
 }
@@ -22,11 +27,22 @@ impl<'a> StreamDecoder for Raw<'a> {
 
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
         match self.table.decode(&self.data) {
-            Ok((frame, consumed)) => {
+            Ok((mut frame, consumed)) => {
                 self.data.drain(0..consumed);
+                self.last_frame_size = consumed;
+                frame.resolve_timestamp(&mut self.last_timestamp);
+                self.table.check_build_id(&frame)?;
                 Ok(frame)
             }
+            Err(DecodeError::Malformed) => {
+                self.last_timestamp = None;
+                Err(DecodeError::Malformed)
+            }
             Err(e) => Err(e),
         }
     }
+
+    fn last_frame_size(&self) -> usize {
+        self.last_frame_size
+    }
 }
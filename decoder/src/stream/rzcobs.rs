@@ -42,6 +42,9 @@ fn rzcobs_decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
 pub struct Rzcobs<'a> {
     table: &'a Table,
     raw: Vec<u8>,
+    last_frame_size: usize,
+    /// The timestamp of the previous frame, to resolve delta timestamps.
+    last_timestamp: Option<u64>,
 }
 
 impl<'a> Rzcobs<'a> {
@@ -49,6 +52,8 @@ impl<'a> Rzcobs<'a> {
         Self {
             table,
             raw: Vec::new(),
+            last_frame_size: 0,
+            last_timestamp: None,
         }
     }
 }
@@ -85,11 +90,28 @@ impl<'a> StreamDecoder for Rzcobs<'a> {
 
         assert!(self.raw.is_empty() || self.raw[0] != 0);
 
-        let frame: Vec<u8> = frame?;
-        match self.table.decode(&frame) {
+        // delta timestamps can't be resolved until the next keyframe if a frame is lost
+        let frame = frame.and_then(|frame| match self.table.decode(&frame) {
             Ok((frame, _consumed)) => Ok(frame),
             Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
-            Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
+            Err(e) => Err(e),
+        });
+        match frame {
+            Ok(mut frame) => {
+                // encoded frame + separator
+                self.last_frame_size = zero + 1;
+                frame.resolve_timestamp(&mut self.last_timestamp);
+                self.table.check_build_id(&frame)?;
+                Ok(frame)
+            }
+            Err(e) => {
+                self.last_timestamp = None;
+                Err(e)
+            }
         }
     }
+
+    fn last_frame_size(&self) -> usize {
+        self.last_frame_size
+    }
 }
@@ -0,0 +1,174 @@
+//! This module emits decoded defmt frames as [`tracing`] events.
+//!
+//! Unlike [`log_defmt`](crate::log::log_defmt), which has to smuggle the level and timestamp
+//! through the target of a `log` record, every piece of information about the frame is attached
+//! to the event as a structured field, so existing subscribers can filter and route device logs.
+//!
+//! [`tracing`]: https://crates.io/crates/tracing
+
+use ::tracing::{event, Level};
+
+use crate::{Frame, Location};
+
+/// The target of all events emitted by [`emit_event`].
+pub const TARGET: &str = "defmt";
+
+/// Emits `frame` as a `tracing` event with target [`TARGET`].
+///
+/// The level of the event is the level of the frame; frames without a level, like those of
+/// `println!`, are emitted at `INFO`. As the metadata of an event is static, the location of the
+/// log statement is attached as fields, not as the file, line and module path of the event.
+///
+/// The event has the following fields:
+///
+/// - `message`: the formatted message
+/// - `index`: the index of the log statement in the defmt table
+/// - `timestamp`: the formatted timestamp, if the firmware defines one
+/// - `timestamp_ticks`: the raw timestamp, if it is an integer
+/// - `level`: the level of the frame, if it has one
+/// - `module_path`, `file` and `line`: the location of the log statement, if `location` is given
+/// - `args`: the formatted arguments, as a list
+pub fn emit_event(frame: &Frame<'_>, location: Option<&Location>) {
+    macro_rules! emit {
+        ($level:expr) => {
+            event!(
+                target: TARGET,
+                $level,
+                index = frame.index(),
+                timestamp = frame.display_timestamp().map(|ts| ts.to_string()),
+                timestamp_ticks = frame.timestamp_ticks(),
+                level = frame.level().map(|level| level.as_str()),
+                module_path = location.map(|location| location.module.as_str()),
+                file = location.map(|location| location.file.display().to_string()),
+                line = location.map(|location| location.line),
+                args = ?frame.display_args(),
+                "{}",
+                frame.display_message()
+            )
+        };
+    }
+
+    match frame.level() {
+        Some(crate::Level::Trace) => emit!(Level::TRACE),
+        Some(crate::Level::Debug) => emit!(Level::DEBUG),
+        Some(crate::Level::Info) | None => emit!(Level::INFO),
+        Some(crate::Level::Warn) => emit!(Level::WARN),
+        Some(crate::Level::Error) => emit!(Level::ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use ::tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+    use crate::{Encoding, StringEntry, Table, TableEntry, Tag};
+
+    /// The level, target and fields of an event.
+    type Recorded = (Level, String, Vec<String>);
+
+    /// Records every event.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<Recorded>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(Vec::new());
+            event.record(&mut fields);
+            let metadata = event.metadata();
+            self.0.lock().unwrap().push((
+                *metadata.level(),
+                metadata.target().to_string(),
+                fields.0,
+            ));
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn events() {
+        let entry = |tag, format: &str| {
+            TableEntry::new(StringEntry::new(tag, format.to_string()), String::new())
+        };
+        let table = Table::new(
+            [
+                entry(Tag::Warn, "sensor {=u8} temp={=i8}"),
+                entry(Tag::Println, "hello"),
+            ],
+            Encoding::Raw,
+        );
+        let location = Location {
+            file: "src/main.rs".into(),
+            line: 38,
+            module: "app::sensor".to_string(),
+        };
+
+        let recorder = Recorder::default();
+        ::tracing::subscriber::with_default(recorder.clone(), || {
+            let frame = table.decode(&[0, 0, 3, 0xfe]).unwrap().0;
+            emit_event(&frame, Some(&location));
+            let frame = table.decode(&[1, 0]).unwrap().0;
+            emit_event(&frame, None);
+        });
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(
+            *events,
+            [
+                (
+                    Level::WARN,
+                    "defmt".to_string(),
+                    [
+                        "message=sensor 3 temp=-2",
+                        "index=0",
+                        "level=\"warn\"",
+                        "module_path=\"app::sensor\"",
+                        "file=\"src/main.rs\"",
+                        "line=38",
+                        "args=[\"3\", \"-2\"]",
+                    ]
+                    .map(String::from)
+                    .to_vec()
+                ),
+                (
+                    Level::INFO,
+                    "defmt".to_string(),
+                    ["message=hello", "index=1", "args=[]"]
+                        .map(String::from)
+                        .to_vec()
+                ),
+            ]
+        );
+    }
+}
@@ -7,6 +7,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     fs::write(out.join("defmt.x"), linker_script)?;
     println!("cargo:rustc-link-search={}", out.display());
     let target = env::var("TARGET")?;
+    println!("cargo:rustc-check-cfg=cfg(c_variadic, no_cas)");
 
     // `"atomic-cas": false` in `--print target-spec-json`
     // last updated: rust 1.48.0
@@ -9,14 +9,79 @@ macro_rules! write_to_le_bytes {
     };
 }
 
-write_to_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+write_to_le_bytes!(u8, i8);
+
+#[cfg(not(feature = "varint"))]
+write_to_le_bytes!(u16, u32, u64, u128, i16, i32, i64, i128);
+
+/// Sends the unsigned integer `$value` of type `$ty` LEB128-encoded: 7 bits per byte, starting with
+/// the least significant ones, and the highest bit set in all bytes but the last.
+macro_rules! write_leb128 {
+    ($value:expr, $ty:ty) => {{
+        let mut buf = [0; (<$ty>::BITS as usize).div_ceil(7)];
+        let mut value: $ty = $value;
+        let mut len = 0;
+        while value >= 0x80 {
+            buf[len] = value as u8 | 0x80;
+            value >>= 7;
+            len += 1;
+        }
+        buf[len] = value as u8;
+        write(&buf[..=len])
+    }};
+}
+
+/// Integers that are wider than a byte are sent LEB128-encoded; signed integers are zigzag-encoded
+/// first, so that small negative numbers are short too.
+#[cfg(feature = "varint")]
+macro_rules! write_varint {
+    ($($u:ident, $i:ident);*) => {
+        $(/// Implementation detail
+        pub fn $u(b: &$u) {
+            write_leb128!(*b, $u)
+        }
+
+        /// Implementation detail
+        pub fn $i(b: &$i) {
+            $u(&(((*b << 1) ^ (*b >> ($i::BITS - 1))) as $u))
+        })*
+    };
+}
+
+#[cfg(feature = "varint")]
+write_varint!(u16, i16; u32, i32; u64, i64; u128, i128);
+
+/// Implementation detail
+///
+/// Sends the lower 24 bits of `b`.
+pub fn u24(b: &u32) {
+    write(&b.to_le_bytes()[..3])
+}
+
+/// Implementation detail
+///
+/// Sends the lower 24 bits of `b`, which the decoder sign-extends.
+pub fn i24(b: &i32) {
+    write(&b.to_le_bytes()[..3])
+}
 
 /// Implementation detail
 pub fn usize(b: &usize) {
-    write(&(*b as u32).to_le_bytes())
+    u32(&(*b as u32))
 }
 
 /// Implementation detail
 pub fn isize(b: &isize) {
-    write(&(*b as i32).to_le_bytes())
+    i32(&(*b as i32))
+}
+
+/// Sends the index of an interned string that doesn't fit into 16 bits, see `istr`.
+pub(crate) fn wide_index(index: u32) {
+    write_leb128!(index, u32)
+}
+
+/// Sends `value` LEB128-encoded, with or without the `varint` feature, see `timestamp_delta`.
+#[cfg(feature = "timestamp-delta")]
+pub(crate) fn leb128_u64(value: u64) {
+    write_leb128!(value, u64)
 }
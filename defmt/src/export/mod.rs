@@ -1,11 +1,16 @@
 mod integers;
 mod traits;
 
-use core::fmt::Write as _;
+use core::{
+    fmt::Write as _,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
 
 use crate::{Format, Formatter, Str};
 
 pub use self::integers::*;
+#[cfg(feature = "std")]
+pub use crate::host::HostString;
 pub use bitflags::bitflags;
 
 pub trait UnsignedInt {}
@@ -17,8 +22,8 @@ impl UnsignedInt for u128 {}
 
 #[cfg(feature = "unstable-test")]
 thread_local! {
-    static I: core::sync::atomic::AtomicU16 = core::sync::atomic::AtomicU16::new(0);
-    static BYTES: core::cell::RefCell<Vec<u8>> = core::cell::RefCell::new(Vec::new());
+    static I: core::sync::atomic::AtomicU16 = const { core::sync::atomic::AtomicU16::new(0) };
+    static BYTES: core::cell::RefCell<Vec<u8>> = const { core::cell::RefCell::new(Vec::new()) };
 }
 
 /// For testing purposes
@@ -46,13 +51,30 @@ pub unsafe fn acquire() {}
 
 /// Only to be used by the defmt macros
 /// Safety: must be paired with a later call to release()
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(feature = "std")]
+pub unsafe fn acquire() {
+    crate::host::acquire()
+}
+
+/// Only to be used by the defmt macros
+/// Safety: must be paired with a later call to release()
+#[cfg(not(any(feature = "unstable-test", feature = "std")))]
 #[inline(always)]
 pub unsafe fn acquire() {
     extern "Rust" {
         fn _defmt_acquire();
+        #[cfg(feature = "build-id")]
+        fn _defmt_release();
+    }
+    _defmt_acquire();
+
+    // the build ID handshake precedes the first frame
+    #[cfg(feature = "build-id")]
+    if !BUILD_ID_SENT.load(Ordering::Relaxed) {
+        build_id();
+        _defmt_release();
+        _defmt_acquire();
     }
-    _defmt_acquire()
 }
 
 /// Only to be used by the defmt macros
@@ -62,7 +84,14 @@ pub unsafe fn release() {}
 
 /// Only to be used by the defmt macros
 /// Safety: must follow an earlier call to acquire()
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(feature = "std")]
+pub unsafe fn release() {
+    crate::host::release()
+}
+
+/// Only to be used by the defmt macros
+/// Safety: must follow an earlier call to acquire()
+#[cfg(not(any(feature = "unstable-test", feature = "std")))]
 #[inline(always)]
 pub unsafe fn release() {
     extern "Rust" {
@@ -76,7 +105,12 @@ pub fn write(bytes: &[u8]) {
     BYTES.with(|b| b.borrow_mut().extend(bytes))
 }
 
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(feature = "std")]
+pub fn write(bytes: &[u8]) {
+    crate::host::write(bytes)
+}
+
+#[cfg(not(any(feature = "unstable-test", feature = "std")))]
 #[inline(always)]
 pub fn write(bytes: &[u8]) {
     extern "Rust" {
@@ -85,11 +119,11 @@ pub fn write(bytes: &[u8]) {
     unsafe { _defmt_write(bytes) }
 }
 
-/// For testing purposes
-#[cfg(feature = "unstable-test")]
+/// For testing purposes; the host logger doesn't support timestamps either
+#[cfg(any(feature = "unstable-test", feature = "std"))]
 pub fn timestamp(_fmt: crate::Formatter<'_>) {}
 
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(not(any(feature = "unstable-test", feature = "std")))]
 #[inline(always)]
 pub fn timestamp(fmt: crate::Formatter<'_>) {
     extern "Rust" {
@@ -98,8 +132,179 @@ pub fn timestamp(fmt: crate::Formatter<'_>) {
     unsafe { _defmt_timestamp(fmt) }
 }
 
+/// How many frames a keyframe of delta timestamps covers, see [`timestamp_delta`].
+#[cfg(feature = "timestamp-delta")]
+pub const TIMESTAMP_KEYFRAME_INTERVAL: u32 = 32;
+
+/// Sends the timestamp `ticks` as the difference to the timestamp of the previous frame.
+///
+/// Only to be used by the `timestamp!` macro with the `timestamp-delta` feature, while the global
+/// logger is acquired. A delta `d` is sent as the LEB128 encoding of `d + 1`, a keyframe as a zero
+/// byte followed by the LEB128-encoded `ticks`. Keyframes are sent with the first frame, every
+/// [`TIMESTAMP_KEYFRAME_INTERVAL`] frames, and when the timestamp goes backwards.
+#[cfg(feature = "timestamp-delta")]
+pub fn timestamp_delta(ticks: u64) {
+    // 64-bit atomics are not available on all targets; acquiring the logger gives us exclusive
+    // access, so the halves of the previous timestamp can be loaded and stored separately
+    static LAST_LOW: AtomicU32 = AtomicU32::new(0);
+    static LAST_HIGH: AtomicU32 = AtomicU32::new(0);
+    static SINCE_KEYFRAME: AtomicU32 = AtomicU32::new(TIMESTAMP_KEYFRAME_INTERVAL);
+
+    let last = u64::from(LAST_HIGH.load(Ordering::Relaxed)) << 32
+        | u64::from(LAST_LOW.load(Ordering::Relaxed));
+    let since_keyframe = SINCE_KEYFRAME.load(Ordering::Relaxed);
+    match ticks
+        .checked_sub(last)
+        .and_then(|delta| delta.checked_add(1))
+    {
+        Some(delta) if since_keyframe < TIMESTAMP_KEYFRAME_INTERVAL => {
+            leb128_u64(delta);
+            SINCE_KEYFRAME.store(since_keyframe + 1, Ordering::Relaxed);
+        }
+        _ => {
+            write(&[0]);
+            leb128_u64(ticks);
+            SINCE_KEYFRAME.store(1, Ordering::Relaxed);
+        }
+    }
+    LAST_LOW.store(ticks as u32, Ordering::Relaxed);
+    LAST_HIGH.store((ticks >> 32) as u32, Ordering::Relaxed);
+}
+
+/// Whether the build ID handshake has been sent, see [`build_id`].
+#[cfg(all(feature = "build-id", not(feature = "std")))]
+pub(crate) static BUILD_ID_SENT: AtomicBool = AtomicBool::new(false);
+
+/// Sends the build ID handshake: the reserved string index 0, followed by the GNU build ID of the
+/// firmware as a `{=[u8]}`, which the decoder compares with the build ID of the ELF file.
+///
+/// Only to be used while the global logger is acquired, as a frame of its own.
+#[cfg(all(feature = "build-id", not(feature = "std")))]
+#[inline(never)]
+pub fn build_id() {
+    BUILD_ID_SENT.store(true, Ordering::Relaxed);
+    istr(&make_istr(0));
+    slice(build_id_bytes());
+}
+
+#[cfg(all(
+    feature = "build-id",
+    not(any(feature = "unstable-test", feature = "std"))
+))]
+fn build_id_bytes() -> &'static [u8] {
+    extern "C" {
+        // the `.note.gnu.build-id` section, which starts with the size of the name, the size of
+        // the build ID, the note type and the name "GNU\0", followed by the build ID
+        static __defmt_build_id: [u32; 4];
+    }
+    let note = core::ptr::addr_of!(__defmt_build_id);
+    unsafe {
+        let [_, size, _, _] = *note;
+        core::slice::from_raw_parts(note.add(1).cast::<u8>(), size as usize)
+    }
+}
+
+/// For testing purposes
+#[cfg(all(feature = "build-id", feature = "unstable-test"))]
+fn build_id_bytes() -> &'static [u8] {
+    &[0xde, 0xfe, 0xc7]
+}
+
+/// Returns the ID for a new span.
+///
+/// Only to be used by the defmt macros, while the global logger is acquired.
+pub fn span_id() -> u32 {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+    match () {
+        // the host logger is acquired per thread, so it doesn't give us exclusive access
+        #[cfg(feature = "std")]
+        () => NEXT_ID.fetch_add(1, Ordering::Relaxed),
+
+        // acquiring the logger gives us exclusive access, so there's no need for a `fetch_add`,
+        // which is not available on all targets
+        #[cfg(not(feature = "std"))]
+        () => {
+            let id = NEXT_ID.load(Ordering::Relaxed);
+            NEXT_ID.store(id.wrapping_add(1), Ordering::Relaxed);
+            id
+        }
+    }
+}
+
+/// Per-call-site state of the rate-limited logging macros.
+///
+/// Only loads and stores are used, as read-modify-write atomics are not available on all targets.
+/// When a call preempts another call of the same log statement, a frame may be emitted more often
+/// than requested, or a suppressed call may not be counted.
+pub struct RateLimit {
+    started: AtomicBool,
+    /// The time at which the last frame was emitted, for `*_throttled!`.
+    last: AtomicU32,
+    suppressed: AtomicU32,
+}
+
+impl RateLimit {
+    pub const fn new() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+            last: AtomicU32::new(0),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns `Some(0)` on the first call, and `None` on all following calls.
+    pub fn once(&self) -> Option<u32> {
+        match self.started.load(Ordering::Relaxed) {
+            true => None,
+            false => {
+                self.started.store(true, Ordering::Relaxed);
+                Some(0)
+            }
+        }
+    }
+
+    /// Returns the number of suppressed calls on the first and then every `n`th call, and `None`
+    /// on all other calls.
+    pub fn every_n(&self, n: u32) -> Option<u32> {
+        let due = self.suppressed.load(Ordering::Relaxed).saturating_add(1) >= n;
+        self.emit_if(due)
+    }
+
+    /// Returns the number of suppressed calls on the first call and on calls at least `interval`
+    /// after the last call that returned `Some`, and `None` on all other calls.
+    ///
+    /// `now` may wrap around.
+    pub fn throttled(&self, interval: u32, now: u32) -> Option<u32> {
+        let elapsed = now.wrapping_sub(self.last.load(Ordering::Relaxed));
+        let frame = self.emit_if(elapsed >= interval);
+        if frame.is_some() {
+            self.last.store(now, Ordering::Relaxed);
+        }
+        frame
+    }
+
+    fn emit_if(&self, due: bool) -> Option<u32> {
+        let suppressed = self.suppressed.load(Ordering::Relaxed);
+        if due || !self.started.load(Ordering::Relaxed) {
+            self.started.store(true, Ordering::Relaxed);
+            self.suppressed.store(0, Ordering::Relaxed);
+            Some(suppressed)
+        } else {
+            self.suppressed
+                .store(suppressed.saturating_add(1), Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Returns the interned string at `address`.
-pub fn make_istr(address: u16) -> Str {
+pub fn make_istr(address: u32) -> Str {
     Str { address }
 }
 
@@ -124,7 +329,13 @@ pub fn panic() -> ! {
     panic!()
 }
 
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(feature = "std")]
+#[track_caller]
+pub fn panic() -> ! {
+    crate::host::panic()
+}
+
+#[cfg(not(any(feature = "unstable-test", feature = "std")))]
 #[inline(always)]
 pub fn panic() -> ! {
     extern "Rust" {
@@ -148,6 +359,102 @@ pub fn fmt_slice<T: Format>(values: &[T]) {
     }
 }
 
+/// Implementation detail
+///
+/// Encodes the `len` items of a collection like [`fmt_slice`] encodes a slice.
+pub fn fmt_iter<'a, T: Format + 'a>(len: usize, values: impl IntoIterator<Item = &'a T>) {
+    usize(&len);
+    istr(&T::_format_tag());
+    for value in values {
+        value._format_data();
+    }
+}
+
+/// Implementation detail
+pub fn fmt_map<K: Format, V: Format>(entries: &[(K, V)]) {
+    fmt_map_entries(
+        entries.len(),
+        entries.iter().map(|(key, value)| (key, value)),
+    );
+}
+
+/// Implementation detail
+///
+/// Encodes the `len` entries of a map like [`fmt_map`] encodes a slice of key-value pairs.
+pub fn fmt_map_entries<'a, K, V>(len: usize, entries: impl IntoIterator<Item = (&'a K, &'a V)>)
+where
+    K: Format + 'a,
+    V: Format + 'a,
+{
+    usize(&len);
+    istr(&K::_format_tag());
+    istr(&V::_format_tag());
+    for (key, value) in entries {
+        key._format_data();
+        value._format_data();
+    }
+}
+
+/// Implementation detail
+///
+/// Rounds `b` to the nearest IEEE 754 half-precision value, ties to even.
+pub fn f16(b: &f32) {
+    write(&f32_to_f16_bits(*b).to_le_bytes())
+}
+
+/// Implementation detail
+///
+/// Rounds `b` to the nearest bfloat16 value, ties to even.
+pub fn bf16(b: &f32) {
+    write(&f32_to_bf16_bits(*b).to_le_bytes())
+}
+
+fn f32_to_f16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = (bits >> 16) as u16 & 0x8000;
+    let exponent = (bits >> 23) as i32 & 0xff;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // infinity, or NaN which stays quiet
+        return match mantissa {
+            0 => sign | 0x7c00,
+            _ => sign | 0x7e00 | (mantissa >> 13) as u16,
+        };
+    }
+
+    // rebias the exponent: 127 for `f32`, 15 for `f16`
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let (value, dropped_bits) = if exponent > 0 {
+        ((exponent as u32) << 10 | mantissa >> 13, 13)
+    } else {
+        // subnormal: shift in the implicit leading bit
+        let dropped_bits = (14 - exponent) as u32;
+        if dropped_bits > 24 {
+            return sign;
+        }
+        ((mantissa | 0x80_0000) >> dropped_bits, dropped_bits)
+    };
+
+    // a carry out of the mantissa correctly bumps the exponent, up to infinity
+    let rest = (mantissa | 0x80_0000) & ((1 << dropped_bits) - 1);
+    let half = 1 << (dropped_bits - 1);
+    let round_up = rest > half || (rest == half && value & 1 == 1);
+    sign | (value + u32::from(round_up)) as u16
+}
+
+fn f32_to_bf16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    if x.is_nan() {
+        return (bits >> 16) as u16 | 0x40;
+    }
+    let round = 0x7fff + ((bits >> 16) & 1);
+    ((bits + round) >> 16) as u16
+}
+
 /// Implementation detail
 pub fn f32(b: &f32) {
     write(&f32::to_bits(*b).to_le_bytes())
@@ -187,8 +494,39 @@ pub fn fmt_array<T: Format>(a: &[T]) {
 }
 
 /// Implementation detail
+///
+/// Sends the index of an interned string as a `u16`, or LEB128-encoded if the `.defmt` section
+/// has too many strings for 16-bit indices.
+///
+/// With the `stable-ids` feature the index is a hash, which is always sent as a `u32`.
 pub fn istr(s: &Str) {
-    write(&s.address.to_le_bytes())
+    if cfg!(all(feature = "stable-ids", not(feature = "std"))) {
+        // hashes are spread over the whole range, so LEB128 would make most of them longer
+        write(&s.address.to_le_bytes())
+    } else if wide_indices() {
+        wide_index(s.address)
+    } else {
+        u16(&(s.address as u16))
+    }
+}
+
+/// Whether the indices of interned strings need more than 16 bits, which the linker script decides
+/// after it has placed all of them.
+#[cfg(not(any(feature = "unstable-test", feature = "std", target_os = "macos")))]
+#[inline(always)]
+fn wide_indices() -> bool {
+    extern "C" {
+        // only the address of this symbol, 16 or 32, is meaningful
+        static __DEFMT_INDEX_BITS: u8;
+    }
+    core::ptr::addr_of!(__DEFMT_INDEX_BITS) as usize == 32
+}
+
+/// Strings interned at runtime or by the mocked interner always fit into 16 bits. macOS builds
+/// don't use the linker script, so `__DEFMT_INDEX_BITS` isn't defined there.
+#[cfg(any(feature = "unstable-test", feature = "std", target_os = "macos"))]
+fn wide_indices() -> bool {
+    false
 }
 
 /// Implementation detail
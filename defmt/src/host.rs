@@ -0,0 +1,266 @@
+//! Logging on the host, enabled by the `std` feature.
+//!
+//! Instead of sending log frames to a global logger, every frame is decoded in-process and
+//! printed to stderr, which makes crates that use `defmt` testable with `cargo test`. Frames can
+//! also be captured to make assertions about them, see [`capture`].
+//!
+//! As there is no `.defmt` section on the host, strings are interned at runtime. Timestamps are
+//! not supported and `defmt::bitflags!` values are displayed as numbers.
+
+use core::{
+    cell::RefCell,
+    fmt,
+    sync::atomic::{AtomicU16, Ordering},
+};
+use std::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+    vec::Vec,
+};
+
+use defmt_decoder::{Encoding, StringEntry, Table, TableEntry, Tag};
+
+/// Log level of a [`Record`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    #[allow(missing_docs)]
+    Trace,
+    #[allow(missing_docs)]
+    Debug,
+    #[allow(missing_docs)]
+    Info,
+    #[allow(missing_docs)]
+    Warn,
+    #[allow(missing_docs)]
+    Error,
+}
+
+impl Level {
+    fn from_decoder(level: defmt_decoder::Level) -> Self {
+        match level {
+            defmt_decoder::Level::Trace => Level::Trace,
+            defmt_decoder::Level::Debug => Level::Debug,
+            defmt_decoder::Level::Info => Level::Info,
+            defmt_decoder::Level::Warn => Level::Warn,
+            defmt_decoder::Level::Error => Level::Error,
+        }
+    }
+}
+
+/// A decoded log frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The level of the log statement, or `None` for `println!` and other frames without a level.
+    pub level: Option<Level>,
+    /// The formatted message.
+    pub message: String,
+    /// Source file of the log statement.
+    pub file: &'static str,
+    /// Line of the log statement.
+    pub line: u32,
+    /// Module path of the log statement.
+    pub module: &'static str,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            None => "",
+            Some(Level::Trace) => "TRACE ",
+            Some(Level::Debug) => "DEBUG ",
+            Some(Level::Info) => "INFO  ",
+            Some(Level::Warn) => "WARN  ",
+            Some(Level::Error) => "ERROR ",
+        };
+        write!(
+            f,
+            "{}{}\n└─ {} @ {}:{}",
+            level, self.message, self.module, self.file, self.line
+        )
+    }
+}
+
+/// Runs `f` and returns its result, together with the frames it logged on the current thread.
+///
+/// Captured frames are not printed. Frames logged by other threads are not captured.
+///
+/// ```
+/// let ((), records) = defmt::host::capture(|| defmt::println!("x = {=u8}", 42));
+/// assert_eq!(records[0].message, "x = 42");
+/// ```
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<Record>) {
+    /// Restores the previous capture, even if `f` panics.
+    struct Restore(Option<Vec<Record>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CAPTURED.with(|captured| *captured.borrow_mut() = previous);
+        }
+    }
+
+    let previous = CAPTURED.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let restore = Restore(previous);
+    let result = f();
+    let records = CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+    drop(restore);
+    (result, records)
+}
+
+/// A string that is interned on first use; created by the defmt macros.
+#[doc(hidden)]
+pub struct HostString {
+    tag: &'static str,
+    string: &'static str,
+    file: &'static str,
+    line: u32,
+    module: &'static str,
+    /// `0` until the string is interned, as index `0` is reserved.
+    index: AtomicU16,
+}
+
+impl HostString {
+    /// Only to be used by the defmt macros
+    pub const fn new(
+        tag: &'static str,
+        string: &'static str,
+        file: &'static str,
+        line: u32,
+        module: &'static str,
+    ) -> Self {
+        Self {
+            tag,
+            string,
+            file,
+            line,
+            module,
+            index: AtomicU16::new(0),
+        }
+    }
+
+    /// Only to be used by the defmt macros
+    pub fn index(&'static self) -> u32 {
+        match self.index.load(Ordering::Acquire) {
+            0 => lock_registry().intern(self).into(),
+            index => index.into(),
+        }
+    }
+}
+
+/// The interned strings, in the form of the table that the decoder would read from an ELF file.
+struct Registry {
+    table: Table,
+    strings: BTreeMap<u16, &'static HostString>,
+}
+
+impl Registry {
+    fn intern(&mut self, string: &'static HostString) -> u16 {
+        // another thread may have interned the string while we were waiting for the lock
+        let index = string.index.load(Ordering::Acquire);
+        if index != 0 {
+            return index;
+        }
+
+        let index = self.strings.len() + 1;
+        let index = u16::try_from(index)
+            .ok()
+            .filter(|index| *index < u16::MAX)
+            .expect("too many interned strings");
+        let tag = Tag::from_symbol_tag(string.tag).unwrap_or(Tag::Str);
+        let entry = StringEntry::new(tag, string.string.to_string());
+        self.table
+            .insert(index.into(), TableEntry::new(entry, String::new()));
+        self.strings.insert(index, string);
+        string.index.store(index, Ordering::Release);
+        index
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<Record> {
+        let (frame, _) = self.table.decode(bytes).ok()?;
+        let string = self.strings.get(&u16::try_from(frame.index()).ok()?)?;
+        Some(Record {
+            level: frame.level().map(Level::from_decoder),
+            message: frame.display_message().to_string(),
+            file: string.file,
+            line: string.line,
+            module: string.module,
+        })
+    }
+}
+
+fn lock_registry() -> MutexGuard<'static, Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    let registry = REGISTRY.get_or_init(|| {
+        let mut table = Table::new([], Encoding::Raw);
+        table.set_varint(cfg!(feature = "varint"));
+        Mutex::new(Registry {
+            table,
+            strings: BTreeMap::new(),
+        })
+    });
+    // a panicking test must not break logging in the other tests
+    registry.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+std::thread_local! {
+    /// The frame that is being encoded; `Some` between `acquire` and `release`.
+    static FRAME: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    /// The records captured by [`capture`], if it's running.
+    static CAPTURED: RefCell<Option<Vec<Record>>> = const { RefCell::new(None) };
+    /// The message of the most recent frame, used as the message of `defmt::panic!`.
+    static LAST_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn acquire() {
+    FRAME.with(|frame| {
+        let mut frame = frame.borrow_mut();
+        if frame.is_some() {
+            std::panic!("defmt logger taken reentrantly")
+        }
+        *frame = Some(Vec::new());
+    })
+}
+
+pub(crate) fn write(bytes: &[u8]) {
+    FRAME.with(|frame| {
+        if let Some(frame) = &mut *frame.borrow_mut() {
+            frame.extend_from_slice(bytes);
+        }
+    })
+}
+
+pub(crate) fn release() {
+    let bytes = match FRAME.with(|frame| frame.borrow_mut().take()) {
+        Some(bytes) => bytes,
+        None => return,
+    };
+    let record = match lock_registry().decode(&bytes) {
+        Some(record) => record,
+        None => {
+            std::eprintln!("(defmt) malformed frame skipped: {bytes:02x?}");
+            return;
+        }
+    };
+
+    LAST_MESSAGE.with(|message| *message.borrow_mut() = Some(record.message.clone()));
+    let record = CAPTURED.with(|captured| match &mut *captured.borrow_mut() {
+        Some(captured) => {
+            captured.push(record);
+            None
+        }
+        None => Some(record),
+    });
+    if let Some(record) = record {
+        std::eprintln!("{record}");
+    }
+}
+
+#[track_caller]
+pub(crate) fn panic() -> ! {
+    // `defmt::panic!` and friends log "panicked at '..'" right before panicking
+    match LAST_MESSAGE.with(|message| message.borrow_mut().take()) {
+        Some(message) if message.starts_with("panicked at '") => std::panic!("{message}"),
+        _ => std::panic!("explicit panic"),
+    }
+}
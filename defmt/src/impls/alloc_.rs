@@ -44,3 +44,40 @@ where
 impl<'a> Format for alloc::borrow::Cow<'a, str> {
     delegate_format!(str, self, self.as_ref());
 }
+
+impl<T> Format for alloc::collections::VecDeque<T>
+where
+    T: Format,
+{
+    iter_format!();
+}
+
+impl<T> Format for alloc::collections::LinkedList<T>
+where
+    T: Format,
+{
+    iter_format!();
+}
+
+/// The elements are formatted in arbitrary order, like `core::fmt::Debug` does.
+impl<T> Format for alloc::collections::BinaryHeap<T>
+where
+    T: Format,
+{
+    iter_format!();
+}
+
+impl<T> Format for alloc::collections::BTreeSet<T>
+where
+    T: Format,
+{
+    iter_format!();
+}
+
+impl<K, V> Format for alloc::collections::BTreeMap<K, V>
+where
+    K: Format,
+    V: Format,
+{
+    map_format!();
+}
@@ -0,0 +1,116 @@
+//! The numbers are formatted with the fixed-point display hint `:q<n>`, where `n` is the number
+//! of fractional bits, e.g. `FixedI16<U15>` like `{=i16:q15}`.
+
+use fixed::types::extra::{LeEqU16, LeEqU32, LeEqU64, LeEqU8};
+
+use crate::export;
+
+use super::*;
+
+macro_rules! fixed {
+    ($ty:ident, $inner:ident, $bound:ident, { $($bits:literal => $fmt:literal),+ $(,)? }) => {
+        impl<Frac> Format for fixed::$ty<Frac>
+        where
+            Frac: $bound,
+        {
+            default_format!();
+
+            #[inline]
+            fn _format_tag() -> Str {
+                match Frac::U32 {
+                    $($bits => internp!($fmt),)+
+                    _ => unreachable!(),
+                }
+            }
+
+            #[inline]
+            fn _format_data(&self) {
+                export::$inner(&self.to_bits())
+            }
+        }
+    };
+}
+
+fixed!(FixedI8, i8, LeEqU8, {
+    0 => "{=i8:q0}", 1 => "{=i8:q1}", 2 => "{=i8:q2}", 3 => "{=i8:q3}", 4 => "{=i8:q4}",
+    5 => "{=i8:q5}", 6 => "{=i8:q6}", 7 => "{=i8:q7}", 8 => "{=i8:q8}",
+});
+
+fixed!(FixedI16, i16, LeEqU16, {
+    0 => "{=i16:q0}", 1 => "{=i16:q1}", 2 => "{=i16:q2}", 3 => "{=i16:q3}", 4 => "{=i16:q4}",
+    5 => "{=i16:q5}", 6 => "{=i16:q6}", 7 => "{=i16:q7}", 8 => "{=i16:q8}", 9 => "{=i16:q9}",
+    10 => "{=i16:q10}", 11 => "{=i16:q11}", 12 => "{=i16:q12}", 13 => "{=i16:q13}",
+    14 => "{=i16:q14}", 15 => "{=i16:q15}", 16 => "{=i16:q16}",
+});
+
+fixed!(FixedI32, i32, LeEqU32, {
+    0 => "{=i32:q0}", 1 => "{=i32:q1}", 2 => "{=i32:q2}", 3 => "{=i32:q3}", 4 => "{=i32:q4}",
+    5 => "{=i32:q5}", 6 => "{=i32:q6}", 7 => "{=i32:q7}", 8 => "{=i32:q8}", 9 => "{=i32:q9}",
+    10 => "{=i32:q10}", 11 => "{=i32:q11}", 12 => "{=i32:q12}", 13 => "{=i32:q13}",
+    14 => "{=i32:q14}", 15 => "{=i32:q15}", 16 => "{=i32:q16}", 17 => "{=i32:q17}",
+    18 => "{=i32:q18}", 19 => "{=i32:q19}", 20 => "{=i32:q20}", 21 => "{=i32:q21}",
+    22 => "{=i32:q22}", 23 => "{=i32:q23}", 24 => "{=i32:q24}", 25 => "{=i32:q25}",
+    26 => "{=i32:q26}", 27 => "{=i32:q27}", 28 => "{=i32:q28}", 29 => "{=i32:q29}",
+    30 => "{=i32:q30}", 31 => "{=i32:q31}", 32 => "{=i32:q32}",
+});
+
+fixed!(FixedI64, i64, LeEqU64, {
+    0 => "{=i64:q0}", 1 => "{=i64:q1}", 2 => "{=i64:q2}", 3 => "{=i64:q3}", 4 => "{=i64:q4}",
+    5 => "{=i64:q5}", 6 => "{=i64:q6}", 7 => "{=i64:q7}", 8 => "{=i64:q8}", 9 => "{=i64:q9}",
+    10 => "{=i64:q10}", 11 => "{=i64:q11}", 12 => "{=i64:q12}", 13 => "{=i64:q13}",
+    14 => "{=i64:q14}", 15 => "{=i64:q15}", 16 => "{=i64:q16}", 17 => "{=i64:q17}",
+    18 => "{=i64:q18}", 19 => "{=i64:q19}", 20 => "{=i64:q20}", 21 => "{=i64:q21}",
+    22 => "{=i64:q22}", 23 => "{=i64:q23}", 24 => "{=i64:q24}", 25 => "{=i64:q25}",
+    26 => "{=i64:q26}", 27 => "{=i64:q27}", 28 => "{=i64:q28}", 29 => "{=i64:q29}",
+    30 => "{=i64:q30}", 31 => "{=i64:q31}", 32 => "{=i64:q32}", 33 => "{=i64:q33}",
+    34 => "{=i64:q34}", 35 => "{=i64:q35}", 36 => "{=i64:q36}", 37 => "{=i64:q37}",
+    38 => "{=i64:q38}", 39 => "{=i64:q39}", 40 => "{=i64:q40}", 41 => "{=i64:q41}",
+    42 => "{=i64:q42}", 43 => "{=i64:q43}", 44 => "{=i64:q44}", 45 => "{=i64:q45}",
+    46 => "{=i64:q46}", 47 => "{=i64:q47}", 48 => "{=i64:q48}", 49 => "{=i64:q49}",
+    50 => "{=i64:q50}", 51 => "{=i64:q51}", 52 => "{=i64:q52}", 53 => "{=i64:q53}",
+    54 => "{=i64:q54}", 55 => "{=i64:q55}", 56 => "{=i64:q56}", 57 => "{=i64:q57}",
+    58 => "{=i64:q58}", 59 => "{=i64:q59}", 60 => "{=i64:q60}", 61 => "{=i64:q61}",
+    62 => "{=i64:q62}", 63 => "{=i64:q63}", 64 => "{=i64:q64}",
+});
+
+fixed!(FixedU8, u8, LeEqU8, {
+    0 => "{=u8:q0}", 1 => "{=u8:q1}", 2 => "{=u8:q2}", 3 => "{=u8:q3}", 4 => "{=u8:q4}",
+    5 => "{=u8:q5}", 6 => "{=u8:q6}", 7 => "{=u8:q7}", 8 => "{=u8:q8}",
+});
+
+fixed!(FixedU16, u16, LeEqU16, {
+    0 => "{=u16:q0}", 1 => "{=u16:q1}", 2 => "{=u16:q2}", 3 => "{=u16:q3}", 4 => "{=u16:q4}",
+    5 => "{=u16:q5}", 6 => "{=u16:q6}", 7 => "{=u16:q7}", 8 => "{=u16:q8}", 9 => "{=u16:q9}",
+    10 => "{=u16:q10}", 11 => "{=u16:q11}", 12 => "{=u16:q12}", 13 => "{=u16:q13}",
+    14 => "{=u16:q14}", 15 => "{=u16:q15}", 16 => "{=u16:q16}",
+});
+
+fixed!(FixedU32, u32, LeEqU32, {
+    0 => "{=u32:q0}", 1 => "{=u32:q1}", 2 => "{=u32:q2}", 3 => "{=u32:q3}", 4 => "{=u32:q4}",
+    5 => "{=u32:q5}", 6 => "{=u32:q6}", 7 => "{=u32:q7}", 8 => "{=u32:q8}", 9 => "{=u32:q9}",
+    10 => "{=u32:q10}", 11 => "{=u32:q11}", 12 => "{=u32:q12}", 13 => "{=u32:q13}",
+    14 => "{=u32:q14}", 15 => "{=u32:q15}", 16 => "{=u32:q16}", 17 => "{=u32:q17}",
+    18 => "{=u32:q18}", 19 => "{=u32:q19}", 20 => "{=u32:q20}", 21 => "{=u32:q21}",
+    22 => "{=u32:q22}", 23 => "{=u32:q23}", 24 => "{=u32:q24}", 25 => "{=u32:q25}",
+    26 => "{=u32:q26}", 27 => "{=u32:q27}", 28 => "{=u32:q28}", 29 => "{=u32:q29}",
+    30 => "{=u32:q30}", 31 => "{=u32:q31}", 32 => "{=u32:q32}",
+});
+
+fixed!(FixedU64, u64, LeEqU64, {
+    0 => "{=u64:q0}", 1 => "{=u64:q1}", 2 => "{=u64:q2}", 3 => "{=u64:q3}", 4 => "{=u64:q4}",
+    5 => "{=u64:q5}", 6 => "{=u64:q6}", 7 => "{=u64:q7}", 8 => "{=u64:q8}", 9 => "{=u64:q9}",
+    10 => "{=u64:q10}", 11 => "{=u64:q11}", 12 => "{=u64:q12}", 13 => "{=u64:q13}",
+    14 => "{=u64:q14}", 15 => "{=u64:q15}", 16 => "{=u64:q16}", 17 => "{=u64:q17}",
+    18 => "{=u64:q18}", 19 => "{=u64:q19}", 20 => "{=u64:q20}", 21 => "{=u64:q21}",
+    22 => "{=u64:q22}", 23 => "{=u64:q23}", 24 => "{=u64:q24}", 25 => "{=u64:q25}",
+    26 => "{=u64:q26}", 27 => "{=u64:q27}", 28 => "{=u64:q28}", 29 => "{=u64:q29}",
+    30 => "{=u64:q30}", 31 => "{=u64:q31}", 32 => "{=u64:q32}", 33 => "{=u64:q33}",
+    34 => "{=u64:q34}", 35 => "{=u64:q35}", 36 => "{=u64:q36}", 37 => "{=u64:q37}",
+    38 => "{=u64:q38}", 39 => "{=u64:q39}", 40 => "{=u64:q40}", 41 => "{=u64:q41}",
+    42 => "{=u64:q42}", 43 => "{=u64:q43}", 44 => "{=u64:q44}", 45 => "{=u64:q45}",
+    46 => "{=u64:q46}", 47 => "{=u64:q47}", 48 => "{=u64:q48}", 49 => "{=u64:q49}",
+    50 => "{=u64:q50}", 51 => "{=u64:q51}", 52 => "{=u64:q52}", 53 => "{=u64:q53}",
+    54 => "{=u64:q54}", 55 => "{=u64:q55}", 56 => "{=u64:q56}", 57 => "{=u64:q57}",
+    58 => "{=u64:q58}", 59 => "{=u64:q59}", 60 => "{=u64:q60}", 61 => "{=u64:q61}",
+    62 => "{=u64:q62}", 63 => "{=u64:q63}", 64 => "{=u64:q64}",
+});
@@ -0,0 +1,31 @@
+use crate::export;
+
+use super::*;
+
+impl Format for half::f16 {
+    default_format!();
+
+    #[inline]
+    fn _format_tag() -> Str {
+        internp!("{=f16}")
+    }
+
+    #[inline]
+    fn _format_data(&self) {
+        export::write(&self.to_bits().to_le_bytes());
+    }
+}
+
+impl Format for half::bf16 {
+    default_format!();
+
+    #[inline]
+    fn _format_tag() -> Str {
+        internp!("{=bf16}")
+    }
+
+    #[inline]
+    fn _format_data(&self) {
+        export::write(&self.to_bits().to_le_bytes());
+    }
+}
@@ -27,11 +27,51 @@ macro_rules! delegate_format {
     };
 }
 
+/// Formats a collection like a slice; `&self` must iterate over its elements.
+#[cfg(feature = "alloc")]
+macro_rules! iter_format {
+    () => {
+        default_format!();
+
+        #[inline]
+        fn _format_tag() -> Str {
+            internp!("{=[?]}")
+        }
+
+        #[inline]
+        fn _format_data(&self) {
+            crate::export::fmt_iter(self.len(), self);
+        }
+    };
+}
+
+/// Formats a map like `{=[?:?]}`; `&self` must iterate over its `(key, value)` entries.
+#[cfg(feature = "alloc")]
+macro_rules! map_format {
+    () => {
+        default_format!();
+
+        #[inline]
+        fn _format_tag() -> Str {
+            internp!("{=[?:?]}")
+        }
+
+        #[inline]
+        fn _format_data(&self) {
+            crate::export::fmt_map_entries(self.len(), self);
+        }
+    };
+}
+
 pub mod adapter;
 #[cfg(feature = "alloc")]
 mod alloc_;
 mod arrays;
 mod core_;
+#[cfg(feature = "fixed")]
+mod fixed_;
+#[cfg(feature = "half")]
+mod half_;
 mod primitives;
 mod tuples;
 
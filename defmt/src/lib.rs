@@ -18,6 +18,11 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(all(feature = "std", feature = "unstable-test"))]
+compile_error!("the `std` and `unstable-test` features can't be enabled at the same time");
 
 // This must be in the root lib.rs, otherwise it doesn't appear in the final binary.
 
@@ -33,13 +38,66 @@ extern crate alloc;
 #[export_name = "_defmt_version_ = 4"]
 static DEFMT_VERSION: u8 = 0;
 
+// `concat!` only accepts literals, so every part of the encoding name is a `cfg`-dependent macro
+#[cfg(feature = "encoding-raw")]
+macro_rules! encoding_framing {
+    () => {
+        "raw"
+    };
+}
+#[cfg(not(feature = "encoding-raw"))]
+macro_rules! encoding_framing {
+    () => {
+        "rzcobs"
+    };
+}
+#[cfg(feature = "varint")]
+macro_rules! encoding_varint {
+    () => {
+        "+varint"
+    };
+}
+#[cfg(not(feature = "varint"))]
+macro_rules! encoding_varint {
+    () => {
+        ""
+    };
+}
+#[cfg(feature = "timestamp-delta")]
+macro_rules! encoding_timestamp_delta {
+    () => {
+        "+timestamp-delta"
+    };
+}
+#[cfg(not(feature = "timestamp-delta"))]
+macro_rules! encoding_timestamp_delta {
+    () => {
+        ""
+    };
+}
+#[cfg(feature = "stable-ids")]
+macro_rules! encoding_stable_ids {
+    () => {
+        "+stable-ids"
+    };
+}
+#[cfg(not(feature = "stable-ids"))]
+macro_rules! encoding_stable_ids {
+    () => {
+        ""
+    };
+}
+
 #[used]
 #[cfg_attr(target_os = "macos", link_section = ".defmt,end.ENCODING")]
 #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
-#[cfg_attr(feature = "encoding-raw", export_name = "_defmt_encoding_ = raw")]
-#[cfg_attr(
-    not(feature = "encoding-raw"),
-    export_name = "_defmt_encoding_ = rzcobs"
+// the framing, followed by the features that change the wire format
+#[export_name = concat!(
+    "_defmt_encoding_ = ",
+    encoding_framing!(),
+    encoding_varint!(),
+    encoding_timestamp_delta!(),
+    encoding_stable_ids!(),
 )]
 #[allow(missing_docs)]
 #[doc(hidden)]
@@ -49,7 +107,10 @@ mod encoding;
 #[doc(hidden)]
 pub mod export;
 mod formatter;
+#[cfg(feature = "std")]
+pub mod host;
 mod impls;
+mod span;
 #[cfg(all(test, feature = "unstable-test"))]
 mod tests;
 mod traits;
@@ -58,7 +119,8 @@ pub use crate::{
     encoding::Encoder,
     formatter::{Formatter, Str},
     impls::adapter::{Debug2Format, Display2Format},
-    traits::{Format, Logger},
+    span::SpanGuard,
+    traits::{DynFormat, Format, Logger},
 };
 
 #[cfg(all(test, not(feature = "unstable-test")))]
@@ -257,6 +319,174 @@ pub use defmt_macros::trace;
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::warn;
 
+/// Logs data at *trace* level, but only the first time the log statement is executed.
+///
+/// Please refer to [the manual] for documentation on the rate-limited logging macros.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::trace_once;
+
+/// Logs data at *debug* level, but only the first time the log statement is executed.
+///
+/// Please refer to [the manual] for documentation on the rate-limited logging macros.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::debug_once;
+
+/// Logs data at *info* level, but only the first time the log statement is executed.
+///
+/// Please refer to [the manual] for documentation on the rate-limited logging macros.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::info_once;
+
+/// Logs data at *warn* level, but only the first time the log statement is executed.
+///
+/// Please refer to [the manual] for documentation on the rate-limited logging macros.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::warn_once;
+
+/// Logs data at *error* level, but only the first time the log statement is executed.
+///
+/// Please refer to [the manual] for documentation on the rate-limited logging macros.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::error_once;
+
+/// Logs data at *trace* level, but only every `n`th time the log statement is executed.
+///
+/// `defmt::trace_every_n!(n, "format string", args...)` logs on the first execution and then on every
+/// `n`th; the frame includes the number of executions that were suppressed since the last one.
+/// Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::trace_every_n;
+
+/// Logs data at *debug* level, but only every `n`th time the log statement is executed.
+///
+/// `defmt::debug_every_n!(n, "format string", args...)` logs on the first execution and then on every
+/// `n`th; the frame includes the number of executions that were suppressed since the last one.
+/// Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::debug_every_n;
+
+/// Logs data at *info* level, but only every `n`th time the log statement is executed.
+///
+/// `defmt::info_every_n!(n, "format string", args...)` logs on the first execution and then on every
+/// `n`th; the frame includes the number of executions that were suppressed since the last one.
+/// Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::info_every_n;
+
+/// Logs data at *warn* level, but only every `n`th time the log statement is executed.
+///
+/// `defmt::warn_every_n!(n, "format string", args...)` logs on the first execution and then on every
+/// `n`th; the frame includes the number of executions that were suppressed since the last one.
+/// Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::warn_every_n;
+
+/// Logs data at *error* level, but only every `n`th time the log statement is executed.
+///
+/// `defmt::error_every_n!(n, "format string", args...)` logs on the first execution and then on every
+/// `n`th; the frame includes the number of executions that were suppressed since the last one.
+/// Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::error_every_n;
+
+/// Logs data at *trace* level, but at most once per time interval.
+///
+/// `defmt::trace_throttled!(interval, now, "format string", args...)` logs if at least `interval` has
+/// passed since the last frame of this log statement; `interval` and `now` are converted to `u32`
+/// and must use the same unit. The frame includes the number of executions that were suppressed
+/// since the last one. Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::trace_throttled;
+
+/// Logs data at *debug* level, but at most once per time interval.
+///
+/// `defmt::debug_throttled!(interval, now, "format string", args...)` logs if at least `interval` has
+/// passed since the last frame of this log statement; `interval` and `now` are converted to `u32`
+/// and must use the same unit. The frame includes the number of executions that were suppressed
+/// since the last one. Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::debug_throttled;
+
+/// Logs data at *info* level, but at most once per time interval.
+///
+/// `defmt::info_throttled!(interval, now, "format string", args...)` logs if at least `interval` has
+/// passed since the last frame of this log statement; `interval` and `now` are converted to `u32`
+/// and must use the same unit. The frame includes the number of executions that were suppressed
+/// since the last one. Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::info_throttled;
+
+/// Logs data at *warn* level, but at most once per time interval.
+///
+/// `defmt::warn_throttled!(interval, now, "format string", args...)` logs if at least `interval` has
+/// passed since the last frame of this log statement; `interval` and `now` are converted to `u32`
+/// and must use the same unit. The frame includes the number of executions that were suppressed
+/// since the last one. Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::warn_throttled;
+
+/// Logs data at *error* level, but at most once per time interval.
+///
+/// `defmt::error_throttled!(interval, now, "format string", args...)` logs if at least `interval` has
+/// passed since the last frame of this log statement; `interval` and `now` are converted to `u32`
+/// and must use the same unit. The frame includes the number of executions that were suppressed
+/// since the last one. Please refer to [the manual] for details.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html#rate-limited-logging
+pub use defmt_macros::error_throttled;
+
+/// Enters a span and returns a [`SpanGuard`] that exits it when dropped.
+///
+/// The format string and its arguments describe the span and are only sent when entering it. Span
+/// frames are enabled and disabled together with the *debug* level.
+///
+/// Please refer to [the manual] for documentation on the syntax.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/spans.html
+pub use defmt_macros::span;
+
+/// Increments a counter metric.
+///
+/// `counter!("name")` increments the counter by one, `counter!("name", n)` increments it by `n`,
+/// which is converted to `u32` with `as`. Metric frames are not affected by `DEFMT_LOG`.
+///
+/// Please refer to [the manual] for documentation on the syntax.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/metrics.html
+pub use defmt_macros::counter;
+
+/// Sets a gauge metric to a value, which is converted to `f32` with `as`.
+///
+/// Metric frames are not affected by `DEFMT_LOG`.
+///
+/// Please refer to [the manual] for documentation on the syntax.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/metrics.html
+pub use defmt_macros::gauge;
+
+/// Records an observation of a histogram metric, which is converted to `f32` with `as`.
+///
+/// Metric frames are not affected by `DEFMT_LOG`.
+///
+/// Please refer to [the manual] for documentation on the syntax.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/metrics.html
+pub use defmt_macros::histogram;
+
 /// Just like the [`std::dbg!`] macro but `defmt` is used to log the message at `TRACE` level.
 ///
 /// [`std::dbg!`]: https://doc.rust-lang.org/std/macro.dbg.html
@@ -368,6 +598,31 @@ fn default_panic() -> ! {
     core::panic!()
 }
 
+/// Sends the build ID of the firmware, which lets the printer check that it decodes the log frames
+/// with the ELF file of this firmware.
+///
+/// With the `build-id` feature this happens before the first log frame; call this function when
+/// the printer may have missed it, e.g. when it attaches to an already-running device.
+#[cfg(feature = "build-id")]
+pub fn send_build_id() {
+    match () {
+        // the host logger decodes the frames in-process, with the table of this program
+        #[cfg(feature = "std")]
+        () => {}
+
+        #[cfg(not(feature = "std"))]
+        () => {
+            // keep `acquire` from sending the handshake too
+            export::BUILD_ID_SENT.store(true, core::sync::atomic::Ordering::Relaxed);
+            unsafe {
+                export::acquire();
+                export::build_id();
+                export::release();
+            }
+        }
+    }
+}
+
 /// Block until host has read all pending data.
 ///
 /// The flush operation will not fail, but might not succeed in flushing _all_ pending data. It is
@@ -377,12 +632,12 @@ fn default_panic() -> ! {
 /// [`defmt-rtt`](https://crates.io/crates/defmt-rtt) or [`defmt-itm`](https://crates.io/crates/defmt-itm).
 pub fn flush() {
     match () {
-        #[cfg(feature = "unstable-test")]
+        #[cfg(any(feature = "unstable-test", feature = "std"))]
         () => {
             // no-op when run on host
         }
 
-        #[cfg(not(feature = "unstable-test"))]
+        #[cfg(not(any(feature = "unstable-test", feature = "std")))]
         () => {
             extern "Rust" {
                 fn _defmt_acquire();
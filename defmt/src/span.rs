@@ -0,0 +1,42 @@
+use crate::{export, Str};
+
+/// Guard returned by [`span!`](crate::span); the span is exited when the guard is dropped.
+#[must_use = "the span is exited as soon as the guard is dropped"]
+pub struct SpanGuard {
+    /// Exit format string and span ID; `None` if the span is disabled by the log filter.
+    inner: Option<(Str, u32)>,
+}
+
+impl SpanGuard {
+    /// Only to be used by the defmt macros
+    #[doc(hidden)]
+    pub fn new(exit: Str, id: u32) -> Self {
+        Self {
+            inner: Some((exit, id)),
+        }
+    }
+
+    /// Only to be used by the defmt macros
+    #[doc(hidden)]
+    pub fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    /// Returns the ID of the span, or `None` if it is disabled by the log filter.
+    pub fn id(&self) -> Option<u32> {
+        self.inner.map(|(_, id)| id)
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if let Some((exit, id)) = self.inner {
+            // safety: will be released a few lines further down
+            unsafe { export::acquire() };
+            export::header(&exit);
+            export::u32(&id);
+            // safety: acquire() was called a few lines above
+            unsafe { export::release() }
+        }
+    }
+}
@@ -54,10 +54,61 @@ pub trait Format {
     #[doc(hidden)]
     fn _format_data(&self) {
         self.format(export::make_formatter());
-        export::u16(&0); // terminator
+        export::istr(&export::make_istr(0)); // terminator
     }
 }
 
+/// Object-safe companion of [`Format`], for values whose type is only known at runtime.
+///
+/// `Format` can't be used as a trait object, because of its hidden methods. `DynFormat` is
+/// implemented for all types that implement `Format`, and `dyn DynFormat` implements `Format`, so
+/// a `&dyn DynFormat` can be stored alongside values of other types, passed through trait objects,
+/// and logged with `{}`.
+///
+/// # Example
+///
+/// ```
+/// use defmt::DynFormat;
+///
+/// let values: [&dyn DynFormat; 3] = [&1u8, &"two", &Some(3.0f32)];
+/// for value in values {
+///     defmt::info!("value: {}", value);
+/// }
+/// ```
+pub trait DynFormat {
+    /// Writes the defmt representation of `self` to `fmt`, like [`Format::format`].
+    fn format_dyn(&self, fmt: Formatter);
+}
+
+impl<T: Format + ?Sized> DynFormat for T {
+    #[inline]
+    fn format_dyn(&self, _fmt: Formatter) {
+        // not `self.format(fmt)`, which `#[derive(Format)]` doesn't implement
+        export::istr(&T::_format_tag());
+        self._format_data();
+    }
+}
+
+// the type of a `dyn DynFormat` is unknown at compile time, so it is sent as a format sequence
+macro_rules! impl_format_for_dyn {
+    ($($ty:ty),*) => {
+        $(
+            impl Format for $ty {
+                fn format(&self, fmt: Formatter) {
+                    self.format_dyn(fmt)
+                }
+            }
+        )*
+    };
+}
+
+impl_format_for_dyn!(
+    dyn DynFormat + '_,
+    dyn DynFormat + Send + '_,
+    dyn DynFormat + Sync + '_,
+    dyn DynFormat + Send + Sync + '_
+);
+
 /// Global logger acquire-release mechanism
 ///
 /// This trait's methods will be called by the defmt logging macros to transmit the
@@ -0,0 +1,20 @@
+//! Tests for the `build-id` feature, which sends the build ID handshake before the first frame.
+//!
+//! Run with `cargo test -p defmt --features unstable-test,build-id --test build_id`.
+//!
+//! NOTE the mocked build ID is `[0xde, 0xfe, 0xc7]`.
+
+#![cfg(all(feature = "unstable-test", feature = "build-id"))]
+
+#[test]
+fn handshake() {
+    defmt::send_build_id();
+    assert_eq!(
+        defmt::export::fetch_bytes(),
+        [
+            0, 0, // reserved index
+            3, 0, 0, 0, // length
+            0xde, 0xfe, 0xc7, // build ID
+        ]
+    );
+}
@@ -36,6 +36,10 @@
 //
 // - the mocked index is 7 bits so its LEB128 encoding is the input byte
 
+// the expected bytes assume fixed-width integers and 16-bit indices, see `tests/varint.rs` and
+// `tests/stable_ids.rs` for the `varint` and `stable-ids` features
+#![cfg(not(any(feature = "varint", feature = "stable-ids")))]
+
 use defmt::{export::fetch_string_index, write, Debug2Format, Display2Format, Format, Formatter};
 
 // Increase the 7-bit mocked interned index
@@ -439,6 +443,7 @@ fn c_like_enum() {
 
 #[test]
 fn uninhabited_enum() {
+    #[allow(dead_code)]
     #[derive(Format)]
     enum Void {}
 }
@@ -592,6 +597,69 @@ fn slice_of_bools() {
     )
 }
 
+#[test]
+fn map() {
+    let index = fetch_string_index();
+    let g = defmt::export::make_formatter();
+    let map: &[(u8, u16)] = &[(1, 256), (2, 512)];
+    write!(g, "{=[?:?]}", map);
+    check!([
+        index,         // "{=[?:?]}"
+        2u32,          // length
+        inc(index, 1), // "{=u8}"
+        inc(index, 2), // "{=u16}"
+        1u8,           // key 0
+        256u16,        // value 0
+        2u8,           // key 1
+        512u16,        // value 1
+    ]);
+}
+
+#[test]
+fn integers_24_bit() {
+    let index = fetch_string_index();
+    let g = defmt::export::make_formatter();
+    write!(g, "{=u24} {=i24}", 0x12_3456u32, -2i32);
+    check!([
+        index, // "{=u24} {=i24}"
+        0x56u8, 0x34u8, 0x12u8, // 0x123456
+        0xfeu8, 0xffu8, 0xffu8, // -2
+    ]);
+}
+
+#[test]
+fn half_precision_floats() {
+    let index = fetch_string_index();
+    let f16_cases = [
+        (0.1f32, 0x2e66u16),
+        (-2.0, 0xc000),
+        (65504.0, 0x7bff),      // largest finite value
+        (65520.0, 0x7c00),      // rounds to infinity
+        (5.9604645e-8, 0x0001), // smallest subnormal
+        (1e-7, 0x0002),
+        (2.9802322e-8, 0x0000), // ties to even
+        (f32::NAN, 0x7e00),
+    ];
+    for (n, (value, bits)) in f16_cases.into_iter().enumerate() {
+        let g = defmt::export::make_formatter();
+        write!(g, "{=f16}", value);
+        check!([inc(index, n as u16), bits]);
+    }
+
+    let index = fetch_string_index();
+    let bf16_cases = [
+        (0.1f32, 0x3dcdu16),
+        (2.72, 0x402e),
+        (f32::MAX, 0x7f80), // rounds to infinity
+        (f32::NAN, 0x7fc0),
+    ];
+    for (n, (value, bits)) in bf16_cases.into_iter().enumerate() {
+        let g = defmt::export::make_formatter();
+        write!(g, "{=bf16}", value);
+        check!([inc(index, n as u16), bits]);
+    }
+}
+
 #[test]
 fn format_primitives() {
     let index = fetch_string_index();
@@ -913,6 +981,105 @@ fn derive_with_bounds() {
     );
 }
 
+#[test]
+fn derive_attributes() {
+    struct NotFormat;
+
+    #[derive(Format)]
+    #[defmt(bound = "")]
+    struct S<T> {
+        #[defmt(skip)]
+        _secret: T,
+        #[defmt(format = "{=u16:x}")]
+        a: u16,
+    }
+
+    #[derive(Format)]
+    #[defmt(transparent)]
+    struct W(u8);
+
+    #[derive(Format)]
+    #[defmt(format = "{=u8}.{=u8}", self.major, self.minor)]
+    struct Version {
+        major: u8,
+        minor: u8,
+    }
+
+    #[derive(Format)]
+    #[defmt(rename = "Temp")]
+    struct Temperature {
+        #[defmt(rename = "celsius", format = "{=i8}°C")]
+        value: i8,
+        unit: u8,
+    }
+
+    #[derive(Format)]
+    enum State {
+        #[defmt(rename = "idle")]
+        Idle,
+        Busy(u8),
+    }
+
+    let index = fetch_string_index();
+    check_format!(
+        &S {
+            _secret: NotFormat,
+            a: 0x1234,
+        },
+        [
+            index,     // "S {{ a: {=u16:x} }}"
+            0x1234u16, // a
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &W(5),
+        [
+            index, // "{=u8}"
+            5u8,
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &Version { major: 1, minor: 2 },
+        [
+            index, // "{=u8}.{=u8}"
+            1u8, 2u8,
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &Temperature { value: -4, unit: 0 },
+        [
+            index, // "Temp {{ celsius: {=i8}°C, unit: {=u8:?} }}"
+            -4i8,  // value
+            0u8,   // unit
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &State::Idle,
+        [
+            index, // "idle|Busy({=u8})"
+            0u8,   // discriminant
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &State::Busy(7),
+        [
+            index, // "idle|Busy({=u8})"
+            1u8,   // discriminant
+            7u8,   // field
+        ],
+    );
+}
+
 #[test]
 fn format_bools() {
     #[derive(Format)]
@@ -1008,3 +1175,145 @@ fn core_fmt_adapters() {
     let index = fetch_string_index();
     check_format!(&Display2Format(&123u8), [index, b'1', b'2', b'3', 0xffu8]);
 }
+
+#[test]
+fn dyn_format() {
+    // the concrete type isn't known at compile time, so the value is sent as a format sequence
+    let values: [&dyn defmt::DynFormat; 2] = [&42u8, &true];
+    let index = fetch_string_index();
+    check_format!(
+        values[0],
+        [
+            index,         // "{=__internal_FormatSequence}"
+            inc(index, 1), // "{=u8}"
+            42u8,
+            0u16, // terminator
+        ]
+    );
+
+    let index = fetch_string_index();
+    let g = defmt::export::make_formatter();
+    write!(g, "{}", values[1]);
+    check!([
+        index,         // "{}"
+        inc(index, 1), // "{=__internal_FormatSequence}"
+        inc(index, 2), // "{=bool}"
+        1u8,
+        0u16, // terminator
+    ]);
+
+    #[derive(Format)]
+    struct Point {
+        x: u8,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Format)]
+    enum Mode {
+        Idle,
+        Active(u8),
+    }
+
+    let values: [&dyn defmt::DynFormat; 2] = [&Point { x: 1 }, &Mode::Active(2)];
+    let index = fetch_string_index();
+    check_format!(
+        values[0],
+        [
+            index,         // "{=__internal_FormatSequence}"
+            inc(index, 1), // "Point {{ x: {=u8:?} }}"
+            1u8,           // x
+            0u16,          // terminator
+        ]
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        values[1],
+        [
+            index,         // "{=__internal_FormatSequence}"
+            inc(index, 1), // "Idle|Active({=u8})"
+            1u8,           // discriminant
+            2u8,           // field
+            0u16,          // terminator
+        ]
+    );
+}
+
+#[test]
+fn span_guard() {
+    let index = fetch_string_index();
+    let guard = defmt::SpanGuard::new(defmt::export::make_istr(index.into()), 7);
+    assert_eq!(guard.id(), Some(7));
+    drop(guard);
+    check!([
+        index, // exit format string
+        7u32,  // span ID
+    ]);
+
+    let guard = defmt::SpanGuard::disabled();
+    assert_eq!(guard.id(), None);
+    drop(guard);
+    assert!(defmt::export::fetch_bytes().is_empty());
+}
+
+#[test]
+fn metrics() {
+    let index = fetch_string_index();
+    defmt::counter!("rx_packets");
+    check!([
+        index, // metric name
+        1u32,  // increment
+    ]);
+
+    let index = fetch_string_index();
+    defmt::counter!("rx_bytes", 300u16);
+    check!([index, 300u32]);
+
+    let index = fetch_string_index();
+    defmt::gauge!("temperature", 21);
+    check!([index, 21f32.to_bits()]);
+
+    let index = fetch_string_index();
+    defmt::histogram!("latency_us", 12.5f64);
+    check!([index, 12.5f32.to_bits()]);
+}
+
+#[test]
+fn rate_limited() {
+    for i in 0..3u8 {
+        let index = fetch_string_index();
+        defmt::error_once!("once {=u8}", i);
+        match i {
+            0 => check!([index, i]),
+            _ => assert!(defmt::export::fetch_bytes().is_empty()),
+        }
+    }
+
+    for i in 0..7u8 {
+        let index = fetch_string_index();
+        defmt::error_every_n!(3, "every third {=u8}", i);
+        match i {
+            0 => check!([index, i]),
+            // the number of suppressed calls follows the arguments
+            3 | 6 => check!([index, i, 2u32]),
+            _ => assert!(defmt::export::fetch_bytes().is_empty()),
+        }
+    }
+
+    for (now, logged) in [
+        (0u64, Some(0u32)),
+        (500, None),
+        (999, None),
+        (1000, Some(2)),
+        (1500, None),
+        (2500, Some(1)),
+    ] {
+        let index = fetch_string_index();
+        defmt::error_throttled!(1000, now, "throttled");
+        match logged {
+            Some(0) => check!([index]),
+            Some(suppressed) => check!([index, suppressed]),
+            None => assert!(defmt::export::fetch_bytes().is_empty()),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Tests for the `stable-ids` feature, which sends the indices of interned strings as a `u32`.
+//!
+//! Run with `cargo test -p defmt --features unstable-test,stable-ids --test stable_ids`.
+//!
+//! NOTE the mocked interner still hands out consecutive indices instead of hashes.
+
+#![cfg(all(feature = "unstable-test", feature = "stable-ids"))]
+
+use defmt::{export::fetch_string_index, write};
+
+fn index() -> [u8; 4] {
+    (fetch_string_index() as u32).to_le_bytes()
+}
+
+#[test]
+fn indices() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    // the argument is interned before the format string
+    write!(g, "{=u8} {=istr}", 42u8, defmt::intern!("hi"));
+    let mut expected = (u32::from_le_bytes(index) + 1).to_le_bytes().to_vec();
+    expected.push(42);
+    expected.extend(index);
+    assert_eq!(defmt::export::fetch_bytes(), expected);
+}
+
+#[test]
+fn format_sequence() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    write!(g, "{=?}", 1u8);
+    let mut expected = index.to_vec();
+    expected.extend((u32::from_le_bytes(index) + 1).to_le_bytes()); // "{=u8}"
+    expected.push(1);
+    assert_eq!(defmt::export::fetch_bytes(), expected);
+}
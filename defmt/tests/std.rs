@@ -0,0 +1,289 @@
+//! Tests for the host logger, which is enabled by the `std` feature.
+//!
+//! Run with `cargo test -p defmt --features std --test std`. Without `DEFMT_LOG`, only `error!`
+//! and `println!` are enabled.
+
+#![cfg(feature = "std")]
+
+use defmt::host::{capture, Level};
+
+#[derive(defmt::Format)]
+struct Reading {
+    channel: u8,
+    value: i16,
+}
+
+enum Mode {
+    Idle,
+    Active(u32),
+}
+
+impl defmt::Format for Mode {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Mode::Idle => defmt::write!(f, "idle"),
+            Mode::Active(rate) => defmt::write!(f, "active at {=u32} Hz", rate),
+        }
+    }
+}
+
+#[test]
+fn capture_records() {
+    let (result, records) = capture(|| {
+        defmt::println!(
+            "reading: {}",
+            Reading {
+                channel: 2,
+                value: -7
+            }
+        );
+        defmt::error!("mode {} after {=[u8]:x}", Mode::Active(50), [1u8, 2]);
+        defmt::println!("{}", Mode::Idle);
+        42
+    });
+    assert_eq!(result, 42);
+
+    let messages = records
+        .iter()
+        .map(|record| record.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "reading: Reading { channel: 2, value: -7 }",
+            "mode active at 50 Hz after [1, 2]",
+            "idle",
+        ]
+    );
+    assert_eq!(records[0].level, None);
+    assert_eq!(records[1].level, Some(Level::Error));
+    assert_eq!(records[1].module, "std");
+    assert!(records[1].file.ends_with("std.rs"));
+    assert_eq!(
+        records[2].to_string(),
+        format!("idle\n└─ std @ {}:{}", records[2].file, records[2].line)
+    );
+}
+
+#[test]
+fn dyn_format() {
+    #[derive(defmt::Format)]
+    struct Point {
+        x: u8,
+        y: u8,
+    }
+
+    #[derive(defmt::Format)]
+    enum Event {
+        Reset,
+        Moved(Point),
+    }
+
+    let values: [&(dyn defmt::DynFormat + Sync); 6] = [
+        &Mode::Idle,
+        &-3i16,
+        &[1u8, 2],
+        &Point { x: 1, y: 2 },
+        &Event::Reset,
+        &Event::Moved(Point { x: 3, y: 4 }),
+    ];
+    let ((), records) = capture(|| {
+        for value in values {
+            defmt::println!("{}", value);
+        }
+    });
+    let messages = records.iter().map(|record| &*record.message);
+    assert_eq!(
+        messages.collect::<Vec<_>>(),
+        [
+            "idle",
+            "-3",
+            "[1, 2]",
+            "Point { x: 1, y: 2 }",
+            "Reset",
+            "Moved(Point { x: 3, y: 4 })",
+        ]
+    );
+}
+
+#[test]
+fn nested_capture() {
+    let (inner, outer) = capture(|| {
+        defmt::println!("outer");
+        let ((), inner) = capture(|| defmt::println!("inner"));
+        inner
+    });
+    assert_eq!(inner.len(), 1);
+    assert_eq!(inner[0].message, "inner");
+    assert_eq!(outer.len(), 1);
+    assert_eq!(outer[0].message, "outer");
+}
+
+#[test]
+fn threads() {
+    let handles = (0..4u8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let ((), records) = capture(|| {
+                    for _ in 0..100 {
+                        defmt::println!("thread {=u8}", i);
+                    }
+                });
+                records
+            })
+        })
+        .collect::<Vec<_>>();
+    for (i, handle) in handles.into_iter().enumerate() {
+        let records = handle.join().unwrap();
+        assert_eq!(records.len(), 100);
+        assert!(records
+            .iter()
+            .all(|record| record.message == format!("thread {i}")));
+    }
+}
+
+#[test]
+fn derive_attributes() {
+    #[derive(defmt::Format)]
+    struct Credentials<'a> {
+        user: &'a str,
+        #[defmt(skip)]
+        _password: &'a str,
+        #[defmt(format = "{=u32:#x}")]
+        flags: u32,
+    }
+
+    #[derive(defmt::Format)]
+    #[defmt(transparent)]
+    struct Meters(f32);
+
+    #[derive(defmt::Format)]
+    #[defmt(format = "({=i32}, {=i32})", self.x, self.y)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(defmt::Format)]
+    #[allow(dead_code)] // the field of `Reset` is skipped
+    enum Event {
+        Moved(Point, #[defmt(format = "{=u16}ms")] u16),
+        Reset(#[defmt(skip)] u8),
+    }
+
+    #[derive(defmt::Format)]
+    #[defmt(rename = "Temp")]
+    struct Temperature {
+        #[defmt(rename = "celsius", format = "{=i8}°C")]
+        value: i8,
+    }
+
+    #[derive(defmt::Format)]
+    enum State {
+        #[defmt(rename = "idle")]
+        Idle,
+        Busy,
+    }
+
+    let ((), records) = capture(|| {
+        let credentials = Credentials {
+            user: "admin",
+            _password: "hunter2",
+            flags: 0x2a,
+        };
+        defmt::println!("{}", credentials);
+        defmt::println!("{}", Meters(1.5));
+        defmt::println!("{}", Event::Moved(Point { x: 1, y: -2 }, 20));
+        defmt::println!("{}", Event::Reset(3));
+        defmt::println!("{}", Temperature { value: -4 });
+        defmt::println!("{} {}", State::Idle, State::Busy);
+    });
+    let messages = records
+        .iter()
+        .map(|record| record.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Credentials { user: \"admin\", flags: 0x2a }",
+            "1.5",
+            "Moved((1, -2), 20ms)",
+            "Reset",
+            "Temp { celsius: -4°C }",
+            "idle Busy",
+        ]
+    );
+}
+
+#[test]
+fn maps() {
+    let ((), records) = capture(|| {
+        let map: &[(&str, u8)] = &[("a", 1), ("b", 2)];
+        defmt::println!("{=[?:?]}", map);
+        defmt::println!("{=[?:?]:#x}", map);
+    });
+    assert_eq!(records[0].message, "{a: 1, b: 2}");
+    assert_eq!(records[1].message, "{a: 0x1, b: 0x2}");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn collections() {
+    extern crate alloc;
+
+    use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+
+    let ((), records) = capture(|| {
+        let map = BTreeMap::from([(2u8, true), (1, false)]);
+        defmt::println!("{}", map);
+        defmt::println!("{}", BTreeSet::from([3u16, 1, 2]));
+        defmt::println!("{}", VecDeque::from([1i8, -1]));
+        defmt::println!("{}", BTreeMap::<u8, u8>::new());
+    });
+    let messages = records
+        .iter()
+        .map(|record| record.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        ["{1: false, 2: true}", "[1, 2, 3]", "[1, -1]", "{}"]
+    );
+}
+
+#[cfg(all(feature = "fixed", feature = "half"))]
+#[test]
+fn ecosystem_types() {
+    use fixed::types::{I16F16, U8F8};
+
+    let ((), records) = capture(|| {
+        defmt::println!("{} {}", I16F16::from_num(-1.25), U8F8::from_num(3.5));
+        defmt::println!("{}", half::f16::from_f32(0.5));
+    });
+    let messages = records
+        .iter()
+        .map(|record| record.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, ["-1.25 3.5", "0.5"]);
+}
+
+#[test]
+fn metrics() {
+    let ((), records) = capture(|| {
+        defmt::counter!("rx_bytes", 64);
+        defmt::gauge!("temperature", 21.5);
+    });
+    assert_eq!(records[0].message, "rx_bytes += 64");
+    assert_eq!(records[1].message, "temperature = 21.5");
+}
+
+#[test]
+#[should_panic(expected = "panicked at 'sensor 3 failed'")]
+fn panic() {
+    defmt::panic!("sensor {=u8} failed", 3);
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: 1 + 1 == 3")]
+fn assert() {
+    defmt::assert!(1 + 1 == 3);
+}
@@ -0,0 +1,43 @@
+//! Tests for the `timestamp-delta` feature, which sends timestamps as deltas to the previous frame.
+//!
+//! Run with `cargo test -p defmt --features unstable-test,timestamp-delta --test timestamp_delta`.
+
+#![cfg(all(feature = "unstable-test", feature = "timestamp-delta"))]
+
+use defmt::export::{fetch_bytes, timestamp_delta, TIMESTAMP_KEYFRAME_INTERVAL};
+
+// the delta encoding only accepts a single unsigned integer
+defmt::timestamp!("{=u32:us}", 0);
+
+// NOTE the state of the encoder is global, so all checks are in a single test
+#[test]
+fn deltas_and_keyframes() {
+    // the first frame is a keyframe: a zero byte and the LEB128-encoded timestamp
+    timestamp_delta(1000);
+    assert_eq!(fetch_bytes(), [0, 0xe8, 0x07]);
+
+    // deltas are sent incremented by one, so that they never start with a zero byte
+    timestamp_delta(1500);
+    assert_eq!(fetch_bytes(), [0xf5, 0x03]);
+    timestamp_delta(1500);
+    assert_eq!(fetch_bytes(), [0x01]);
+
+    // a timestamp that goes backwards is sent as a keyframe
+    timestamp_delta(1200);
+    assert_eq!(fetch_bytes(), [0, 0xb0, 0x09]);
+
+    // as is every `TIMESTAMP_KEYFRAME_INTERVAL`th frame
+    let mut ticks = 1200;
+    for _ in 1..TIMESTAMP_KEYFRAME_INTERVAL {
+        ticks += 1;
+        timestamp_delta(ticks);
+        assert_eq!(fetch_bytes(), [0x02]);
+    }
+    timestamp_delta(ticks + 1);
+    assert_eq!(fetch_bytes(), [0, 0xd0, 0x09]);
+
+    timestamp_delta(u64::MAX);
+    assert_eq!(fetch_bytes().len(), 10);
+    timestamp_delta(0);
+    assert_eq!(fetch_bytes(), [0, 0]);
+}
@@ -0,0 +1,5 @@
+#[derive(defmt::Format)]
+#[defmt(format = "{=u8} | {=u8}", self.0, self.1)]
+struct S(u8, u8);
+
+fn main() {}
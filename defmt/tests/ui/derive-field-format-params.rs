@@ -0,0 +1,7 @@
+#[derive(defmt::Format)]
+struct S {
+    #[defmt(format = "{=u8} {=u8}")]
+    f: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[derive(defmt::Format)]
+enum E {
+    A {
+        #[defmt(format = "{=u8}|")]
+        f: u8,
+    },
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,5 @@
+#[derive(defmt::Format)]
+#[defmt(transparnt)]
+struct S(u8);
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[derive(defmt::Format)]
+#[defmt(rename = "Mode")]
+enum E {
+    A,
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[derive(defmt::Format)]
+struct S(#[defmt(rename = "x")] u8);
+
+fn main() {}
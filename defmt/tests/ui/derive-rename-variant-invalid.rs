@@ -0,0 +1,8 @@
+#[derive(defmt::Format)]
+enum E {
+    #[defmt(rename = "A|B")]
+    A,
+    B,
+}
+
+fn main() {}
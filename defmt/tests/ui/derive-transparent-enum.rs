@@ -0,0 +1,7 @@
+#[derive(defmt::Format)]
+#[defmt(transparent)]
+enum E {
+    A(u8),
+}
+
+fn main() {}
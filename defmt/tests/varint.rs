@@ -0,0 +1,98 @@
+//! Tests for the `varint` feature, which LEB128-encodes integers, lengths and string indices.
+//!
+//! Run with `cargo test -p defmt --features unstable-test,varint --test varint`.
+//!
+//! NOTE the mocked string index starts at 0 in every test, so it fits into a single byte.
+
+#![cfg(all(feature = "unstable-test", feature = "varint"))]
+
+use defmt::{export::fetch_string_index, write};
+
+fn index() -> u8 {
+    fetch_string_index() as u8
+}
+
+#[test]
+fn unsigned() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    write!(
+        g,
+        "{=u16} {=u32} {=u64} {=usize}",
+        127u16,
+        300u32,
+        u64::MAX,
+        0usize
+    );
+    let mut expected = vec![index, 127, 0xac, 0x02];
+    expected.extend([0xff; 9]);
+    expected.extend([0x01, 0]);
+    assert_eq!(defmt::export::fetch_bytes(), expected);
+}
+
+#[test]
+fn signed() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    write!(g, "{=i16} {=i32} {=i64}", 0i16, -65i32, 64i64);
+    assert_eq!(
+        defmt::export::fetch_bytes(),
+        [
+            index, //
+            0,     // 0
+            0x81, 0x01, // -65, zigzag-encoded as 129
+            0x80, 0x01, // 64, zigzag-encoded as 128
+        ]
+    );
+
+    let g = defmt::export::make_formatter();
+    write!(g, "{=i128}", i128::MIN);
+    let mut expected = vec![index + 1];
+    expected.extend([0xff; 18]);
+    expected.push(0x03);
+    assert_eq!(defmt::export::fetch_bytes(), expected);
+}
+
+#[test]
+fn fixed_width() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    write!(
+        g,
+        "{=u8} {=i8} {=u24} {=f32} {=char}",
+        200u8, -1i8, 1u32, 1.0f32, 'a'
+    );
+    assert_eq!(
+        defmt::export::fetch_bytes(),
+        [
+            index, //
+            200,   // u8
+            0xff,  // i8
+            1, 0, 0, // u24
+            0, 0, 0x80, 0x3f, // f32
+            b'a', 0, 0, 0, // char
+        ]
+    );
+}
+
+#[test]
+fn lengths_and_indices() {
+    let index = index();
+    let g = defmt::export::make_formatter();
+    write!(g, "{=[u8]} {=str} {=?}", [1u8, 2][..], "hi", 200u16);
+    assert_eq!(
+        defmt::export::fetch_bytes(),
+        [
+            index, //
+            2,
+            1,
+            2, // [u8]
+            2,
+            b'h',
+            b'i',      // str
+            index + 1, // "{=u16}"
+            0xc8,
+            0x01, // 200
+        ]
+    );
+}
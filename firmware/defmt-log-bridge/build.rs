@@ -0,0 +1,12 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DEFMT_LOG");
+
+    // the records are filtered at runtime against the user's `DEFMT_LOG`, so the defmt macros of
+    // this crate must not filter them again
+    if let Ok(filter) = env::var("DEFMT_LOG") {
+        println!("cargo:rustc-env=DEFMT_LOG_BRIDGE_FILTER={filter}");
+    }
+    println!("cargo:rustc-env=DEFMT_LOG=trace");
+}
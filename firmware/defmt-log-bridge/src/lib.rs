@@ -0,0 +1,236 @@
+//! Forwards the records of the [`log`](https://docs.rs/log) crate to [`defmt`](https://github.com/knurling-rs/defmt).
+//!
+//! This makes the log messages of third-party crates that use `log::info!` and friends show up
+//! in the defmt output of the application. Call `init` once, before the first record is logged:
+//!
+//! ``` no_run
+//! // src/main.rs or src/bin/my-app.rs
+//!
+//! defmt_log_bridge::init().unwrap();
+//!
+//! log::info!("Hello from the `log` crate");
+//! ```
+//!
+//! Every record becomes a defmt frame with the same level. Its message is prefixed with the target
+//! of the record and, if it differs from the target, the module path; the `fmt::Arguments` of
+//! the record are formatted on the device, using [`defmt::Display2Format`].
+//!
+//! Records are filtered at runtime, following the same rules as the compile-time filtering of
+//! the defmt macros: `DEFMT_LOG` (read when this crate is built) is matched against the target of
+//! the record, which defaults to its module path.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+use defmt::Display2Format;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// The value of `DEFMT_LOG` when this crate was built.
+///
+/// The build script moves it out of `DEFMT_LOG`, which enables all log levels for this crate.
+const FILTER: Option<&str> = option_env!("DEFMT_LOG_BRIDGE_FILTER");
+
+/// A `log::Log` implementation that forwards records to defmt.
+pub struct Logger;
+
+static LOGGER: Logger = Logger;
+
+/// Registers the bridge as the logger of the `log` crate.
+///
+/// Fails if a logger has already been registered.
+#[cfg(target_has_atomic = "ptr")]
+pub fn init() -> Result<(), log::SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(max_level(FILTER.unwrap_or_default()));
+    Ok(())
+}
+
+/// Registers the bridge as the logger of the `log` crate, on targets without atomic pointers.
+///
+/// # Safety
+///
+/// Must not be called concurrently with any other function of the `log` crate, see
+/// [`log::set_logger_racy`].
+pub unsafe fn init_racy() -> Result<(), log::SetLoggerError> {
+    log::set_logger_racy(&LOGGER)?;
+    log::set_max_level_racy(max_level(FILTER.unwrap_or_default()));
+    Ok(())
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= target_level(FILTER.unwrap_or_default(), metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        macro_rules! forward {
+            ($($args:tt)*) => {
+                match record.level() {
+                    Level::Error => defmt::error!($($args)*),
+                    Level::Warn => defmt::warn!($($args)*),
+                    Level::Info => defmt::info!($($args)*),
+                    Level::Debug => defmt::debug!($($args)*),
+                    Level::Trace => defmt::trace!($($args)*),
+                }
+            };
+        }
+
+        let target = record.target();
+        let args = Display2Format(record.args());
+        match record.module_path() {
+            Some(module) if module != target => {
+                forward!("{=str} ({=str}): {}", target, module, args)
+            }
+            _ => forward!("{=str}: {}", target, args),
+        }
+    }
+
+    fn flush(&self) {
+        defmt::flush()
+    }
+}
+
+/// Returns the most verbose level that `target` may log at, according to the `DEFMT_LOG` value
+/// `filter`.
+///
+/// Like the defmt macros, the most specific module path of `filter` that contains `target`
+/// applies; if there is none, the rightmost level without a module path applies, and `error`
+/// if there is no such level either.
+fn target_level(filter: &str, target: &str) -> LevelFilter {
+    let mut fallback = None;
+    let mut most_specific: Option<(&str, LevelFilter)> = None;
+    for entry in entries(filter) {
+        match entry {
+            (None, level) => {
+                fallback.get_or_insert(level);
+            }
+            (Some(path), level) => {
+                let is_more_specific =
+                    most_specific.is_none_or(|(best, _)| path.len() > best.len());
+                if is_more_specific && is_inside(target, path) {
+                    most_specific = Some((path, level));
+                }
+            }
+        }
+    }
+    most_specific
+        .map(|(_, level)| level)
+        .or(fallback)
+        .unwrap_or(LevelFilter::Error)
+}
+
+/// Returns the most verbose level that any target may log at, according to `filter`.
+fn max_level(filter: &str) -> LevelFilter {
+    let mut fallback = None;
+    let mut max = LevelFilter::Off;
+    for entry in entries(filter) {
+        match entry {
+            (None, level) => {
+                fallback.get_or_insert(level);
+            }
+            (Some(_), level) => max = max.max(level),
+        }
+    }
+    max.max(fallback.unwrap_or(LevelFilter::Error))
+}
+
+/// Parses the `DEFMT_LOG` value `filter` from right to left, skipping entries with an unknown
+/// level.
+fn entries(filter: &str) -> impl Iterator<Item = (Option<&str>, LevelFilter)> {
+    filter
+        .rsplit(',')
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.rsplit_once('=') {
+            Some((path, level)) => Some((Some(path), parse_level(level)?)),
+            None => match parse_level(entry) {
+                Some(level) => Some((None, level)),
+                // a module path without a level enables all levels
+                None => Some((Some(entry), LevelFilter::Trace)),
+            },
+        })
+}
+
+fn parse_level(input: &str) -> Option<LevelFilter> {
+    Some(match input {
+        "off" => LevelFilter::Off,
+        "error" => LevelFilter::Error,
+        "warn" => LevelFilter::Warn,
+        "info" => LevelFilter::Info,
+        "debug" => LevelFilter::Debug,
+        "trace" => LevelFilter::Trace,
+        _ => return None,
+    })
+}
+
+/// Returns `true` if `target` is the module `path` or one of its submodules.
+fn is_inside(target: &str, path: &str) -> bool {
+    match target.strip_prefix(path) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_path_boundaries() {
+        let filter = "app::net=debug";
+        assert_eq!(target_level(filter, "app::net"), LevelFilter::Debug);
+        assert_eq!(target_level(filter, "app::net::tcp"), LevelFilter::Debug);
+        // `app::network` starts with `app::net`, but isn't inside of it
+        assert_eq!(target_level(filter, "app::network"), LevelFilter::Error);
+        assert_eq!(target_level(filter, "app"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let filter = "app::net::tcp=trace,app=warn,app::net=info";
+        assert_eq!(
+            target_level(filter, "app::net::tcp::tx"),
+            LevelFilter::Trace
+        );
+        assert_eq!(target_level(filter, "app::net::udp"), LevelFilter::Info);
+        assert_eq!(target_level(filter, "app::main"), LevelFilter::Warn);
+        assert_eq!(max_level(filter), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn crate_only_and_global_levels() {
+        // a module path without a level enables all levels, but only for that module
+        assert_eq!(target_level("app", "app::main"), LevelFilter::Trace);
+        assert_eq!(target_level("app", "embassy_net"), LevelFilter::Error);
+
+        // a level without a module path applies to all targets; the rightmost one wins
+        let filter = "debug,app=trace,warn";
+        assert_eq!(target_level(filter, "embassy_net"), LevelFilter::Warn);
+        assert_eq!(target_level(filter, "app"), LevelFilter::Trace);
+        assert_eq!(max_level(filter), LevelFilter::Trace);
+
+        // nothing set
+        assert_eq!(target_level("", "app"), LevelFilter::Error);
+        assert_eq!(max_level(""), LevelFilter::Error);
+    }
+
+    #[test]
+    fn off() {
+        let filter = "info,app::noisy=off";
+        assert_eq!(target_level(filter, "app::noisy::driver"), LevelFilter::Off);
+        assert_eq!(target_level(filter, "app"), LevelFilter::Info);
+        assert_eq!(max_level(filter), LevelFilter::Info);
+
+        assert_eq!(target_level("off", "app"), LevelFilter::Off);
+        assert_eq!(max_level("off"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn unknown_levels_are_skipped() {
+        let filter = "app=info,app=verbose";
+        assert_eq!(target_level(filter, "app"), LevelFilter::Info);
+    }
+}
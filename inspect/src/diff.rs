@@ -0,0 +1,261 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{tag_name, Elf, Entry};
+
+/// Differences between the log statements of two ELF files.
+#[derive(Serialize)]
+pub struct Diff {
+    encoding: Option<Change<&'static str>>,
+    timestamp: Option<Change<Option<String>>>,
+    added: Vec<Entry>,
+    removed: Vec<Entry>,
+    changed: Vec<Change<Entry>>,
+    unchanged: usize,
+    /// Number of unchanged log statements whose table index is different in the new ELF.
+    reindexed: usize,
+}
+
+#[derive(Serialize)]
+struct Change<T> {
+    old: T,
+    new: T,
+}
+
+/// Compares the log statements of `old` and `new`.
+///
+/// Statements with the same level, format string and module are considered unchanged, even if they
+/// moved to a different line. Of the remaining statements, the ones found at the same source
+/// location in both files are considered changed. Lines added or removed above a statement are
+/// accounted for using the closest preceding unchanged statement of the same file.
+pub fn diff(old: Elf, new: Elf) -> Diff {
+    let encoding = (old.encoding != new.encoding).then_some(Change {
+        old: old.encoding,
+        new: new.encoding,
+    });
+    let timestamp = (old.timestamp != new.timestamp).then(|| Change {
+        old: old.timestamp.clone(),
+        new: new.timestamp.clone(),
+    });
+
+    let mut removed = old
+        .entries
+        .into_iter()
+        .filter(Entry::is_log_statement)
+        .collect::<Vec<_>>();
+    let mut added = new
+        .entries
+        .into_iter()
+        .filter(Entry::is_log_statement)
+        .collect::<Vec<_>>();
+
+    let (mut unchanged, mut reindexed) = (0, 0);
+    // `(file, old line, new line)` of the unchanged statements
+    let mut anchors = Vec::new();
+    removed.retain(|old| {
+        let pos = added.iter().position(|new| {
+            new.tag == old.tag && new.format == old.format && new.module == old.module
+        });
+        match pos {
+            Some(pos) => {
+                let new = added.remove(pos);
+                unchanged += 1;
+                if new.index != old.index {
+                    reindexed += 1;
+                }
+                if let (Some(file), Some(old_line), Some(new_line)) =
+                    (&new.file, old.line, new.line)
+                {
+                    anchors.push((file.clone(), old_line, new_line));
+                }
+                false
+            }
+            None => true,
+        }
+    });
+
+    let mut changed = Vec::new();
+    removed.retain(|old| {
+        let (Some(file), Some(line)) = (&old.file, old.line) else {
+            return true;
+        };
+        let shift = anchors
+            .iter()
+            .filter(|(anchor, old_line, _)| anchor == file && *old_line <= line)
+            .max_by_key(|(_, old_line, _)| *old_line)
+            .map_or(0, |(_, old_line, new_line)| {
+                *new_line as i64 - *old_line as i64
+            });
+        let line = (line as i64 + shift) as u64;
+        let pos = added
+            .iter()
+            .position(|new| new.file.as_ref() == Some(file) && new.line == Some(line));
+        match pos {
+            Some(pos) => {
+                changed.push(Change {
+                    old: old.clone(),
+                    new: added.remove(pos),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+
+    Diff {
+        encoding,
+        timestamp,
+        added,
+        removed,
+        changed,
+        unchanged,
+        reindexed,
+    }
+}
+
+impl Diff {
+    pub fn print(&self, sink: &mut impl Write) -> anyhow::Result<()> {
+        if let Some(Change { old, new }) = &self.encoding {
+            writeln!(sink, "encoding: {old} -> {new}")?;
+        }
+        if let Some(Change { old, new }) = &self.timestamp {
+            writeln!(sink, "timestamp: {old:?} -> {new:?}")?;
+        }
+
+        for entry in &self.removed {
+            print_entry(sink, '-', entry)?;
+        }
+        for entry in &self.added {
+            print_entry(sink, '+', entry)?;
+        }
+        for Change { old, new } in &self.changed {
+            print_entry(sink, '~', old)?;
+            print_entry(sink, '>', new)?;
+        }
+
+        writeln!(
+            sink,
+            "{} added, {} removed, {} changed, {} unchanged ({} with a different index)",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged,
+            self.reindexed,
+        )?;
+        Ok(())
+    }
+}
+
+fn print_entry(sink: &mut impl Write, marker: char, entry: &Entry) -> io::Result<()> {
+    writeln!(
+        sink,
+        "{marker} {:7} {:30} {:?}",
+        tag_name(entry.tag),
+        entry.location(),
+        entry.format
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use defmt_decoder::Tag;
+
+    use super::*;
+
+    fn entry(index: usize, tag: Tag, format: &str, line: u64) -> Entry {
+        Entry {
+            index,
+            tag,
+            format: format.to_string(),
+            file: Some("src/main.rs".to_string()),
+            line: Some(line),
+            module: Some("app".to_string()),
+            max_size: None,
+        }
+    }
+
+    fn elf(entries: Vec<Entry>) -> Elf {
+        Elf {
+            encoding: "rzcobs",
+            timestamp: None,
+            entries,
+        }
+    }
+
+    fn formats(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.format.as_str()).collect()
+    }
+
+    #[test]
+    fn moved_statement_is_unchanged() {
+        let old = elf(vec![
+            entry(0, Tag::Info, "boot", 10),
+            entry(1, Tag::Warn, "low battery", 20),
+        ]);
+        // two lines were inserted above both statements, which also swapped their indices
+        let new = elf(vec![
+            entry(0, Tag::Warn, "low battery", 22),
+            entry(1, Tag::Info, "boot", 12),
+        ]);
+
+        let diff = diff(old, new);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+        assert_eq!((diff.unchanged, diff.reindexed), (2, 2));
+    }
+
+    #[test]
+    fn edited_format_string_is_changed() {
+        let old = elf(vec![
+            entry(0, Tag::Info, "boot", 10),
+            entry(1, Tag::Debug, "temp={=u8}", 30),
+        ]);
+        // the edited statement is found at its old line plus the shift of the `boot` anchor
+        let new = elf(vec![
+            entry(0, Tag::Info, "boot", 15),
+            entry(1, Tag::Debug, "temp={=f32}", 35),
+        ]);
+
+        let diff = diff(old, new);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        let [Change { old, new }] = diff.changed.as_slice() else {
+            panic!("expected exactly one changed statement");
+        };
+        assert_eq!((old.format.as_str(), old.line), ("temp={=u8}", Some(30)));
+        assert_eq!((new.format.as_str(), new.line), ("temp={=f32}", Some(35)));
+        assert_eq!((diff.unchanged, diff.reindexed), (1, 0));
+    }
+
+    #[test]
+    fn added_and_removed_statements() {
+        let old = elf(vec![
+            entry(0, Tag::Info, "boot", 10),
+            entry(1, Tag::Error, "fault", 20),
+            // not a log statement, so not part of the diff
+            entry(2, Tag::Derived, "Point {=u8}", 0),
+        ]);
+        let new = elf(vec![
+            entry(0, Tag::Info, "boot", 10),
+            entry(1, Tag::Trace, "tick", 40),
+        ]);
+
+        let diff = diff(old, new);
+        assert_eq!(formats(&diff.removed), ["fault"]);
+        assert_eq!(formats(&diff.added), ["tick"]);
+        assert!(diff.changed.is_empty());
+        assert_eq!((diff.unchanged, diff.reindexed), (1, 0));
+
+        let mut output = Vec::new();
+        diff.print(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"- error   src/main.rs:20                 "fault"
++ trace   src/main.rs:40                 "tick"
+1 added, 1 removed, 0 changed, 1 unchanged (0 with a different index)
+"#
+        );
+    }
+}
@@ -0,0 +1,235 @@
+mod diff;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use clap::{Parser, Subcommand};
+use defmt_decoder::{Encoding, MaxFrameSize, Table, Tag};
+use serde::Serialize;
+
+/// Lists and compares the defmt log statements contained in ELF files
+#[derive(Parser)]
+#[command(name = "defmt-inspect")]
+struct Opts {
+    /// Produce machine readable output
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List all interned strings and log statements of an ELF file
+    List {
+        elf: PathBuf,
+
+        /// Only list log statements (including `println!`)
+        #[arg(long)]
+        logs_only: bool,
+    },
+    /// Report log statements that were added, removed or changed between two ELF files
+    Diff { old: PathBuf, new: PathBuf },
+}
+
+fn main() -> anyhow::Result<()> {
+    let Opts { json, command } = Opts::parse();
+
+    match command {
+        Command::List { elf, logs_only } => {
+            let mut elf = load(&elf)?;
+            if logs_only {
+                elf.entries.retain(Entry::is_log_statement);
+            }
+            match json {
+                true => print_json(&elf),
+                false => print_list(&elf),
+            }
+        }
+        Command::Diff { old, new } => {
+            let diff = diff::diff(load(&old)?, load(&new)?);
+            match json {
+                true => print_json(&diff),
+                false => diff.print(&mut io::stdout().lock()),
+            }
+        }
+    }
+}
+
+/// The defmt-related contents of an ELF file.
+#[derive(Serialize)]
+pub struct Elf {
+    encoding: &'static str,
+    timestamp: Option<String>,
+    entries: Vec<Entry>,
+}
+
+/// An interned string of the `.defmt` table, plus the information derived from it.
+#[derive(Clone, Serialize)]
+pub struct Entry {
+    index: usize,
+    #[serde(serialize_with = "serialize_tag")]
+    tag: Tag,
+    format: String,
+    file: Option<String>,
+    line: Option<u64>,
+    module: Option<String>,
+    /// Upper bound of the encoded size of a log frame; only present for log statements.
+    #[serde(serialize_with = "serialize_max_size")]
+    max_size: Option<MaxFrameSize>,
+}
+
+impl Entry {
+    fn is_log_statement(&self) -> bool {
+        matches!(
+            self.tag,
+            Tag::Println
+                | Tag::Trace
+                | Tag::Debug
+                | Tag::Info
+                | Tag::Warn
+                | Tag::Error
+                | Tag::SpanEnter
+                | Tag::SpanExit
+                | Tag::Counter
+                | Tag::Gauge
+                | Tag::Histogram
+        )
+    }
+
+    fn location(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!("{file}:{line}"),
+            _ => "<unknown location>".to_string(),
+        }
+    }
+}
+
+fn serialize_tag<S: serde::Serializer>(tag: &Tag, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(tag_name(*tag))
+}
+
+fn serialize_max_size<S: serde::Serializer>(
+    size: &Option<MaxFrameSize>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Size {
+        bytes: usize,
+        unbounded: bool,
+    }
+
+    size.map(|size| Size {
+        bytes: size.fixed,
+        unbounded: size.unbounded,
+    })
+    .serialize(serializer)
+}
+
+fn tag_name(tag: Tag) -> &'static str {
+    match tag {
+        Tag::Prim => "prim",
+        Tag::Derived => "derived",
+        Tag::Bitflags => "bitflags",
+        Tag::Write => "write",
+        Tag::Str => "str",
+        Tag::Timestamp => "timestamp",
+        Tag::BitflagsValue => "bitflags_value",
+        Tag::Println => "println",
+        Tag::SpanEnter => "span_enter",
+        Tag::SpanExit => "span_exit",
+        Tag::Counter => "counter",
+        Tag::Gauge => "gauge",
+        Tag::Histogram => "histogram",
+        Tag::Trace => "trace",
+        Tag::Debug => "debug",
+        Tag::Info => "info",
+        Tag::Warn => "warn",
+        Tag::Error => "error",
+    }
+}
+
+/// Reads the `.defmt` table of the ELF file at `path`.
+fn load(path: &Path) -> anyhow::Result<Elf> {
+    let bytes = fs::read(path)?;
+    let table = Table::parse(&bytes)?
+        .ok_or_else(|| anyhow!("{}: .defmt data not found", path.display()))?;
+    let locs = table.get_locations(&bytes)?;
+    let encoding = table.encoding();
+
+    let entries = table
+        .entries()
+        .map(|(index, entry)| {
+            let loc = locs.get(&(index as u64));
+            let mut entry = Entry {
+                index,
+                tag: entry.string().tag(),
+                format: entry.string().string().to_string(),
+                file: loc.map(|loc| loc.file.display().to_string()),
+                line: loc.map(|loc| loc.line),
+                module: loc.map(|loc| loc.module.clone()),
+                max_size: None,
+            };
+            if entry.is_log_statement() {
+                entry.max_size = table.max_frame_size(index).map(|mut size| {
+                    size.fixed = encoding.max_encoded_size(size.fixed);
+                    size
+                });
+            }
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Elf {
+        encoding: match encoding {
+            Encoding::Raw => "raw",
+            Encoding::Rzcobs => "rzcobs",
+            _ => "unknown",
+        },
+        timestamp: table
+            .timestamp_entry()
+            .map(|entry| entry.string().string().to_string()),
+        entries,
+    })
+}
+
+fn print_list(elf: &Elf) -> anyhow::Result<()> {
+    let mut sink = io::stdout().lock();
+    writeln!(sink, "encoding: {}", elf.encoding)?;
+    if let Some(timestamp) = &elf.timestamp {
+        writeln!(sink, "timestamp: {timestamp:?}")?;
+    }
+    writeln!(sink)?;
+    writeln!(
+        sink,
+        "{:>5}  {:14} {:>8}  {:30} format",
+        "index", "tag", "max size", "location"
+    )?;
+    for entry in &elf.entries {
+        writeln!(
+            sink,
+            "{:>5}  {:14} {:>8}  {:30} {:?}",
+            entry.index,
+            tag_name(entry.tag),
+            entry
+                .max_size
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            entry.location(),
+            entry.format,
+        )?;
+    }
+    Ok(())
+}
+
+fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+    let mut sink = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut sink, value)?;
+    writeln!(sink)?;
+    Ok(())
+}
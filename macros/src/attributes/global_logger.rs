@@ -10,6 +10,13 @@ pub(crate) fn expand(args: TokenStream, item: TokenStream) -> TokenStream {
 
     let strukt = parse_macro_input!(item as ItemStruct);
 
+    if cfg!(feature = "std") {
+        abort!(
+            strukt.ident,
+            "`#[global_logger]` can't be used together with the `std` feature of `defmt`, which provides its own logger"
+        );
+    }
+
     validate(&strukt);
 
     codegen(&strukt)
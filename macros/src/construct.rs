@@ -37,12 +37,16 @@ pub(crate) fn interned_string(string: &str, tag: &str, is_log_statement: bool) -
     };
 
     let var_addr = if cfg!(feature = "unstable-test") {
-        quote!({ defmt::export::fetch_add_string_index() })
+        quote!({ defmt::export::fetch_add_string_index() as u32 })
+    } else if cfg!(feature = "std") {
+        host_string(&var_name, string, tag)
+    } else if cfg!(feature = "stable-ids") {
+        stable_string(&var_name, string, tag)
     } else {
         let var_item = static_variable(&var_name, string, tag);
         quote!({
             #var_item
-            &#var_name as *const u8 as u16
+            &#var_name as *const u8 as u32
         })
     };
 
@@ -82,6 +86,39 @@ pub(crate) fn static_variable(name: &Ident2, data: &str, tag: &str) -> TokenStre
     )
 }
 
+/// With the `std` feature there is no `.defmt` section; instead, strings are assigned an index
+/// when they are used for the first time.
+pub(crate) fn host_string(name: &Ident2, data: &str, tag: &str) -> TokenStream2 {
+    let tag = format!("defmt_{tag}");
+    quote!({
+        static #name: defmt::export::HostString = defmt::export::HostString::new(
+            #tag,
+            #data,
+            ::core::file!(),
+            ::core::line!(),
+            ::core::module_path!(),
+        );
+        #name.index()
+    })
+}
+
+/// With the `stable-ids` feature the index is a hash of the string instead of its address. Nothing
+/// references the symbol then, so it's placed in the `.defmt.end` section that the linker keeps.
+pub(crate) fn stable_string(name: &Ident2, data: &str, tag: &str) -> TokenStream2 {
+    let sym_name = mangled_symbol_name(tag, data);
+    let section = linker_section(false, Some("end"), &sym_name);
+    let section_for_macos = linker_section(true, Some("end"), &sym_name);
+    let index = symbol::stable_id(tag, data);
+
+    quote!({
+        #[cfg_attr(target_os = "macos", link_section = #section_for_macos)]
+        #[cfg_attr(not(target_os = "macos"), link_section = #section)]
+        #[export_name = #sym_name]
+        static #name: u8 = 0;
+        #index
+    })
+}
+
 pub(crate) fn string_literal(content: &str) -> LitStr {
     LitStr::new(content, Span2::call_site())
 }
@@ -6,6 +6,12 @@ pub(crate) fn mangled(defmt_tag: &str, data: &str) -> String {
     Symbol::new(defmt_tag, data).mangle()
 }
 
+/// The index of the symbol with the `stable-ids` feature, which the decoder recomputes from it.
+pub(crate) fn stable_id(defmt_tag: &str, data: &str) -> u32 {
+    let symbol = Symbol::new(defmt_tag, data);
+    defmt_parser::stable_id(&symbol.tag, &symbol.crate_name, symbol.data)
+}
+
 struct Symbol<'a> {
     /// Name of the Cargo package in which the symbol is being instantiated. Used for avoiding
     /// symbol name collisions.
@@ -21,6 +27,9 @@ struct Symbol<'a> {
     /// * `defmt_println` for logging messages that are always displayed.
     /// * `defmt_trace`, `defmt_debug`, `defmt_info`, `defmt_warn`, `defmt_error` for logging
     ///   messages used at the different log levels.
+    /// * `defmt_span_enter`, `defmt_span_exit` for the frames marking the start and end of a span.
+    /// * `defmt_counter`, `defmt_gauge`, `defmt_histogram` for metric updates; the data is the name
+    ///   of the metric.
     /// * `defmt_bitflags` indicates that a format string was generated by a `defmt::bitflags!`
     ///   invocation, and that the decoder should look up possible flags in the binary.
     ///   The data string is of the format `NAME@REPR#NUM`, where `NAME` is the name of the bitflags
@@ -3,16 +3,36 @@ use proc_macro_error::abort_call_site;
 use quote::quote;
 use syn::{parse_macro_input, Data, DeriveInput};
 
+use self::attributes::ContainerAttributes;
+
+mod attributes;
 mod codegen;
 
 pub(crate) fn expand(input: TokenStream) -> TokenStream {
     let mut input = parse_macro_input!(input as DeriveInput);
 
+    let attributes = match ContainerAttributes::parse(&input)
+        .and_then(|attributes| attributes.validate(&input).map(|()| attributes))
+    {
+        Ok(attributes) => attributes,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
     let ident = &input.ident;
-    let encode_data = match &input.data {
-        Data::Enum(data) => codegen::encode_enum_data(ident, data),
-        Data::Struct(data) => codegen::encode_struct_data(ident, data),
-        Data::Union(_) => abort_call_site!("`#[derive(Format)]` does not support unions"),
+    let encode_data = match (&input.data, &attributes.format) {
+        (Data::Union(_), _) => abort_call_site!("`#[derive(Format)]` does not support unions"),
+        (_, Some(format)) => codegen::encode_container_format(format),
+        (Data::Struct(data), None) if attributes.transparent.is_some() => {
+            codegen::encode_transparent_struct(data)
+        }
+        (Data::Enum(data), None) => codegen::encode_enum_data(ident, data),
+        (Data::Struct(data), None) => {
+            let name = match &attributes.rename {
+                Some(rename) => rename.value(),
+                None => ident.to_string(),
+            };
+            codegen::encode_struct_data(&name, data)
+        }
     };
 
     let codegen::EncodeData { format_tag, stmts } = match encode_data {
@@ -24,7 +44,7 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
         impl_generics,
         type_generics,
         where_clause,
-    } = codegen::Generics::codegen(&mut input.generics);
+    } = codegen::Generics::codegen(&mut input.generics, attributes.bound.as_ref());
 
     quote!(
         impl #impl_generics defmt::Format for #ident #type_generics #where_clause {
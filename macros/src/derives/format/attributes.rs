@@ -0,0 +1,302 @@
+use syn::{
+    parse::ParseStream, punctuated::Punctuated, Data, DeriveInput, Error, Expr, Field, Ident, Lit,
+    LitStr, Meta, NestedMeta, Token, Variant, WherePredicate,
+};
+
+/// The `#[defmt(..)]` attributes of the type that `Format` is derived for.
+#[derive(Default)]
+pub(crate) struct ContainerAttributes {
+    /// `#[defmt(transparent)]`, formats the type like its only field.
+    pub(crate) transparent: Option<Ident>,
+    /// `#[defmt(bound = "T: Format")]`, replaces the `Format` bounds of the type parameters.
+    pub(crate) bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    /// `#[defmt(format = "..", args..)]`, formats the type like `write!` would.
+    pub(crate) format: Option<ContainerFormat>,
+    /// `#[defmt(rename = "..")]`, the name that is shown for a struct.
+    pub(crate) rename: Option<LitStr>,
+}
+
+pub(crate) struct ContainerFormat {
+    pub(crate) format_string: LitStr,
+    pub(crate) args: Vec<Expr>,
+}
+
+impl ContainerAttributes {
+    pub(crate) fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = ContainerAttributes::default();
+        for attr in input.attrs.iter().filter(|a| a.path.is_ident("defmt")) {
+            attr.parse_args_with(|input: ParseStream| attributes.parse_options(input))?;
+        }
+        Ok(attributes)
+    }
+
+    /// Parses the comma-separated options of one attribute; the arguments of `format` run until
+    /// the end of the attribute.
+    fn parse_options(&mut self, input: ParseStream) -> syn::Result<()> {
+        if input.is_empty() {
+            return Err(input.error(CONTAINER_EXPECTED));
+        }
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            match &*option.to_string() {
+                "transparent" if self.transparent.is_none() => self.transparent = Some(option),
+                "bound" if self.bound.is_none() => {
+                    let bound = parse_value(input)?;
+                    self.bound = Some(bound.parse_with(Punctuated::parse_terminated)?);
+                }
+                "format" if self.format.is_none() => {
+                    let format_string = parse_value(input)?;
+                    validate_format(&format_string)?;
+                    let mut args = vec![];
+                    while !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                        if input.is_empty() {
+                            break;
+                        }
+                        args.push(input.parse()?);
+                    }
+                    self.format = Some(ContainerFormat {
+                        format_string,
+                        args,
+                    });
+                }
+                "rename" if self.rename.is_none() => self.rename = Some(parse_value(input)?),
+                "transparent" | "bound" | "format" | "rename" => {
+                    return Err(Error::new(option.span(), format!("duplicate `{option}`")))
+                }
+                _ => return Err(Error::new(option.span(), CONTAINER_EXPECTED)),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the options fit each other and the type.
+    pub(crate) fn validate(&self, input: &DeriveInput) -> syn::Result<()> {
+        if let Some(rename) = &self.rename {
+            validate_name(rename)?;
+            if self.transparent.is_some() || self.format.is_some() {
+                return Err(Error::new_spanned(
+                    rename,
+                    "`rename` can't be combined with `transparent` or `format`",
+                ));
+            }
+            if let Data::Enum(_) = &input.data {
+                return Err(Error::new_spanned(
+                    rename,
+                    "the name of an enum isn't shown; use `rename` on its variants instead",
+                ));
+            }
+        }
+
+        let Some(transparent) = &self.transparent else {
+            return Ok(());
+        };
+        if let Some(format) = &self.format {
+            return Err(Error::new_spanned(
+                &format.format_string,
+                "`format` can't be combined with `transparent`",
+            ));
+        }
+        let Data::Struct(data) = &input.data else {
+            return Err(Error::new_spanned(
+                transparent,
+                "`transparent` is only supported on structs",
+            ));
+        };
+        let mut shown = vec![];
+        for field in &data.fields {
+            match FieldAttributes::parse(field)? {
+                FieldAttributes {
+                    kind: None,
+                    rename: None,
+                } => shown.push(field),
+                FieldAttributes {
+                    kind: Some(FieldAttribute::Skip),
+                    rename: None,
+                } => {}
+                _ => {
+                    return Err(Error::new_spanned(
+                        field,
+                        "the field of a `transparent` struct can only have `#[defmt(skip)]`",
+                    ))
+                }
+            }
+        }
+        if shown.len() != 1 {
+            return Err(Error::new_spanned(
+                transparent,
+                "`transparent` requires exactly one field that is not skipped",
+            ));
+        }
+        Ok(())
+    }
+}
+
+const CONTAINER_EXPECTED: &str = "expected `transparent`, `bound`, `format` or `rename`";
+
+/// Parses the `= "value"` of an option.
+fn parse_value(input: ParseStream) -> syn::Result<LitStr> {
+    input.parse::<Token![=]>()?;
+    input.parse()
+}
+
+/// Checks that the name of `#[defmt(rename = "..")]` can be put into the derived format string.
+fn validate_name(name: &LitStr) -> syn::Result<()> {
+    let value = name.value();
+    if value.is_empty() || value.contains(['{', '}', '|']) {
+        return Err(Error::new_spanned(
+            name,
+            "expected a name that is not empty and doesn't contain `{`, `}` or `|`",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a `format = ".."` string can be put into the derived format string, where `|`
+/// separates the variants of an enum.
+fn validate_format(format: &LitStr) -> syn::Result<()> {
+    if format.value().contains('|') {
+        return Err(Error::new_spanned(
+            format,
+            "the format string of a derived `Format` can't contain `|`",
+        ));
+    }
+    Ok(())
+}
+
+/// Parses the `#[defmt(rename = "..")]` attribute of an enum variant.
+pub(crate) fn parse_variant_rename(variant: &Variant) -> syn::Result<Option<LitStr>> {
+    let mut rename = None;
+    for attr in variant.attrs.iter().filter(|a| a.path.is_ident("defmt")) {
+        attr.parse_args_with(|input: ParseStream| {
+            let option: Ident = input.parse()?;
+            if option != "rename" {
+                return Err(Error::new(option.span(), "expected `rename = \"..\"`"));
+            } else if rename.is_some() {
+                return Err(Error::new(option.span(), "duplicate `rename`"));
+            }
+            let name = parse_value(input)?;
+            validate_name(&name)?;
+            rename = Some(name);
+            Ok(())
+        })?;
+    }
+    Ok(rename)
+}
+
+/// The `#[defmt(..)]` attribute of a field.
+#[derive(Default)]
+pub(crate) struct FieldAttributes {
+    /// `Debug2Format`, `Display2Format`, `skip` or `format`, which change how the field is shown
+    pub(crate) kind: Option<FieldAttribute>,
+    /// `#[defmt(rename = "..")]`, the name that is shown for a named field
+    pub(crate) rename: Option<LitStr>,
+}
+
+/// An option of the `#[defmt(..)]` attribute of a field that changes how the field is shown.
+pub(crate) enum FieldAttribute {
+    /// `#[defmt(Debug2Format)]`
+    Debug2Format,
+    /// `#[defmt(Display2Format)]`
+    Display2Format,
+    /// `#[defmt(skip)]`, leaves the field out of the output
+    Skip,
+    /// `#[defmt(format = "{=u32:x}")]`, formats the field with a format string that has a single
+    /// parameter
+    Format(LitStr),
+}
+
+impl FieldAttributes {
+    /// Parses the `defmt` attribute of the field, e.g. `#[defmt(Debug2Format)]`.
+    ///
+    /// Returns `Err` if we can't parse a valid defmt attribute, and the default (no options) if
+    /// there is no `defmt` attribute on the field.
+    pub(crate) fn parse(field: &Field) -> syn::Result<Self> {
+        const EXPECTED: &str =
+            "expected `Debug2Format`, `Display2Format`, `skip`, `format = \"..\"` \
+             or `rename = \"..\"`";
+
+        let attrs = field
+            .attrs
+            .iter()
+            .filter(|a| a.path.is_ident("defmt"))
+            .map(|a| a.parse_meta())
+            .collect::<syn::Result<Vec<_>>>()?;
+        if attrs.is_empty() {
+            return Ok(Self::default());
+        } else if attrs.len() > 1 {
+            return Err(Error::new_spanned(
+                field,
+                "multiple `defmt` attributes not supported",
+            ));
+        } // else attrs.len() == 1
+        let attr = &attrs[0];
+        let args = match attr {
+            Meta::List(list) => &list.nested,
+            bad => return Err(Error::new_spanned(bad, "unrecognized attribute")),
+        };
+        if args.is_empty() {
+            return Err(Error::new_spanned(attr, "expected an attribute argument"));
+        }
+
+        let mut attributes = Self::default();
+        for arg in args {
+            let kind = match arg {
+                NestedMeta::Meta(Meta::Path(arg)) if arg.is_ident("Debug2Format") => {
+                    FieldAttribute::Debug2Format
+                }
+                NestedMeta::Meta(Meta::Path(arg)) if arg.is_ident("Display2Format") => {
+                    FieldAttribute::Display2Format
+                }
+                NestedMeta::Meta(Meta::Path(arg)) if arg.is_ident("skip") => FieldAttribute::Skip,
+                NestedMeta::Meta(Meta::NameValue(arg)) if arg.path.is_ident("format") => {
+                    let format = expect_str(&arg.lit, "expected a format string")?;
+                    validate_format(&format)?;
+                    FieldAttribute::Format(format)
+                }
+                NestedMeta::Meta(Meta::NameValue(arg)) if arg.path.is_ident("rename") => {
+                    if attributes.rename.is_some() {
+                        return Err(Error::new_spanned(arg, "duplicate `rename`"));
+                    }
+                    let name = expect_str(&arg.lit, "expected a name")?;
+                    validate_name(&name)?;
+                    attributes.rename = Some(name);
+                    continue;
+                }
+                bad => return Err(Error::new_spanned(bad, EXPECTED)),
+            };
+            if attributes.kind.is_some() {
+                return Err(Error::new_spanned(
+                    arg,
+                    "only one of `Debug2Format`, `Display2Format`, `skip` and `format` can be used",
+                ));
+            }
+            attributes.kind = Some(kind);
+        }
+
+        if let Some(rename) = &attributes.rename {
+            if field.ident.is_none() {
+                return Err(Error::new_spanned(
+                    rename,
+                    "`rename` is only supported on named fields",
+                ));
+            } else if let Some(FieldAttribute::Skip) = attributes.kind {
+                return Err(Error::new_spanned(
+                    rename,
+                    "`rename` can't be combined with `skip`",
+                ));
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+fn expect_str(lit: &Lit, message: &str) -> syn::Result<LitStr> {
+    match lit {
+        Lit::Str(string) => Ok(string.clone()),
+        bad => Err(Error::new_spanned(bad, message)),
+    }
+}
@@ -1,10 +1,16 @@
+use defmt_parser::ParserMode;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse_quote, DataStruct, GenericParam, Ident, ImplGenerics, TypeGenerics, WhereClause};
+use syn::{
+    parse_quote, punctuated::Punctuated, DataStruct, Error, GenericParam, ImplGenerics, Index,
+    Token, TypeGenerics, WhereClause, WherePredicate,
+};
 
 pub(crate) use enum_data::encode as encode_enum_data;
 
-use crate::construct;
+use crate::{construct, function_like::log};
+
+use super::attributes::{ContainerFormat, FieldAttribute, FieldAttributes};
 
 mod enum_data;
 mod fields;
@@ -14,8 +20,9 @@ pub(crate) struct EncodeData {
     pub(crate) stmts: Vec<TokenStream2>,
 }
 
-pub(crate) fn encode_struct_data(ident: &Ident, data: &DataStruct) -> syn::Result<EncodeData> {
-    let mut format_string = ident.to_string();
+/// Encodes a struct; `name` is the name of the type, unless it is renamed.
+pub(crate) fn encode_struct_data(name: &str, data: &DataStruct) -> syn::Result<EncodeData> {
+    let mut format_string = name.to_string();
     let mut stmts = vec![];
     let mut field_patterns = vec![];
 
@@ -32,6 +39,57 @@ pub(crate) fn encode_struct_data(ident: &Ident, data: &DataStruct) -> syn::Resul
     Ok(EncodeData { format_tag, stmts })
 }
 
+/// Encodes `#[defmt(transparent)]` structs like their only field that is not skipped.
+pub(crate) fn encode_transparent_struct(data: &DataStruct) -> syn::Result<EncodeData> {
+    let mut field_patterns = vec![];
+    let mut shown = None;
+    for (index, field) in data.fields.iter().enumerate() {
+        let pattern = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(index);
+                quote!(#index)
+            }
+        };
+        if let Some(FieldAttribute::Skip) = FieldAttributes::parse(field)?.kind {
+            field_patterns.push(quote!(#pattern: _));
+        } else {
+            field_patterns.push(quote!(#pattern: inner));
+            shown = Some(&field.ty);
+        }
+    }
+    let ty = shown.expect("checked by `ContainerAttributes::validate`");
+
+    Ok(EncodeData {
+        format_tag: quote!(<#ty as defmt::Format>::_format_tag()),
+        stmts: vec![quote!(match self {
+            Self { #(#field_patterns),* } => defmt::Format::_format_data(inner),
+        })],
+    })
+}
+
+/// Encodes the arguments of `#[defmt(format = "..", args..)]` like `write!` does.
+pub(crate) fn encode_container_format(format: &ContainerFormat) -> syn::Result<EncodeData> {
+    let ContainerFormat {
+        format_string,
+        args,
+    } = format;
+    let string = format_string.value();
+    let fragments = defmt_parser::parse(&string, ParserMode::Strict)
+        .map_err(|e| Error::new_spanned(format_string, e))?;
+
+    let log::Codegen { patterns, exprs } =
+        log::Codegen::new(&fragments, args.len(), format_string.span());
+
+    let format_tag = construct::interned_string(&string, "derived", false);
+    let stmts = vec![quote!(match (#(&(#args)),*) {
+        (#(#patterns),*) => {
+            #(#exprs;)*
+        }
+    })];
+    Ok(EncodeData { format_tag, stmts })
+}
+
 pub(crate) struct Generics<'a> {
     pub(crate) impl_generics: ImplGenerics<'a>,
     pub(crate) type_generics: TypeGenerics<'a>,
@@ -39,18 +97,26 @@ pub(crate) struct Generics<'a> {
 }
 
 impl<'a> Generics<'a> {
-    pub(crate) fn codegen(generics: &'a mut syn::Generics) -> Self {
+    /// `bound` replaces the `Format` bounds of the type parameters, if given.
+    pub(crate) fn codegen(
+        generics: &'a mut syn::Generics,
+        bound: Option<&Punctuated<WherePredicate, Token![,]>>,
+    ) -> Self {
         let mut where_clause = generics.make_where_clause().clone();
         let (impl_generics, type_generics, _) = generics.split_for_impl();
 
-        // Extend where-clause with `Format` bounds for type parameters.
-        for param in &generics.params {
-            if let GenericParam::Type(ty) = param {
-                let ident = &ty.ident;
+        if let Some(bound) = bound {
+            where_clause.predicates.extend(bound.iter().cloned());
+        } else {
+            // Extend where-clause with `Format` bounds for type parameters.
+            for param in &generics.params {
+                if let GenericParam::Type(ty) = param {
+                    let ident = &ty.ident;
 
-                where_clause
-                    .predicates
-                    .push(parse_quote!(#ident: defmt::Format));
+                    where_clause
+                        .predicates
+                        .push(parse_quote!(#ident: defmt::Format));
+                }
             }
         }
 
@@ -3,7 +3,7 @@ use proc_macro_error::abort_call_site;
 use quote::quote;
 use syn::{DataEnum, Ident};
 
-use crate::construct;
+use crate::{construct, derives::format::attributes};
 
 use super::EncodeData;
 
@@ -29,7 +29,10 @@ pub(crate) fn encode(ident: &Ident, data: &DataEnum) -> syn::Result<EncodeData>
         } else {
             format_string.push('|');
         }
-        format_string.push_str(&variant_ident.to_string());
+        match attributes::parse_variant_rename(variant)? {
+            Some(name) => format_string.push_str(&name.value()),
+            None => format_string.push_str(&variant_ident.to_string()),
+        }
 
         let mut field_patterns = vec![];
         let encode_fields_stmts =
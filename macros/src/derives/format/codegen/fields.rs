@@ -1,10 +1,15 @@
 use std::fmt::Write as _;
 
+use defmt_parser::{Fragment, ParserMode};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{Field, Fields, Index, Meta, NestedMeta, Type};
+use syn::{Error, Fields, Ident, Index, LitStr, Type};
 
-use crate::consts;
+use crate::{
+    consts,
+    derives::format::attributes::{FieldAttribute, FieldAttributes},
+    function_like::log,
+};
 
 pub(crate) fn codegen(
     fields: &Fields,
@@ -21,117 +26,144 @@ pub(crate) fn codegen(
         return Ok(vec![]);
     }
 
-    if fields_are_named {
-        format_string.push_str(" {{ ");
-    } else {
-        format_string.push('(');
+    let attributes = fields
+        .iter()
+        .map(FieldAttributes::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let all_skipped = attributes
+        .iter()
+        .all(|attributes| matches!(attributes.kind, Some(FieldAttribute::Skip)));
+
+    if !all_skipped {
+        if fields_are_named {
+            format_string.push_str(" {{ ");
+        } else {
+            format_string.push('(');
+        }
     }
 
     let mut stmts = vec![];
     let mut is_first = true;
-    for (index, field) in fields.iter().enumerate() {
+    for (index, (field, attributes)) in fields.iter().zip(attributes).enumerate() {
+        let FieldAttributes {
+            kind: attribute,
+            rename,
+        } = attributes;
+        let ident = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("arg{}", index));
+        let pattern = match &field.ident {
+            Some(_) => quote!( #ident ),
+            None => {
+                let index = Index::from(index);
+                quote!( #index )
+            }
+        };
+
+        if let Some(FieldAttribute::Skip) = attribute {
+            patterns.push(quote!( #pattern: _ ));
+            continue;
+        }
+
         if is_first {
             is_first = false;
         } else {
             format_string.push_str(", ");
         }
 
-        let format_opt = get_defmt_format_option(field)?;
         let ty = as_native_type(&field.ty).unwrap_or_else(|| consts::TYPE_FORMAT.to_string());
-        let ident = field
-            .ident
-            .clone()
-            .unwrap_or_else(|| format_ident!("arg{}", index));
 
-        if let Some(FormatOption::Debug2Format) = format_opt {
-            stmts.push(quote!(defmt::export::fmt(&defmt::Debug2Format(&#ident))));
-        } else if let Some(FormatOption::Display2Format) = format_opt {
-            stmts.push(quote!(defmt::export::fmt(&defmt::Display2Format(&#ident))));
-        } else if ty == consts::TYPE_FORMAT {
-            stmts.push(quote!(defmt::export::fmt(#ident)));
-        } else {
-            let method = format_ident!("{}", ty);
-            stmts.push(quote!(defmt::export::#method(#ident)));
-        }
+        let param = match attribute {
+            Some(FieldAttribute::Debug2Format) => {
+                stmts.push(quote!(defmt::export::fmt(&defmt::Debug2Format(&#ident))));
+                None
+            }
+            Some(FieldAttribute::Display2Format) => {
+                stmts.push(quote!(defmt::export::fmt(&defmt::Display2Format(&#ident))));
+                None
+            }
+            Some(FieldAttribute::Format(field_format)) => {
+                stmts.push(encode_with_format(&field_format, &ident)?);
+                Some(field_format.value())
+            }
+            Some(FieldAttribute::Skip) => unreachable!(),
+            None if ty == consts::TYPE_FORMAT => {
+                stmts.push(quote!(defmt::export::fmt(#ident)));
+                None
+            }
+            None => {
+                let method = format_ident!("{}", ty);
+                stmts.push(quote!(defmt::export::#method(#ident)));
+                None
+            }
+        };
 
         if field.ident.is_some() {
             // Named field.
-            write!(format_string, "{ident}: {{={ty}:?}}").ok();
+            let param = param.unwrap_or_else(|| format!("{{={ty}:?}}"));
+            let name = rename.map_or_else(|| ident.to_string(), |name| name.value());
+            write!(format_string, "{name}: {param}").ok();
 
             patterns.push(quote!( #ident ));
         } else {
             // Unnamed (tuple) field.
-            write!(format_string, "{{={ty}}}").ok();
+            let param = param.unwrap_or_else(|| format!("{{={ty}}}"));
+            format_string.push_str(&param);
 
-            let index = Index::from(index);
-            patterns.push(quote!( #index: #ident ));
+            patterns.push(quote!( #pattern: #ident ));
         }
     }
 
-    if fields_are_named {
-        format_string.push_str(" }}");
-    } else {
-        format_string.push(')');
+    if !all_skipped {
+        if fields_are_named {
+            format_string.push_str(" }}");
+        } else {
+            format_string.push(')');
+        }
     }
 
     Ok(stmts)
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum FormatOption {
-    Debug2Format,
-    Display2Format,
-}
+/// Encodes the field `ident` with the format string of `#[defmt(format = "..")]`, which must have
+/// exactly one parameter.
+fn encode_with_format(field_format: &LitStr, ident: &Ident) -> syn::Result<TokenStream2> {
+    let string = field_format.value();
+    let fragments = defmt_parser::parse(&string, ParserMode::Strict)
+        .map_err(|e| Error::new_spanned(field_format, e))?;
 
-/// If the field has a valid defmt attribute (e.g. `#[defmt(Debug2Format)]`), returns `Ok(Some(FormatOption))`.
-/// Returns `Err` if we can't parse a valid defmt attribute.
-/// Returns `Ok(None)` if there are no `defmt` attributes on the field.
-fn get_defmt_format_option(field: &Field) -> syn::Result<Option<FormatOption>> {
-    use syn::Error;
-    let attrs = field
-        .attrs
+    let params = fragments
         .iter()
-        .filter(|a| a.path.is_ident("defmt"))
-        .map(|a| a.parse_meta())
-        .collect::<syn::Result<Vec<_>>>()?;
-    if attrs.is_empty() {
-        return Ok(None);
-    } else if attrs.len() > 1 {
+        .filter(|fragment| matches!(fragment, Fragment::Parameter(_)))
+        .count();
+    if params != 1 || has_explicit_index(&string) {
         return Err(Error::new_spanned(
-            field,
-            "multiple `defmt` attributes not supported",
-        ));
-    } // else attrs.len() == 1
-    let attr = &attrs[0];
-    let args = match attr {
-        Meta::List(list) => &list.nested,
-        bad => return Err(syn::Error::new_spanned(bad, "unrecognized attribute")),
-    };
-    if args.len() != 1 {
-        return Err(syn::Error::new_spanned(
-            attr,
-            "expected 1 attribute argument",
+            field_format,
+            "expected a format string with exactly one parameter without index, e.g. `{=u32:x}`",
         ));
     }
-    let arg = match &args[0] {
-        NestedMeta::Meta(Meta::Path(arg)) => arg,
-        bad => {
-            return Err(syn::Error::new_spanned(
-                bad,
-                "expected `Debug2Format` or `Display2Format`",
-            ))
+
+    let log::Codegen { patterns, exprs } = log::Codegen::new(&fragments, 1, field_format.span());
+    let (pattern, expr) = (&patterns[0], &exprs[0]);
+    Ok(quote!({
+        let #pattern = #ident;
+        #expr
+    }))
+}
+
+/// Returns `true` if a parameter of `format` has an explicit index, like `{0=u8}`.
+fn has_explicit_index(format: &str) -> bool {
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('{') {
+            rest = escaped;
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return true;
         }
-    };
-    if arg.is_ident("Debug2Format") {
-        Ok(Some(FormatOption::Debug2Format))
-    } else if arg.is_ident("Display2Format") {
-        Ok(Some(FormatOption::Display2Format))
-    } else {
-        Err(syn::Error::new_spanned(
-            arg,
-            "expected `Debug2Format` or `Display2Format`",
-        ))
     }
+    false
 }
 
 /// Returns `Some` if `ty` refers to a builtin Rust type that has native support from defmt and does
@@ -4,6 +4,8 @@ pub(crate) mod dbg;
 pub(crate) mod intern;
 pub(crate) mod internp;
 pub(crate) mod log;
+pub(crate) mod metric;
 pub(crate) mod panic_like;
 pub(crate) mod println;
+pub(crate) mod span;
 pub(crate) mod write;
@@ -1,5 +1,5 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{parse_macro_input, LitStr};
 
 use crate::construct;
@@ -13,14 +13,18 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
     let section_for_macos = construct::linker_section(true, prefix, &sym_name);
 
     let var_addr = if cfg!(feature = "unstable-test") {
-        quote!({ defmt::export::fetch_add_string_index() as u16 })
+        quote!({ defmt::export::fetch_add_string_index() as u32 })
+    } else if cfg!(feature = "std") {
+        construct::host_string(&format_ident!("S"), &literal.value(), "prim")
+    } else if cfg!(feature = "stable-ids") {
+        construct::stable_string(&format_ident!("S"), &literal.value(), "prim")
     } else {
         quote!({
             #[cfg_attr(target_os = "macos", link_section = #section_for_macos)]
             #[cfg_attr(not(target_os = "macos"), link_section = #section)]
             #[export_name = #sym_name]
             static S: u8 = 0;
-            &S as *const u8 as u16
+            &S as *const u8 as u32
         })
     };
 
@@ -3,22 +3,40 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort;
 use quote::quote;
-use syn::parse_macro_input;
+use syn::{parse::Parser, parse_macro_input};
 
 use crate::construct;
 
-use self::env_filter::EnvFilter;
-pub(crate) use self::{args::Args, codegen::Codegen};
+pub(crate) use self::{
+    args::Args, codegen::Codegen, env_filter::EnvFilter, limit::Kind as LimitKind,
+};
+
+use self::limit::Limit;
 
 mod args;
 mod codegen;
 mod env_filter;
+mod limit;
 
 pub(crate) fn expand(level: Level, args: TokenStream) -> TokenStream {
     expand_parsed(level, parse_macro_input!(args as Args)).into()
 }
 
 pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
+    expand_with_limit(level, args, None)
+}
+
+/// Expands `*_once!`, `*_every_n!` and `*_throttled!`.
+pub(crate) fn expand_limited(level: Level, kind: LimitKind, args: TokenStream) -> TokenStream {
+    let parser = |input: syn::parse::ParseStream| Limit::parse(kind, input);
+    let (limit, args) = match parser.parse(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    expand_with_limit(level, args, Some(limit)).into()
+}
+
+fn expand_with_limit(level: Level, args: Args, limit: Option<Limit>) -> TokenStream2 {
     let format_string = args.format_string.value();
     let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
         Ok(args) => args,
@@ -27,8 +45,8 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
 
     let formatting_exprs = args
         .formatting_args
-        .map(|punctuated| punctuated.into_iter().collect())
-        .unwrap_or_else(Vec::new);
+        .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
 
     let Codegen { patterns, exprs } = Codegen::new(
         &fragments,
@@ -40,17 +58,60 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
     let env_filter = EnvFilter::from_env_var();
 
     if let Some(filter_check) = env_filter.path_check(level) {
-        quote!(
-            match (#(&(#formatting_exprs)),*) {
-                (#(#patterns),*) => {
-                    if #filter_check {
+        let log = match limit {
+            None => quote!(
+                // safety: will be released a few lines further down
+                unsafe { defmt::export::acquire() };
+                defmt::export::header(&#header);
+                #(#exprs;)*
+                // safety: acquire() was called a few lines above
+                unsafe { defmt::export::release() }
+            ),
+            Some(limit) => {
+                let check = limit.check(&quote!(STATE));
+                // the number of suppressed calls is sent as an extra argument, with a format
+                // string of its own so that nothing is displayed if no call was suppressed
+                let (header, suppressed) = match limit.counts_suppressed() {
+                    true => {
+                        let counted = format!(
+                            "{} (suppressed {{{}=u32}} times)",
+                            format_string,
+                            formatting_exprs.len()
+                        );
+                        let counted = construct::interned_string(&counted, level.as_str(), true);
+                        (
+                            quote!(if suppressed == 0 { #header } else { #counted }),
+                            quote!(if suppressed != 0 {
+                                defmt::export::u32(&suppressed);
+                            }),
+                        )
+                    }
+                    false => (header, quote!()),
+                };
+                let pattern = match limit.counts_suppressed() {
+                    true => quote!(Some(suppressed)),
+                    false => quote!(Some(_)),
+                };
+                quote!(
+                    static STATE: defmt::export::RateLimit = defmt::export::RateLimit::new();
+                    if let #pattern = #check {
                         // safety: will be released a few lines further down
                         unsafe { defmt::export::acquire() };
                         defmt::export::header(&#header);
                         #(#exprs;)*
+                        #suppressed
                         // safety: acquire() was called a few lines above
                         unsafe { defmt::export::release() }
                     }
+                )
+            }
+        };
+        quote!(
+            match (#(&(#formatting_exprs)),*) {
+                (#(#patterns),*) => {
+                    if #filter_check {
+                        #log
+                    }
                 }
             }
         )
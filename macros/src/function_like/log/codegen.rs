@@ -63,6 +63,7 @@ fn encode_arg(ty: &Type, params: &[Parameter], arg_index: usize, arg: &Ident2) -
     match ty {
         Type::I8 => quote!(defmt::export::i8(#arg)),
         Type::I16 => quote!(defmt::export::i16(#arg)),
+        Type::I24 => quote!(defmt::export::i24(#arg)),
         Type::I32 => quote!(defmt::export::i32(#arg)),
         Type::I64 => quote!(defmt::export::i64(#arg)),
         Type::I128 => quote!(defmt::export::i128(#arg)),
@@ -70,11 +71,14 @@ fn encode_arg(ty: &Type, params: &[Parameter], arg_index: usize, arg: &Ident2) -
 
         Type::U8 => quote!(defmt::export::u8(#arg)),
         Type::U16 => quote!(defmt::export::u16(#arg)),
+        Type::U24 => quote!(defmt::export::u24(#arg)),
         Type::U32 => quote!(defmt::export::u32(#arg)),
         Type::U64 => quote!(defmt::export::u64(#arg)),
         Type::U128 => quote!(defmt::export::u128(#arg)),
         Type::Usize => quote!(defmt::export::usize(#arg)),
 
+        Type::F16 => quote!(defmt::export::f16(#arg)),
+        Type::Bf16 => quote!(defmt::export::bf16(#arg)),
         Type::F32 => quote!(defmt::export::f32(#arg)),
         Type::F64 => quote!(defmt::export::f64(#arg)),
 
@@ -86,6 +90,7 @@ fn encode_arg(ty: &Type, params: &[Parameter], arg_index: usize, arg: &Ident2) -
 
         Type::Format => quote!(defmt::export::fmt(#arg)),
         Type::FormatSlice => quote!(defmt::export::fmt_slice(#arg)),
+        Type::FormatMap => quote!(defmt::export::fmt_map(#arg)),
         Type::FormatArray(len) => quote!(defmt::export::fmt_array({
             let tmp: &[_; #len] = #arg;
             tmp
@@ -0,0 +1,65 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse::{self, ParseStream},
+    Expr, Token,
+};
+
+use super::Args;
+
+/// How often a rate-limited log statement may emit a frame.
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    /// `*_once!`
+    Once,
+    /// `*_every_n!`
+    EveryN,
+    /// `*_throttled!`
+    Throttled,
+}
+
+/// The arguments of a rate-limited log statement.
+pub(crate) struct Limit {
+    kind: Kind,
+    /// `n` of `*_every_n!`, or the interval of `*_throttled!`.
+    amount: Option<Expr>,
+    /// The current time, for `*_throttled!`.
+    now: Option<Expr>,
+}
+
+impl Limit {
+    /// Parses the arguments that precede the format string.
+    pub(crate) fn parse(kind: Kind, input: ParseStream) -> parse::Result<(Self, Args)> {
+        let mut limit = Limit {
+            kind,
+            amount: None,
+            now: None,
+        };
+        if let Kind::EveryN | Kind::Throttled = kind {
+            limit.amount = Some(input.parse()?);
+            let _comma: Token![,] = input.parse()?;
+        }
+        if let Kind::Throttled = kind {
+            limit.now = Some(input.parse()?);
+            let _comma: Token![,] = input.parse()?;
+        }
+        Ok((limit, input.parse()?))
+    }
+
+    /// Whether the number of suppressed calls is reported when a frame is emitted.
+    pub(crate) fn counts_suppressed(&self) -> bool {
+        !matches!(self.kind, Kind::Once)
+    }
+
+    /// Returns an expression that evaluates to the number of suppressed calls if `state` allows
+    /// a frame to be emitted, and to `None` otherwise.
+    pub(crate) fn check(&self, state: &TokenStream2) -> TokenStream2 {
+        let amount = &self.amount;
+        let now = &self.now;
+        match self.kind {
+            Kind::Once => quote!(#state.once()),
+            Kind::EveryN => quote!(#state.every_n((#amount) as u32)),
+            Kind::Throttled => quote!(#state.throttled((#amount) as u32, (#now) as u32)),
+        }
+    }
+}
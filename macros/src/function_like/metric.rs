@@ -0,0 +1,94 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::parse_macro_input;
+
+use crate::construct;
+
+use self::args::Args;
+
+mod args;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn tag(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+pub(crate) fn expand(kind: Kind, args: TokenStream) -> TokenStream {
+    expand_parsed(kind, parse_macro_input!(args as Args)).into()
+}
+
+fn expand_parsed(kind: Kind, args: Args) -> TokenStream2 {
+    let name = args.name.value();
+    if !is_valid_name(&name) {
+        abort!(
+            args.name,
+            "invalid metric name `{}`", name;
+            help = "metric names must match `[a-zA-Z_:][a-zA-Z0-9_:]*`"
+        );
+    }
+
+    // counters are incremented by one if no value is given; the other kinds need a value
+    let value = match (kind, args.value) {
+        (_, Some(value)) => quote!((#value)),
+        (Kind::Counter, None) => quote!(1),
+        (_, None) => abort!(args.name, "`{}!` requires a value", kind.tag()),
+    };
+    let (value, encode) = match kind {
+        Kind::Counter => (quote!(#value as u32), quote!(defmt::export::u32)),
+        Kind::Gauge | Kind::Histogram => (quote!(#value as f32), quote!(defmt::export::f32)),
+    };
+
+    let header = construct::interned_string(&name, kind.tag(), true);
+    quote!(
+        match #value {
+            value => {
+                // safety: will be released a few lines further down
+                unsafe { defmt::export::acquire() };
+                defmt::export::header(&#header);
+                #encode(&value);
+                // safety: acquire() was called a few lines above
+                unsafe { defmt::export::release() }
+            }
+        }
+    )
+}
+
+/// Checks that `name` is a valid Prometheus metric name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_names() {
+        assert!(is_valid_name("rx_bytes"));
+        assert!(is_valid_name("_private"));
+        assert!(is_valid_name("radio:tx_power"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("0_errors"));
+        assert!(!is_valid_name("temperature °C"));
+        assert!(!is_valid_name("rx-bytes"));
+    }
+}
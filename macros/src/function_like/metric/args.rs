@@ -0,0 +1,32 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Expr, LitStr, Token,
+};
+
+pub(crate) struct Args {
+    pub(crate) name: LitStr,
+    pub(crate) value: Option<Expr>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        if input.is_empty() {
+            return Ok(Self { name, value: None });
+        }
+
+        let _comma: Token![,] = input.parse()?;
+        if input.is_empty() {
+            // trailing comma
+            return Ok(Self { name, value: None });
+        }
+        let value = input.parse()?;
+        if input.peek(Token![,]) {
+            let _comma: Token![,] = input.parse()?;
+        }
+        Ok(Self {
+            name,
+            value: Some(value),
+        })
+    }
+}
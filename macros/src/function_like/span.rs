@@ -0,0 +1,68 @@
+use defmt_parser::{Level, ParserMode};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::parse_macro_input;
+
+use crate::construct;
+use crate::function_like::log::{Args, Codegen, EnvFilter};
+
+/// Span frames are filtered like log statements at this level.
+const LEVEL: Level = Level::Debug;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    expand_parsed(parse_macro_input!(args as Args)).into()
+}
+
+pub(crate) fn expand_parsed(args: Args) -> TokenStream2 {
+    let format_string = args.format_string.value();
+    let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
+        Ok(args) => args,
+        Err(e) => abort!(args.format_string, "{}", e),
+    };
+
+    let formatting_exprs = args
+        .formatting_args
+        .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let Codegen { patterns, exprs } = Codegen::new(
+        &fragments,
+        formatting_exprs.len(),
+        args.format_string.span(),
+    );
+
+    let enter = construct::interned_string(&format_string, "span_enter", true);
+    let exit = construct::interned_string(&format_string, "span_exit", true);
+    let env_filter = EnvFilter::from_env_var();
+
+    if let Some(filter_check) = env_filter.path_check(LEVEL) {
+        quote!(
+            match (#(&(#formatting_exprs)),*) {
+                (#(#patterns),*) => {
+                    if #filter_check {
+                        // safety: will be released a few lines further down
+                        unsafe { defmt::export::acquire() };
+                        defmt::export::header(&#enter);
+                        let id = defmt::export::span_id();
+                        defmt::export::u32(&id);
+                        #(#exprs;)*
+                        // safety: acquire() was called a few lines above
+                        unsafe { defmt::export::release() }
+                        defmt::SpanGuard::new(#exit, id)
+                    } else {
+                        defmt::SpanGuard::disabled()
+                    }
+                }
+            }
+        )
+    } else {
+        // if logging is disabled match args, so they are not considered "unused"
+        quote!(
+            match (#(&(#formatting_exprs)),*) {
+                _ => defmt::SpanGuard::disabled()
+            }
+        )
+    }
+}
@@ -1,9 +1,9 @@
-use defmt_parser::ParserMode;
+use defmt_parser::{Fragment, ParserMode, Type};
 use proc_macro::TokenStream;
 use proc_macro_error::abort;
 use quote::format_ident;
 use quote::quote;
-use syn::parse_macro_input;
+use syn::{parse_macro_input, LitStr};
 
 use crate::{construct, function_like::log};
 
@@ -28,20 +28,68 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
         args.format_string.span(),
     );
 
+    if cfg!(feature = "std") {
+        // the host logger doesn't support timestamps; only type-check the arguments
+        return quote!(
+            const _: () = {
+                #[allow(dead_code)]
+                fn defmt_timestamp(fmt: ::defmt::Formatter<'_>) {
+                    match (#(&(#formatting_exprs)),*) {
+                        (#(#patterns),*) => {
+                            #(#exprs;)*
+                        }
+                    }
+                }
+            };
+        )
+        .into();
+    }
+
     let var_name = format_ident!("S");
     let var_item = construct::static_variable(&var_name, &format_string, "timestamp");
 
+    let body = if cfg!(feature = "timestamp-delta") {
+        // the decoder reconstructs the value from deltas, so it has to be a single integer
+        let params = fragments
+            .iter()
+            .filter_map(|fragment| match fragment {
+                Fragment::Parameter(param) => Some(param),
+                Fragment::Literal(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let ty = match &params[..] {
+            [param] if formatting_exprs.len() == 1 => match param.ty {
+                Type::U8 => quote!(u8),
+                Type::U16 => quote!(u16),
+                Type::U32 => quote!(u32),
+                Type::U64 => quote!(u64),
+                Type::Usize => quote!(usize),
+                _ => abort_delta(&args.format_string),
+            },
+            _ => abort_delta(&args.format_string),
+        };
+        let expr = &formatting_exprs[0];
+        quote!(
+            let ticks: #ty = #expr;
+            ::defmt::export::timestamp_delta(ticks as u64);
+        )
+    } else {
+        quote!(
+            match (#(&(#formatting_exprs)),*) {
+                (#(#patterns),*) => {
+                // NOTE: No format string index, and no finalize call.
+                    #(#exprs;)*
+                }
+            }
+        )
+    };
+
     quote!(
         const _: () = {
             #[export_name = "_defmt_timestamp"]
             #[inline(never)]
             fn defmt_timestamp(fmt: ::defmt::Formatter<'_>) {
-                match (#(&(#formatting_exprs)),*) {
-                    (#(#patterns),*) => {
-                    // NOTE: No format string index, and no finalize call.
-                        #(#exprs;)*
-                    }
-                }
+                #body
             }
 
             #var_item;
@@ -57,3 +105,10 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+fn abort_delta(format_string: &LitStr) -> ! {
+    abort!(
+        format_string,
+        "with the `timestamp-delta` feature, the timestamp must be a single unsigned integer, e.g. `{=u64:us}`"
+    )
+}
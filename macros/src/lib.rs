@@ -152,6 +152,126 @@ pub fn error(args: TokenStream) -> TokenStream {
 }
 /* ## end of logging macros */
 
+/* ## Rate-limited logging macros */
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn trace_once(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Trace, function_like::log::LimitKind::Once, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn debug_once(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Debug, function_like::log::LimitKind::Once, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn info_once(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Info, function_like::log::LimitKind::Once, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn warn_once(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Warn, function_like::log::LimitKind::Once, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn error_once(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Error, function_like::log::LimitKind::Once, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn trace_every_n(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Trace, function_like::log::LimitKind::EveryN, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn debug_every_n(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Debug, function_like::log::LimitKind::EveryN, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn info_every_n(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Info, function_like::log::LimitKind::EveryN, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn warn_every_n(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Warn, function_like::log::LimitKind::EveryN, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn error_every_n(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Error, function_like::log::LimitKind::EveryN, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn trace_throttled(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Trace, function_like::log::LimitKind::Throttled, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn debug_throttled(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Debug, function_like::log::LimitKind::Throttled, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn info_throttled(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Info, function_like::log::LimitKind::Throttled, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn warn_throttled(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Warn, function_like::log::LimitKind::Throttled, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn error_throttled(args: TokenStream) -> TokenStream {
+    function_like::log::expand_limited(Level::Error, function_like::log::LimitKind::Throttled, args)
+}
+/* ## end of rate-limited logging macros */
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn span(args: TokenStream) -> TokenStream {
+    function_like::span::expand(args)
+}
+
+/* ## Metric macros */
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn counter(args: TokenStream) -> TokenStream {
+    function_like::metric::expand(function_like::metric::Kind::Counter, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn gauge(args: TokenStream) -> TokenStream {
+    function_like::metric::expand(function_like::metric::Kind::Gauge, args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn histogram(args: TokenStream) -> TokenStream {
+    function_like::metric::expand(function_like::metric::Kind::Histogram, args)
+}
+/* ## end of metric macros */
+
 #[proc_macro]
 #[proc_macro_error]
 pub fn panic_(args: TokenStream) -> TokenStream {
@@ -1,5 +1,7 @@
 use std::str::FromStr;
 
+use crate::FormatSpec;
+
 /// All display hints
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DisplayHint {
@@ -19,12 +21,21 @@ pub enum DisplayHint {
     },
     /// `:a`
     Ascii,
+    /// `:hexdump`, formats byte slices as a multi-line dump of offsets, hex values and ASCII
+    Hexdump,
     /// `:?`
     Debug,
     /// `:us`, formats integers as timestamps in microseconds
     Microseconds,
     /// `:iso8601{ms,s}`, formats integers as timestamp in ISO8601 date time format
     ISO8601(TimePrecision),
+    /// `:q<n>` or `:q<m>.<n>`, formats integers as fixed-point numbers with `n` fractional bits,
+    /// e.g. `:q15` or `:q16.16`
+    FixedPoint {
+        /// `m`, if given; it documents the format but doesn't change the output
+        integer_bits: Option<u8>,
+        fractional_bits: u8,
+    },
     /// `__internal_bitflags_NAME` instructs the decoder to print the flags that are set, instead of
     /// the raw value.
     Bitflags {
@@ -33,15 +44,21 @@ pub enum DisplayHint {
         disambiguator: String,
         crate_name: Option<String>,
     },
+    /// `:@name`, formats the value with the handler the host registered for `name`
+    Custom(String),
     /// Display hints currently not supported / understood
     Unknown(String),
 }
 
 impl DisplayHint {
-    /// Parses the display hint (e.g. the `#x` in `{=u8:#x}`)
-    pub(crate) fn parse(mut s: &str) -> Option<Self> {
+    /// Parses the display hint (e.g. the `#x` in `{=u8:#x}`), together with the format spec that
+    /// surrounds it (e.g. the `>` and `8` in `{=u8:>#8x}`)
+    pub(crate) fn parse(mut s: &str) -> Option<(Self, FormatSpec)> {
         const BITFLAGS_HINT_START: &str = "__internal_bitflags_";
 
+        let mut spec = FormatSpec::default();
+        s = spec.parse_fill_align_sign(s);
+
         // The `#` comes before any padding hints (I think this matches core::fmt).
         // It is ignored for types that don't have an alternate representation.
         let alternate = if let Some(rest) = s.strip_prefix('#') {
@@ -58,25 +75,28 @@ impl DisplayHint {
         } else {
             0 // default behavior is the same as no zero-padding.
         };
+        s = spec.parse_width_precision(s)?;
 
         if let Some(stripped) = s.strip_prefix(BITFLAGS_HINT_START) {
             let parts = stripped.split('@').collect::<Vec<_>>();
             if parts.len() < 3 || parts.len() > 4 {
-                return Some(DisplayHint::Unknown(s.into()));
+                return Some((DisplayHint::Unknown(s.into()), spec));
             }
-            return Some(DisplayHint::Bitflags {
+            let hint = DisplayHint::Bitflags {
                 name: parts[0].into(),
                 package: parts[1].into(),
                 disambiguator: parts[2].into(),
                 // crate_name was added in wire format version 4
                 crate_name: parts.get(3).map(|&s| s.to_string()),
-            });
+            };
+            return Some((hint, spec));
         }
 
-        Some(match s {
+        let hint = match s {
             "" => DisplayHint::NoHint { zero_pad },
             "us" => DisplayHint::Microseconds,
             "a" => DisplayHint::Ascii,
+            "hexdump" => DisplayHint::Hexdump,
             "b" => DisplayHint::Binary {
                 alternate,
                 zero_pad,
@@ -94,11 +114,51 @@ impl DisplayHint {
             "iso8601ms" => DisplayHint::ISO8601(TimePrecision::Millis),
             "iso8601s" => DisplayHint::ISO8601(TimePrecision::Seconds),
             "?" => DisplayHint::Debug,
-            _ => return None,
-        })
+            _ => {
+                if let Some(bits) = s.strip_prefix('q') {
+                    parse_fixed_point(bits)?
+                } else if let Some(name) = s.strip_prefix('@') {
+                    DisplayHint::Custom(parse_custom_name(name)?.into())
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some((hint, spec))
     }
 }
 
+/// Parses the `<n>` or `<m>.<n>` of a fixed-point hint.
+fn parse_fixed_point(s: &str) -> Option<DisplayHint> {
+    let (integer_bits, fractional_bits) = match s.split_once('.') {
+        Some((m, n)) => (Some(parse_bits(m)?), parse_bits(n)?),
+        None => (None, parse_bits(s)?),
+    };
+    if integer_bits.unwrap_or(0) as u16 + fractional_bits as u16 > 128 {
+        return None;
+    }
+    Some(DisplayHint::FixedPoint {
+        integer_bits,
+        fractional_bits,
+    })
+}
+
+fn parse_bits(s: &str) -> Option<u8> {
+    match parse_integer(s)? {
+        ("", bits) if bits <= 128 => Some(bits),
+        _ => None,
+    }
+}
+
+/// Checks that the `name` of a `:@name` hint is an identifier.
+fn parse_custom_name(name: &str) -> Option<&str> {
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    let is_ident = (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    is_ident.then_some(name)
+}
+
 /// Precision of ISO8601 datetime
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum TimePrecision {
@@ -110,7 +170,7 @@ pub enum TimePrecision {
 ///
 /// Returns the integer and remaining text, if `s` started with an integer. Any errors parsing the
 /// number (which we already know only contains digits) are silently ignored.
-fn parse_integer<T: FromStr>(s: &str) -> Option<(&str, T)> {
+pub(crate) fn parse_integer<T: FromStr>(s: &str) -> Option<(&str, T)> {
     let start_digits = s
         .as_bytes()
         .iter()
@@ -0,0 +1,99 @@
+use crate::display_hint::parse_integer;
+
+/// The alignment of a value that is narrower than the width of its parameter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Alignment {
+    /// `<`
+    Left,
+    /// `^`
+    Center,
+    /// `>`
+    Right,
+}
+
+/// The `core::fmt`-style options of a parameter that are not part of its [`DisplayHint`].
+///
+/// For example, `{=f32:*^+10.2}` is centered with `*` as fill character, shows the sign of positive
+/// values, is at least 10 characters wide and shows two decimal places. The `#` flag and zero
+/// padding are part of the [`DisplayHint`].
+///
+/// [`DisplayHint`]: crate::DisplayHint
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormatSpec {
+    /// The character that pads the value to `width`.
+    pub fill: char,
+    /// The alignment within `width`, or `None` for the default of the type: numbers are aligned
+    /// to the right, everything else to the left.
+    pub align: Option<Alignment>,
+    /// `+`: show the sign of positive numbers, too.
+    pub sign_plus: bool,
+    /// The minimum width of the value, in characters.
+    pub width: Option<usize>,
+    /// The number of decimal places of floating point numbers, or the maximum number of
+    /// characters of strings.
+    pub precision: Option<usize>,
+}
+
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            sign_plus: false,
+            width: None,
+            precision: None,
+        }
+    }
+}
+
+impl FormatSpec {
+    /// Parses `[[fill]align][sign]` at the beginning of `s`, and returns the remaining text.
+    pub(crate) fn parse_fill_align_sign<'s>(&mut self, mut s: &'s str) -> &'s str {
+        let mut chars = s.chars();
+        let first = chars.next();
+        let second = chars.next();
+        if let Some(align) = second.and_then(parse_alignment) {
+            self.fill = first.unwrap_or(' ');
+            self.align = Some(align);
+            s = chars.as_str();
+        } else if let Some(align) = first.and_then(parse_alignment) {
+            self.align = Some(align);
+            s = &s[1..];
+        }
+
+        if let Some(rest) = s.strip_prefix('+') {
+            self.sign_plus = true;
+            s = rest;
+        } else if let Some(rest) = s.strip_prefix('-') {
+            // accepted like in `core::fmt`, where it has no effect either
+            s = rest;
+        }
+        s
+    }
+
+    /// Parses `[width]['.' precision]` at the beginning of `s`, and returns the remaining text.
+    ///
+    /// Returns `None` if there is a `.` that is not followed by a precision.
+    pub(crate) fn parse_width_precision<'s>(&mut self, mut s: &'s str) -> Option<&'s str> {
+        if s.starts_with(|c: char| c.is_ascii_digit()) {
+            let (rest, width) = parse_integer(s)?;
+            self.width = Some(width);
+            s = rest;
+        }
+        if let Some(rest) = s.strip_prefix('.') {
+            let (rest, precision) = parse_integer(rest)?;
+            self.precision = Some(precision);
+            s = rest;
+        }
+        Some(s)
+    }
+}
+
+fn parse_alignment(c: char) -> Option<Alignment> {
+    match c {
+        '<' => Some(Alignment::Left),
+        '^' => Some(Alignment::Center),
+        '>' => Some(Alignment::Right),
+        _ => None,
+    }
+}
@@ -11,6 +11,7 @@
 #![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
 
 mod display_hint;
+mod format_spec;
 #[cfg(test)]
 mod tests;
 mod types;
@@ -19,6 +20,7 @@ use std::{borrow::Cow, ops::Range};
 
 pub use crate::{
     display_hint::{DisplayHint, TimePrecision},
+    format_spec::{Alignment, FormatSpec},
     types::Type,
 };
 
@@ -60,6 +62,8 @@ pub struct Parameter {
     pub ty: Type,
     /// The display hint, e.g. ':x', ':b', ':a'.
     pub hint: Option<DisplayHint>,
+    /// Width, alignment, precision and sign, e.g. ':>8', ':.2', ':+'.
+    pub spec: FormatSpec,
 }
 
 /// A part of a format string.
@@ -80,23 +84,29 @@ pub enum Fragment<'f> {
 /// param := '{' [ argument ] [ '=' argtype ] [ ':' format_spec ] '}'
 /// argument := integer
 ///
-/// argtype := bitfield | '?' | format-array | '[?]' | byte-array | '[u8]' | 'istr' | 'str' |
-///     'bool' | 'char' | 'u8' | 'u16' | 'u32' | 'u64' | 'u128' | 'usize' | 'i8' | 'i16' | 'i32' |
-///     'i64' | 'i128 | 'isize' | 'f32' | 'f64'
+/// argtype := bitfield | '?' | format-array | '[?]' | '[?:?]' | byte-array | '[u8]' | 'istr' |
+///     'str' | 'bool' | 'char' | 'u8' | 'u16' | 'u24' | 'u32' | 'u64' | 'u128' | 'usize' | 'i8' |
+///     'i16' | 'i24' | 'i32' | 'i64' | 'i128 | 'isize' | 'f16' | 'bf16' | 'f32' | 'f64'
 /// bitfield := integer '..' integer
 /// format-array := '[?;' spaces integer ']'
 /// byte-array := '[u8;' spaces integer ']'
 /// spaces := ' '*
 ///
-/// format_spec := [ zero_pad ] type
+/// format_spec := [ [ fill ] align ] [ sign ] [ '#' ] [ zero_pad | width ] [ '.' precision ] type
+/// fill := character
+/// align := '<' | '^' | '>'
+/// sign := '+' | '-'
 /// zero_pad := '0' integer
-/// type := 'a' | 'b' | 'o' | 'x' | 'X' | '?' | 'us'
+/// width := integer
+/// precision := integer
+/// type := 'a' | 'b' | 'o' | 'x' | 'X' | '?' | 'us' | ''
 /// ```
 #[derive(Debug, PartialEq)]
 struct Param {
     index: Option<usize>,
     ty: Type,
     hint: Option<DisplayHint>,
+    spec: FormatSpec,
 }
 
 /// The log level
@@ -211,8 +221,15 @@ fn parse_param(mut input: &str, mode: ParserMode) -> Result<Param, Error> {
         // skip the prefix
         input = &input[TYPE_PREFIX.len()..];
 
-        // type is delimited by `HINT_PREFIX` or end-of-string
-        let type_end = input.find(HINT_PREFIX).unwrap_or(input.len());
+        // type is delimited by `HINT_PREFIX` or end-of-string; the map type contains it, though
+        const FORMAT_MAP: &str = "[?:?]";
+        let type_start = match input.starts_with(FORMAT_MAP) {
+            true => FORMAT_MAP.len(),
+            false => 0,
+        };
+        let type_end = input[type_start..]
+            .find(HINT_PREFIX)
+            .map_or(input.len(), |end| type_start + end);
         let type_fragment = &input[..type_end];
 
         const FORMAT_ARRAY_START: &str = "[?;";
@@ -240,6 +257,7 @@ fn parse_param(mut input: &str, mode: ParserMode) -> Result<Param, Error> {
 
     // Then, optional hint
     let mut hint = None;
+    let mut spec = FormatSpec::default();
 
     if input.starts_with(HINT_PREFIX) {
         // skip the prefix
@@ -249,7 +267,10 @@ fn parse_param(mut input: &str, mode: ParserMode) -> Result<Param, Error> {
         }
 
         hint = match (DisplayHint::parse(input), mode) {
-            (Some(a), _) => Some(a),
+            (Some((a, s)), _) => {
+                spec = s;
+                Some(a)
+            }
             (None, ParserMode::Strict) => return Err(Error::UnknownDisplayHint(input.to_owned())),
             (None, ParserMode::ForwardsCompatible) => Some(DisplayHint::Unknown(input.to_owned())),
         };
@@ -257,7 +278,12 @@ fn parse_param(mut input: &str, mode: ParserMode) -> Result<Param, Error> {
         return Err(Error::UnexpectedContentInFormatString(input.to_owned()));
     }
 
-    Ok(Param { index, ty, hint })
+    Ok(Param {
+        index,
+        ty,
+        hint,
+        spec,
+    })
 }
 
 fn push_literal<'f>(frag: &mut Vec<Fragment<'f>>, unescaped_literal: &'f str) -> Result<(), Error> {
@@ -317,6 +343,22 @@ where
     }
 }
 
+/// Returns the index of an interned string with the `stable-ids` feature of `defmt`: the 32-bit
+/// FNV-1a hash of its symbol `tag` (e.g. `defmt_info`), the name of the crate it's defined in, and
+/// its `data`.
+///
+/// The index is never 0, which is reserved as the terminator of format sequences.
+pub fn stable_id(tag: &str, crate_name: &str, data: &str) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c_9dc5;
+    const PRIME: u32 = 0x0100_0193;
+
+    let parts = [tag.as_bytes(), crate_name.as_bytes(), data.as_bytes()];
+    let hash = parts.join(&0).iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(PRIME)
+    });
+    hash.max(1)
+}
+
 pub fn parse(format_string: &str, mode: ParserMode) -> Result<Vec<Fragment<'_>>, Error> {
     let mut fragments = Vec::new();
 
@@ -365,6 +407,7 @@ pub fn parse(format_string: &str, mode: ParserMode) -> Result<Vec<Fragment<'_>>,
             }),
             ty: param.ty,
             hint: param.hint,
+            spec: param.spec,
         }));
     }
 
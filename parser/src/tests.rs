@@ -19,12 +19,18 @@ fn all_parse_param_cases(
 ) {
     assert_eq!(
         parse_param(input, ParserMode::Strict),
-        Ok(Param { index, ty, hint })
+        Ok(Param {
+            index,
+            ty,
+            hint,
+            spec: FormatSpec::default(),
+        })
     );
 }
 
 #[rstest]
 #[case(":a", DisplayHint::Ascii)]
+#[case(":hexdump", DisplayHint::Hexdump)]
 #[case(":b", DisplayHint::Binary { alternate: false, zero_pad: 0 })]
 #[case(":#b", DisplayHint::Binary { alternate: true, zero_pad: 0 })]
 #[case(":x", DisplayHint::Hexadecimal { alternate: false, uppercase: false, zero_pad: 0 })]
@@ -35,6 +41,11 @@ fn all_parse_param_cases(
 #[case(":iso8601s", DisplayHint::ISO8601(TimePrecision::Seconds))]
 #[case(":?", DisplayHint::Debug)]
 #[case(":02", DisplayHint::NoHint { zero_pad: 2 })]
+#[case(":q15", DisplayHint::FixedPoint { integer_bits: None, fractional_bits: 15 })]
+#[case(":q16.16", DisplayHint::FixedPoint { integer_bits: Some(16), fractional_bits: 16 })]
+#[case(":q0.8", DisplayHint::FixedPoint { integer_bits: Some(0), fractional_bits: 8 })]
+#[case(":@reg_status", DisplayHint::Custom("reg_status".to_string()))]
+#[case(":@_E2", DisplayHint::Custom("_E2".to_string()))]
 fn all_display_hints(#[case] input: &str, #[case] hint: DisplayHint) {
     assert_eq!(
         parse_param(input, ParserMode::Strict),
@@ -42,6 +53,7 @@ fn all_display_hints(#[case] input: &str, #[case] hint: DisplayHint) {
             index: None,
             ty: Type::Format,
             hint: Some(hint),
+            spec: FormatSpec::default(),
         })
     );
 }
@@ -55,29 +67,95 @@ fn display_hint_unknown() {
             index: None,
             ty: Type::Format,
             hint: Some(DisplayHint::Unknown("unknown".to_string())),
+            spec: FormatSpec::default(),
+        })
+    );
+}
+
+#[rstest]
+#[case(":>4", DisplayHint::NoHint { zero_pad: 0 }, ' ', Some(Alignment::Right), false, Some(4), None)]
+#[case(":*^+10.2", DisplayHint::NoHint { zero_pad: 0 }, '*', Some(Alignment::Center), true, Some(10), Some(2))]
+#[case(":.3", DisplayHint::NoHint { zero_pad: 0 }, ' ', None, false, None, Some(3))]
+#[case(":+", DisplayHint::NoHint { zero_pad: 0 }, ' ', None, true, None, None)]
+#[case(":<8x", DisplayHint::Hexadecimal { alternate: false, uppercase: false, zero_pad: 0 }, ' ', Some(Alignment::Left), false, Some(8), None)]
+#[case(":+#010b", DisplayHint::Binary { alternate: true, zero_pad: 10 }, ' ', None, true, None, None)]
+#[case(
+    ":>>6?",
+    DisplayHint::Debug,
+    '>',
+    Some(Alignment::Right),
+    false,
+    Some(6),
+    None
+)]
+#[case(":-<3", DisplayHint::NoHint { zero_pad: 0 }, '-', Some(Alignment::Left), false, Some(3), None)]
+#[allow(clippy::too_many_arguments)]
+fn format_spec(
+    #[case] input: &str,
+    #[case] hint: DisplayHint,
+    #[case] fill: char,
+    #[case] align: Option<Alignment>,
+    #[case] sign_plus: bool,
+    #[case] width: Option<usize>,
+    #[case] precision: Option<usize>,
+) {
+    assert_eq!(
+        parse_param(input, ParserMode::Strict),
+        Ok(Param {
+            index: None,
+            ty: Type::Format,
+            hint: Some(hint),
+            spec: FormatSpec {
+                fill,
+                align,
+                sign_plus,
+                width,
+                precision,
+            },
         })
     );
 }
 
+#[rstest]
+#[case(":.")]
+#[case(":.x")]
+#[case(":>4z")]
+#[case(":q")]
+#[case(":q1.")]
+#[case(":q16.16.0")]
+#[case(":q64.65")]
+#[case(":@")]
+#[case(":@2e")]
+#[case(":@reg-status")]
+fn format_spec_err(#[case] input: &str) {
+    assert!(parse_param(input, ParserMode::Strict).is_err());
+}
+
 #[rstest]
 #[case("=i8", Type::I8)]
 #[case("=i16", Type::I16)]
+#[case("=i24", Type::I24)]
 #[case("=i32", Type::I32)]
 #[case("=i64", Type::I64)]
 #[case("=i128", Type::I128)]
 #[case("=isize", Type::Isize)]
 #[case("=u8", Type::U8)]
 #[case("=u16", Type::U16)]
+#[case("=u24", Type::U24)]
 #[case("=u32", Type::U32)]
 #[case("=u64", Type::U64)]
 #[case("=u128", Type::U128)]
 #[case("=usize", Type::Usize)]
+#[case("=f16", Type::F16)]
+#[case("=bf16", Type::Bf16)]
 #[case("=f32", Type::F32)]
 #[case("=f64", Type::F64)]
 #[case("=bool", Type::Bool)]
 #[case("=?", Type::Format)]
 #[case("=str", Type::Str)]
 #[case("=[u8]", Type::U8Slice)]
+#[case("=[?]", Type::FormatSlice)]
+#[case("=[?:?]", Type::FormatMap)]
 fn all_types(#[case] input: &str, #[case] ty: Type) {
     assert_eq!(
         parse_param(input, ParserMode::Strict),
@@ -85,6 +163,24 @@ fn all_types(#[case] input: &str, #[case] ty: Type) {
             index: None,
             ty,
             hint: None,
+            spec: FormatSpec::default(),
+        })
+    );
+}
+
+#[test]
+fn format_map_with_hint() {
+    assert_eq!(
+        parse_param("1=[?:?]:#x", ParserMode::Strict),
+        Ok(Param {
+            index: Some(1),
+            ty: Type::FormatMap,
+            hint: Some(DisplayHint::Hexadecimal {
+                alternate: true,
+                uppercase: false,
+                zero_pad: 0
+            }),
+            spec: FormatSpec::default(),
         })
     );
 }
@@ -102,11 +198,13 @@ fn index(#[case] input: &str, #[case] params: [(usize, Type); 2]) {
                 index: params[0].0,
                 ty: params[0].1.clone(),
                 hint: None,
+                spec: FormatSpec::default(),
             }),
             Fragment::Parameter(Parameter {
                 index: params[1].0,
                 ty: params[1].1.clone(),
                 hint: None,
+                spec: FormatSpec::default(),
             }),
         ])
     );
@@ -123,6 +221,7 @@ fn range(#[case] input: &str, #[case] bit_field: Range<u8>) {
             index: 0,
             ty: Type::BitField(bit_field),
             hint: None,
+            spec: FormatSpec::default(),
         })])
     );
 }
@@ -136,16 +235,19 @@ fn multiple_ranges() {
                 index: 0,
                 ty: Type::BitField(30..31),
                 hint: None,
+                spec: FormatSpec::default(),
             }),
             Fragment::Parameter(Parameter {
                 index: 1,
                 ty: Type::BitField(0..4),
                 hint: None,
+                spec: FormatSpec::default(),
             }),
             Fragment::Parameter(Parameter {
                 index: 1,
                 ty: Type::BitField(2..6),
                 hint: None,
+                spec: FormatSpec::default(),
             }),
         ])
     );
@@ -162,6 +264,7 @@ fn arrays(#[case] input: &str, #[case] length: usize) {
             index: 0,
             ty: Type::U8Array(length),
             hint: None,
+            spec: FormatSpec::default(),
         })])
     );
 }
@@ -222,3 +325,20 @@ fn escaped_braces(#[case] input: &str, #[case] literal: &str) {
         Ok(vec![Fragment::Literal(literal.into())])
     );
 }
+
+#[test]
+fn stable_id() {
+    // the indices must not change between versions, or old captures can't be decoded anymore
+    assert_eq!(
+        super::stable_id("defmt_info", "app", "Hello, world!"),
+        0x43fc_a4de
+    );
+    assert_ne!(
+        super::stable_id("defmt_warn", "app", "Hello, world!"),
+        super::stable_id("defmt_info", "app", "Hello, world!")
+    );
+    assert_ne!(
+        super::stable_id("defmt_info", "app", "Hello, world!"),
+        super::stable_id("defmt_info", "app2", "Hello, world!")
+    );
+}
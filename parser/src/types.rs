@@ -11,6 +11,10 @@ pub enum Type {
     Display,
     FormatSequence,
 
+    /// IEEE 754 half precision
+    F16,
+    /// bfloat16, the upper half of an `f32`
+    Bf16,
     F32,
     F64,
 
@@ -20,9 +24,13 @@ pub enum Type {
     FormatArray(usize), // FIXME: This `usize` is not the target's `usize`; use `u64` instead?
     /// `{=[?]}`
     FormatSlice,
+    /// `{=[?:?]}`, a sequence of key-value pairs
+    FormatMap,
 
     I8,
     I16,
+    /// 24-bit signed integer, sent as 3 bytes
+    I24,
     I32,
     I64,
     I128,
@@ -35,6 +43,8 @@ pub enum Type {
 
     U8,
     U16,
+    /// 24-bit unsigned integer, sent as 3 bytes
+    U24,
     U32,
     U64,
     U128,
@@ -53,16 +63,20 @@ impl FromStr for Type {
         Ok(match s {
             "u8" => Type::U8,
             "u16" => Type::U16,
+            "u24" => Type::U24,
             "u32" => Type::U32,
             "u64" => Type::U64,
             "u128" => Type::U128,
             "usize" => Type::Usize,
             "i8" => Type::I8,
             "i16" => Type::I16,
+            "i24" => Type::I24,
             "i32" => Type::I32,
             "i64" => Type::I64,
             "i128" => Type::I128,
             "isize" => Type::Isize,
+            "f16" => Type::F16,
+            "bf16" => Type::Bf16,
             "f32" => Type::F32,
             "f64" => Type::F64,
             "bool" => Type::Bool,
@@ -74,6 +88,7 @@ impl FromStr for Type {
             "[u8]" => Type::U8Slice,
             "?" => Type::Format,
             "[?]" => Type::FormatSlice,
+            "[?:?]" => Type::FormatMap,
             "char" => Type::Char,
             _ => return Err(()),
         })
@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::bail;
+use defmt_decoder::{Frame, Location, Locations, Table};
+use defmt_parser::{Fragment, ParserMode, Type};
+
+/// Selects the log statements whose arguments are extracted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selector {
+    /// `FILE:LINE`, where `FILE` is matched against the end of the path of the log statement and
+    /// must not contain whitespace.
+    Location { file: String, line: u64 },
+    /// Any other string, which is matched against the format string of the log statement.
+    Format(String),
+}
+
+impl FromStr for Selector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((file, line)) = s.rsplit_once(':') {
+            let is_path = !file.is_empty() && !file.contains(char::is_whitespace);
+            if let (true, Ok(line)) = (is_path, line.parse()) {
+                let file = file.to_string();
+                return Ok(Selector::Location { file, line });
+            }
+        }
+        Ok(Selector::Format(s.to_string()))
+    }
+}
+
+impl Selector {
+    fn matches(&self, format: &str, location: Option<&Location>) -> bool {
+        match self {
+            Selector::Location { file, line } => location.is_some_and(|location| {
+                location.line == *line && location.file.ends_with(Path::new(file))
+            }),
+            Selector::Format(pattern) => format.contains(pattern.as_str()),
+        }
+    }
+}
+
+/// Writes the numeric arguments of the selected log statements as CSV.
+///
+/// There is one column per numeric argument of every selected log statement, named after the text
+/// preceding the argument in the format string. Every frame becomes one row, which only has values
+/// in the columns of its own log statement.
+pub struct Extractor {
+    columns: Vec<String>,
+    /// Maps the index of a log statement to `(argument index, column)` pairs.
+    call_sites: HashMap<u64, Vec<(usize, usize)>>,
+    tick: Option<Duration>,
+}
+
+impl Extractor {
+    pub fn new(
+        selectors: &[Selector],
+        table: &Table,
+        locs: &Option<Locations>,
+    ) -> anyhow::Result<Self> {
+        let needs_locations = selectors
+            .iter()
+            .any(|selector| matches!(selector, Selector::Location { .. }));
+        if needs_locations && locs.is_none() {
+            bail!("selecting log statements by location requires location info in the ELF file");
+        }
+
+        let mut columns = Vec::<(String, String)>::new();
+        let mut call_sites = HashMap::new();
+        let log_statements = table.indices().collect::<HashSet<_>>();
+        for (index, entry) in table.entries() {
+            if !log_statements.contains(&index) {
+                continue;
+            }
+            let format = entry.string().string();
+            let location = locs.as_ref().and_then(|locs| locs.get(&(index as u64)));
+            if !selectors
+                .iter()
+                .any(|selector| selector.matches(format, location))
+            {
+                continue;
+            }
+
+            let site = location
+                .map(|location| format!("{}:{}", location.file.display(), location.line))
+                .unwrap_or_else(|| format!("#{index}"));
+            let mut args = Vec::new();
+            for (arg, name) in numeric_params(format) {
+                args.push((arg, columns.len()));
+                columns.push((name, site.clone()));
+            }
+            if !args.is_empty() {
+                call_sites.insert(index as u64, args);
+            }
+        }
+        if call_sites.is_empty() {
+            bail!("no log statement with numeric arguments matches the selectors");
+        }
+
+        // add the call site to names that are used more than once
+        let mut counts = HashMap::<String, usize>::new();
+        for (name, _) in &columns {
+            *counts.entry(name.clone()).or_default() += 1;
+        }
+        let columns = columns
+            .into_iter()
+            .map(|(name, site)| match counts[&name] {
+                1 => name,
+                _ => format!("{name}@{site}"),
+            })
+            .collect();
+
+        Ok(Self {
+            columns,
+            call_sites,
+            tick: table.timestamp_tick_duration(),
+        })
+    }
+
+    pub fn write_header(&self, mut sink: impl Write) -> io::Result<()> {
+        write!(sink, "timestamp")?;
+        for column in &self.columns {
+            write!(sink, ",{}", csv_field(column))?;
+        }
+        writeln!(sink)
+    }
+
+    /// Writes a row if `frame` was produced by a selected log statement.
+    pub fn write_row(&self, frame: &Frame, mut sink: impl Write) -> io::Result<()> {
+        let args = match self.call_sites.get(&frame.index()) {
+            Some(args) => args,
+            None => return Ok(()),
+        };
+
+        // seconds if the unit of the timestamp is known, raw ticks otherwise
+        let timestamp = match (frame.timestamp_ticks(), self.tick) {
+            (Some(ticks), Some(tick)) => (ticks as f64 * tick.as_secs_f64()).to_string(),
+            (Some(ticks), None) => ticks.to_string(),
+            (None, _) => frame
+                .display_timestamp()
+                .map(|ts| csv_field(&ts.to_string()))
+                .unwrap_or_default(),
+        };
+        let values = frame.numeric_args();
+        let mut row = vec![String::new(); self.columns.len()];
+        for (arg, column) in args {
+            if let Some(Some(value)) = values.get(*arg) {
+                row[*column] = value.to_string();
+            }
+        }
+        writeln!(sink, "{},{}", timestamp, row.join(","))
+    }
+}
+
+/// Returns the index and a column name for every numeric argument of `format`, in argument order.
+fn numeric_params(format: &str) -> Vec<(usize, String)> {
+    let fragments = match defmt_parser::parse(format, ParserMode::ForwardsCompatible) {
+        Ok(fragments) => fragments,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut params = BTreeMap::new();
+    let mut preceding = "";
+    for fragment in &fragments {
+        match fragment {
+            Fragment::Literal(literal) => preceding = literal,
+            Fragment::Parameter(param) => {
+                if is_numeric(&param.ty) {
+                    params
+                        .entry(param.index)
+                        .or_insert_with(|| column_name(preceding, param.index));
+                }
+                preceding = "";
+            }
+        }
+    }
+    params.into_iter().collect()
+}
+
+fn is_numeric(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Bool
+            | Type::F16
+            | Type::Bf16
+            | Type::F32
+            | Type::F64
+            | Type::I8
+            | Type::I16
+            | Type::I24
+            | Type::I32
+            | Type::I64
+            | Type::I128
+            | Type::Isize
+            | Type::U8
+            | Type::U16
+            | Type::U24
+            | Type::U32
+            | Type::U64
+            | Type::U128
+            | Type::Usize
+    )
+}
+
+/// Names a column after the last word before the argument, e.g. `temp` for `temp={=f32}`.
+fn column_name(preceding: &str, index: usize) -> String {
+    let name = preceding
+        .trim_end()
+        .trim_end_matches(['=', ':'])
+        .split_whitespace()
+        .next_back()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .collect::<String>();
+    match name.is_empty() {
+        true => format!("arg{index}"),
+        false => name,
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use defmt_decoder::{Encoding, StringEntry, TableEntry, Tag};
+
+    use super::*;
+
+    #[test]
+    fn selector() {
+        assert_eq!(
+            "src/main.rs:38".parse(),
+            Ok(Selector::Location {
+                file: "src/main.rs".to_string(),
+                line: 38
+            })
+        );
+        assert_eq!(
+            "temp={=f32}".parse(),
+            Ok(Selector::Format("temp={=f32}".to_string()))
+        );
+        assert_eq!(
+            "time: 12:30".parse(),
+            Ok(Selector::Format("time: 12:30".to_string()))
+        );
+    }
+
+    #[test]
+    fn column_names() {
+        assert_eq!(
+            numeric_params("temp={=f32}°C, fault={=bool} {=str} {=u8}"),
+            [
+                (0, "temp".to_string()),
+                (1, "fault".to_string()),
+                (3, "arg3".to_string())
+            ]
+        );
+        assert_eq!(
+            numeric_params("x={1=u8:x} y={0=i16} x={1=u8}"),
+            [(0, "y".to_string()), (1, "x".to_string())]
+        );
+    }
+
+    #[test]
+    fn extract() {
+        let entry = |tag, format: &str| {
+            TableEntry::new(StringEntry::new(tag, format.to_string()), String::new())
+        };
+        let table = Table::new(
+            [
+                entry(Tag::Info, "temp={=f32}"),
+                entry(Tag::Info, "hello"),
+                entry(Tag::Warn, "sensor {=u8} temp={=i8}"),
+            ],
+            Encoding::Raw,
+        );
+        let selectors = [Selector::Format("temp=".to_string())];
+        let extractor = Extractor::new(&selectors, &table, &None).unwrap();
+
+        let mut csv = Vec::new();
+        extractor.write_header(&mut csv).unwrap();
+        let frames: [&[u8]; 3] = [
+            &[0, 0, 0x00, 0x00, 0xac, 0x41], // temp=21.5
+            &[1, 0],                         // hello
+            &[2, 0, 3, 0xfe],                // sensor 3 temp=-2
+        ];
+        for bytes in frames {
+            let frame = table.decode(bytes).unwrap().0;
+            extractor.write_row(&frame, &mut csv).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,temp@#0,sensor,temp@#2
+,21.5,,
+,,3,-2
+"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{anyhow, Context};
+use defmt_decoder::{Table, ValueNames};
+
+/// Registers the user-defined display hints in the JSON file at `path` with `table`.
+///
+/// The file maps every hint name to a table that maps values to names, e.g.
+/// `{ "state": { "0": "IDLE", "1": "BUSY", "0xff": "ERROR" } }` for `{=u8:@state}`.
+pub fn register(table: &mut Table, path: &Path) -> anyhow::Result<()> {
+    let json = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let hints: BTreeMap<String, BTreeMap<String, String>> =
+        serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+
+    for (hint, names) in hints {
+        let names = names
+            .into_iter()
+            .map(|(value, name)| {
+                let value = parse_value(&value)
+                    .ok_or_else(|| anyhow!("invalid value `{value}` of display hint `{hint}`"))?;
+                Ok((value, name))
+            })
+            .collect::<anyhow::Result<ValueNames>>()?;
+        table.register_hint(hint, names);
+    }
+    Ok(())
+}
+
+/// Parses a decimal, `0x` hexadecimal or `0b` binary integer, which may be negative.
+fn parse_value(s: &str) -> Option<i128> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let magnitude = if let Some(hex) = s.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = s.strip_prefix("0b") {
+        i128::from_str_radix(bin, 2).ok()?
+    } else {
+        s.parse().ok()?
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_value("42"), Some(42));
+        assert_eq!(parse_value("-1"), Some(-1));
+        assert_eq!(parse_value("0x80"), Some(0x80));
+        assert_eq!(parse_value("0b101"), Some(5));
+        assert_eq!(parse_value("-0x10"), Some(-16));
+        assert_eq!(parse_value("IDLE"), None);
+        assert_eq!(parse_value(""), None);
+    }
+}
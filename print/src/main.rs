@@ -1,12 +1,29 @@
+mod extract;
+mod hints;
+mod metrics;
+mod stats;
+mod timestamp;
+mod tui;
+
 use std::{
-    env, fs,
-    io::{self, Read},
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, Read},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::anyhow;
 use clap::Parser;
-use defmt_decoder::{DecodeError, Frame, Locations, Table, DEFMT_VERSIONS};
+use defmt_decoder::{ChromeTrace, DecodeError, Frame, Locations, Table, DEFMT_VERSIONS};
+use time::OffsetDateTime;
+
+use crate::{
+    extract::{Extractor, Selector},
+    metrics::{MetricsExport, MetricsFormat},
+    stats::Stats,
+    timestamp::{TimestampMode, Timestamps},
+};
 
 /// Prints defmt-encoded logs to stdout
 #[derive(Parser)]
@@ -21,6 +38,58 @@ struct Opts {
     #[arg(long)]
     show_skipped_frames: bool,
 
+    /// Instead of printing frames, count frames and bytes per log statement and print a report
+    #[arg(long)]
+    stats: bool,
+
+    /// Print the statistics report every SECONDS instead of only at the end of the input
+    #[arg(long, value_name = "SECONDS", requires("stats"))]
+    stats_interval: Option<u64>,
+
+    /// Instead of printing frames, write the numeric arguments of the log statements matching
+    /// SELECTOR as CSV. SELECTOR is either FILE:LINE or part of a format string; can be repeated
+    #[arg(
+        long,
+        value_name = "SELECTOR",
+        conflicts_with_all(["json", "stats", "tui", "metrics"])
+    )]
+    extract: Vec<Selector>,
+
+    /// How to display the timestamp of each frame
+    #[arg(long, value_enum, default_value_t, conflicts_with("json"))]
+    timestamp: TimestampMode,
+
+    /// Write frames and spans to FILE as Chrome trace events, which can be loaded into Perfetto
+    #[arg(long, value_name = "FILE", conflicts_with("tui"))]
+    chrome_trace: Option<PathBuf>,
+
+    /// Aggregate counters, gauges and histograms instead of printing them, and report them in
+    /// FORMAT at the end of the input
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with("tui"))]
+    metrics: Option<MetricsFormat>,
+
+    /// Write the metrics report to FILE, which is rewritten every second, instead of stdout
+    #[arg(long, value_name = "FILE", requires("metrics"))]
+    metrics_output: Option<PathBuf>,
+
+    /// Comma-separated upper bounds of the histogram buckets
+    #[arg(
+        long,
+        value_name = "BOUNDS",
+        value_delimiter = ',',
+        requires("metrics")
+    )]
+    metrics_buckets: Option<Vec<f64>>,
+
+    /// Display the values of user-defined display hints, e.g. `{=u8:@state}`, as names. FILE is a
+    /// JSON object that maps every hint name to an object that maps values to names
+    #[arg(long, value_name = "FILE")]
+    hints: Option<PathBuf>,
+
+    /// Show the frames in an interactive terminal UI that supports scrolling, pausing and filtering
+    #[arg(long, conflicts_with_all(["json", "stats"]))]
+    tui: bool,
+
     #[arg(short, long)]
     verbose: bool,
 
@@ -35,6 +104,16 @@ fn main() -> anyhow::Result<()> {
         elf,
         json,
         show_skipped_frames,
+        stats,
+        stats_interval,
+        extract,
+        timestamp,
+        chrome_trace,
+        metrics,
+        metrics_output,
+        metrics_buckets,
+        hints,
+        tui,
         verbose,
         version,
     } = Opts::parse();
@@ -43,14 +122,20 @@ fn main() -> anyhow::Result<()> {
         return print_version();
     }
 
-    defmt_decoder::log::init_logger(verbose, json, move |metadata| match verbose {
+    // the statistics report replaces the JSON frame output
+    defmt_decoder::log::init_logger(verbose, json && !stats, move |metadata| match verbose {
         false => defmt_decoder::log::is_defmt_frame(metadata), // We display *all* defmt frames, but nothing else.
         true => true,                                          // We display *all* frames.
     });
 
     let bytes = fs::read(elf.unwrap())?;
 
-    let table = Table::parse(&bytes)?.ok_or_else(|| anyhow!(".defmt data not found"))?;
+    let mut table = Table::parse(&bytes)?.ok_or_else(|| anyhow!(".defmt data not found"))?;
+    if let Some(hints) = hints {
+        hints::register(&mut table, &hints)?;
+    }
+    // JSON and the terminal UI show every frame on a single line
+    table.set_hexdump_multiline(!json && !tui);
     let locs = table.get_locations(&bytes)?;
 
     let locs = if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
@@ -60,10 +145,36 @@ fn main() -> anyhow::Result<()> {
         None
     };
 
+    let current_dir = env::current_dir()?;
+
+    if tui {
+        let timestamps = Timestamps::new(timestamp, &table);
+        return tui::run(tui::Session::new(&table, &locs, timestamps, current_dir));
+    }
+
     let mut buf = [0; READ_BUFFER_SIZE];
     let mut stream_decoder = table.new_stream_decoder();
+    let mut timestamps = Timestamps::new(timestamp, &table);
+    let mut stats = stats.then(Stats::new);
+    let stats_interval = stats_interval.map(Duration::from_secs);
+    let mut chrome_trace = match chrome_trace {
+        Some(path) => {
+            let file = BufWriter::new(File::create(path)?);
+            Some(ChromeTrace::new(file, table.timestamp_tick_duration())?)
+        }
+        None => None,
+    };
+    let mut metrics =
+        metrics.map(|format| MetricsExport::new(format, metrics_output, metrics_buckets));
+    let extractor = match extract.is_empty() {
+        true => None,
+        false => {
+            let extractor = Extractor::new(&extract, &table, &locs)?;
+            extractor.write_header(io::stdout().lock())?;
+            Some(extractor)
+        }
+    };
 
-    let current_dir = env::current_dir()?;
     let mut stdin = io::stdin().lock();
 
     loop {
@@ -71,15 +182,60 @@ fn main() -> anyhow::Result<()> {
         let n = stdin.read(&mut buf)?;
         // if 0 bytes where read, we reached EOF, so quit
         if n == 0 {
+            timestamps.report();
+            if let Some(stats) = &mut stats {
+                stats.report(json)?;
+            }
+            if let Some(chrome_trace) = chrome_trace {
+                chrome_trace.finish()?;
+            }
+            if let Some(metrics) = &mut metrics {
+                metrics.write()?;
+            }
             break Ok(());
         }
         stream_decoder.received(&buf[..n]);
+        let received = OffsetDateTime::now_utc();
 
         // decode the received data
         loop {
             match stream_decoder.decode() {
-                Ok(frame) => forward_to_logger(&frame, location_info(&locs, &frame, &current_dir)),
+                // a handshake with the build ID of the ELF file is not part of the log
+                Ok(frame) if frame.build_id().is_some() => {}
+                Ok(frame) => {
+                    if let Some(chrome_trace) = &mut chrome_trace {
+                        chrome_trace.add(&frame)?;
+                    }
+                    let is_metric = match &mut metrics {
+                        Some(metrics) => metrics.record(&frame),
+                        None => false,
+                    };
+                    if let Some(extractor) = &extractor {
+                        extractor.write_row(&frame, io::stdout().lock())?;
+                        continue;
+                    }
+                    let location = location_info(&locs, &frame, &current_dir);
+                    match &mut stats {
+                        Some(stats) => {
+                            let (index, level) = (frame.index(), frame.level());
+                            let size = stream_decoder.last_frame_size();
+                            stats.record(index, level, size, location);
+                        }
+                        // metric frames are reported separately from the log output
+                        None if is_metric => {}
+                        None => {
+                            let timestamp = timestamps.process(&frame, received);
+                            forward_to_logger(&frame, timestamp, location)
+                        }
+                    }
+                }
                 Err(DecodeError::UnexpectedEof) => break,
+                Err(e @ DecodeError::BuildIdMismatch { .. }) => match table.stable_ids() {
+                    // stable indices don't depend on the build, so unchanged log statements
+                    // still decode correctly
+                    true => eprintln!("(HOST) warning: {e}"),
+                    false => return Err(e.into()),
+                },
                 Err(DecodeError::Malformed) => match table.encoding().can_recover() {
                     // if recovery is impossible, abort
                     false => return Err(DecodeError::Malformed.into()),
@@ -96,14 +252,35 @@ fn main() -> anyhow::Result<()> {
                 },
             }
         }
+
+        if let Some(chrome_trace) = &mut chrome_trace {
+            chrome_trace.flush()?;
+        }
+        if let Some(metrics) = &mut metrics {
+            metrics.write_if_due()?;
+        }
+        if let (Some(stats), Some(interval)) = (&mut stats, stats_interval) {
+            if stats.is_report_due(interval) {
+                stats.report(json)?;
+            }
+        }
     }
 }
 
 type LocationInfo = (Option<String>, Option<u32>, Option<String>);
 
-fn forward_to_logger(frame: &Frame, location_info: LocationInfo) {
+fn forward_to_logger(frame: &Frame, timestamp: Option<String>, location_info: LocationInfo) {
     let (file, line, mod_path) = location_info;
-    defmt_decoder::log::log_defmt(frame, file.as_deref(), line, mod_path.as_deref());
+    match timestamp {
+        Some(timestamp) => defmt_decoder::log::log_defmt_with_timestamp(
+            frame,
+            timestamp,
+            file.as_deref(),
+            line,
+            mod_path.as_deref(),
+        ),
+        None => defmt_decoder::log::log_defmt(frame, file.as_deref(), line, mod_path.as_deref()),
+    }
 }
 
 fn location_info(locs: &Option<Locations>, frame: &Frame, current_dir: &Path) -> LocationInfo {
@@ -0,0 +1,82 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use defmt_decoder::{Frame, Metrics};
+
+/// How often the metrics file is rewritten while input is coming in.
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The format of the metrics report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetricsFormat {
+    /// Prometheus text exposition format
+    Prometheus,
+    /// One line of comma-separated values per metric
+    Csv,
+}
+
+/// Aggregates metric frames and writes them to stdout or a file.
+pub struct MetricsExport {
+    format: MetricsFormat,
+    output: Option<PathBuf>,
+    metrics: Metrics,
+    last_write: Instant,
+}
+
+impl MetricsExport {
+    pub fn new(format: MetricsFormat, output: Option<PathBuf>, buckets: Option<Vec<f64>>) -> Self {
+        Self {
+            format,
+            output,
+            metrics: buckets.map_or_else(Metrics::new, Metrics::with_buckets),
+            last_write: Instant::now(),
+        }
+    }
+
+    /// Adds the sample carried by `frame`. Returns whether `frame` was a metric frame.
+    pub fn record(&mut self, frame: &Frame) -> bool {
+        self.metrics.record(frame)
+    }
+
+    /// Rewrites the output file if a file is used and it wasn't written recently.
+    pub fn write_if_due(&mut self) -> anyhow::Result<()> {
+        if self.output.is_some() && self.last_write.elapsed() >= WRITE_INTERVAL {
+            self.write()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the metrics collected so far.
+    ///
+    /// The output file is replaced atomically, so readers like the Prometheus node exporter never
+    /// see a partially written file.
+    pub fn write(&mut self) -> anyhow::Result<()> {
+        self.last_write = Instant::now();
+        let path = match &self.output {
+            Some(path) => path,
+            None => return self.write_to(io::stdout().lock()),
+        };
+
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut file = BufWriter::new(File::create(&tmp)?);
+        self.write_to(&mut file)?;
+        file.flush()?;
+        drop(file);
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn write_to(&self, sink: impl Write) -> anyhow::Result<()> {
+        match self.format {
+            MetricsFormat::Prometheus => self.metrics.write_prometheus(sink)?,
+            MetricsFormat::Csv => self.metrics.write_csv(sink)?,
+        }
+        Ok(())
+    }
+}
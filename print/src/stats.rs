@@ -0,0 +1,396 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use defmt_decoder::Level;
+use serde::Serialize;
+
+use crate::LocationInfo;
+
+/// Number of frames and encoded bytes attributed to some group of log statements.
+#[derive(Clone, Copy, Default, Serialize)]
+struct Counter {
+    frames: u64,
+    bytes: u64,
+}
+
+impl Counter {
+    fn add(&mut self, bytes: usize) {
+        self.frames += 1;
+        self.bytes += bytes as u64;
+    }
+
+    fn rates(&self, elapsed: Duration) -> Rates {
+        let secs = elapsed.as_secs_f64();
+        match secs > 0.0 {
+            true => Rates {
+                frames_per_sec: self.frames as f64 / secs,
+                bytes_per_sec: self.bytes as f64 / secs,
+            },
+            false => Rates::default(),
+        }
+    }
+}
+
+#[derive(Default, Serialize)]
+struct Rates {
+    frames_per_sec: f64,
+    bytes_per_sec: f64,
+}
+
+struct CallSite {
+    level: Option<Level>,
+    location: LocationInfo,
+    counter: Counter,
+}
+
+/// Collects per-call-site statistics about the decoded frames.
+pub struct Stats {
+    start: Instant,
+    last_report: Instant,
+    call_sites: BTreeMap<u64, CallSite>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_report: now,
+            call_sites: BTreeMap::new(),
+        }
+    }
+
+    /// Accounts a frame, which took up `size` bytes in the stream, to its call site.
+    pub fn record(
+        &mut self,
+        index: u64,
+        level: Option<Level>,
+        size: usize,
+        location: LocationInfo,
+    ) {
+        self.call_sites
+            .entry(index)
+            .or_insert_with(|| CallSite {
+                level,
+                location,
+                counter: Counter::default(),
+            })
+            .counter
+            .add(size);
+    }
+
+    /// Returns `true` if at least `interval` has passed since the last report.
+    pub fn is_report_due(&self, interval: Duration) -> bool {
+        self.last_report.elapsed() >= interval
+    }
+
+    /// Writes a report of the statistics collected so far to stdout.
+    pub fn report(&mut self, json: bool) -> anyhow::Result<()> {
+        self.last_report = Instant::now();
+        let report = self.create_report(self.start.elapsed());
+
+        let mut sink = io::stdout().lock();
+        match json {
+            true => {
+                serde_json::to_writer(&mut sink, &report)?;
+                writeln!(sink)?;
+            }
+            false => report.print(&mut sink)?,
+        }
+        Ok(())
+    }
+
+    fn create_report(&self, elapsed: Duration) -> Report {
+        let mut total = Counter::default();
+        let mut levels = BTreeMap::<_, Counter>::new();
+        let mut modules = BTreeMap::<_, Counter>::new();
+        let mut call_sites = Vec::new();
+        for (index, call_site) in &self.call_sites {
+            let counter = call_site.counter;
+            let (file, line, module) = call_site.location.clone();
+            let level = call_site.level.map_or("println", |level| level.as_str());
+
+            total.frames += counter.frames;
+            total.bytes += counter.bytes;
+            for group in [
+                levels.entry(level).or_default(),
+                modules.entry(module.clone()).or_default(),
+            ] {
+                group.frames += counter.frames;
+                group.bytes += counter.bytes;
+            }
+
+            call_sites.push(CallSiteReport {
+                index: *index,
+                level,
+                file,
+                line,
+                module,
+                counter,
+                rates: counter.rates(elapsed),
+            });
+        }
+
+        // heaviest first
+        call_sites.sort_by_key(|entry| Reverse(entry.counter.bytes));
+        let mut levels = levels
+            .into_iter()
+            .map(|(level, counter)| GroupReport {
+                name: level.to_string(),
+                counter,
+                rates: counter.rates(elapsed),
+            })
+            .collect::<Vec<_>>();
+        levels.sort_by_key(|entry| Reverse(entry.counter.bytes));
+        let mut modules = modules
+            .into_iter()
+            .map(|(module, counter)| GroupReport {
+                name: module.unwrap_or_else(|| "<unknown>".to_string()),
+                counter,
+                rates: counter.rates(elapsed),
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by_key(|entry| Reverse(entry.counter.bytes));
+
+        Report {
+            elapsed_secs: elapsed.as_secs_f64(),
+            total,
+            rates: total.rates(elapsed),
+            call_sites,
+            levels,
+            modules,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    elapsed_secs: f64,
+    #[serde(flatten)]
+    total: Counter,
+    #[serde(flatten)]
+    rates: Rates,
+    call_sites: Vec<CallSiteReport>,
+    levels: Vec<GroupReport>,
+    modules: Vec<GroupReport>,
+}
+
+#[derive(Serialize)]
+struct CallSiteReport {
+    index: u64,
+    level: &'static str,
+    file: Option<String>,
+    line: Option<u32>,
+    module: Option<String>,
+    #[serde(flatten)]
+    counter: Counter,
+    #[serde(flatten)]
+    rates: Rates,
+}
+
+#[derive(Serialize)]
+struct GroupReport {
+    name: String,
+    #[serde(flatten)]
+    counter: Counter,
+    #[serde(flatten)]
+    rates: Rates,
+}
+
+impl Report {
+    fn print(&self, sink: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            sink,
+            "{} frames, {} bytes in {:.1} s ({:.1} frames/s, {:.1} B/s)",
+            self.total.frames,
+            self.total.bytes,
+            self.elapsed_secs,
+            self.rates.frames_per_sec,
+            self.rates.bytes_per_sec
+        )?;
+
+        writeln!(sink, "\nby call site:")?;
+        writeln!(
+            sink,
+            "{:>10} {:>10} {:>10} {:>6}  {:7} location",
+            "frames", "bytes", "B/s", "%", "level"
+        )?;
+        for call_site in &self.call_sites {
+            let location = match (&call_site.file, call_site.line) {
+                (Some(file), Some(line)) => format!("{file}:{line}"),
+                _ => format!("index {}", call_site.index),
+            };
+            let module = call_site.module.as_deref().unwrap_or("");
+            self.print_row(
+                sink,
+                &call_site.counter,
+                &call_site.rates,
+                &format!("{:7} {location} {module}", call_site.level),
+            )?;
+        }
+
+        for (title, groups) in [("level", &self.levels), ("module", &self.modules)] {
+            writeln!(sink, "\nby {title}:")?;
+            for group in groups {
+                self.print_row(sink, &group.counter, &group.rates, &group.name)?;
+            }
+        }
+        writeln!(sink)
+    }
+
+    fn print_row(
+        &self,
+        sink: &mut impl Write,
+        counter: &Counter,
+        rates: &Rates,
+        name: &str,
+    ) -> io::Result<()> {
+        let share = match self.total.bytes {
+            0 => 0.0,
+            total => counter.bytes as f64 * 100.0 / total as f64,
+        };
+        writeln!(
+            sink,
+            "{:>10} {:>10} {:>10.1} {share:>6.1}  {name}",
+            counter.frames, counter.bytes, rates.bytes_per_sec,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(file: &str, line: u32, module: &str) -> LocationInfo {
+        (Some(file.to_string()), Some(line), Some(module.to_string()))
+    }
+
+    fn stats() -> Stats {
+        let mut stats = Stats::new();
+        let boot = location("src/main.rs", 10, "app");
+        let sample = location("src/sensor.rs", 42, "app::sensor");
+        stats.record(0, Some(Level::Info), 4, boot);
+        for _ in 0..3 {
+            stats.record(1, Some(Level::Debug), 8, sample.clone());
+        }
+        stats.record(2, None, 6, (None, None, None));
+        stats
+    }
+
+    #[test]
+    fn report() {
+        let report = stats().create_report(Duration::from_secs(2));
+
+        assert_eq!((report.total.frames, report.total.bytes), (5, 34));
+        assert_eq!(report.rates.bytes_per_sec, 17.0);
+
+        let call_sites = report
+            .call_sites
+            .iter()
+            .map(|call_site| {
+                let counter = call_site.counter;
+                (
+                    call_site.index,
+                    call_site.level,
+                    counter.frames,
+                    counter.bytes,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            call_sites,
+            [(1, "debug", 3, 24), (2, "println", 1, 6), (0, "info", 1, 4)]
+        );
+
+        let groups = |groups: &[GroupReport]| {
+            groups
+                .iter()
+                .map(|group| (group.name.clone(), group.counter.bytes))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            groups(&report.levels),
+            [
+                ("debug".to_string(), 24),
+                ("println".to_string(), 6),
+                ("info".to_string(), 4)
+            ]
+        );
+        assert_eq!(
+            groups(&report.modules),
+            [
+                ("app::sensor".to_string(), 24),
+                ("<unknown>".to_string(), 6),
+                ("app".to_string(), 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn print() {
+        let report = stats().create_report(Duration::from_secs(2));
+        let mut table = Vec::new();
+        report.print(&mut table).unwrap();
+        // the row without a location ends with an empty module column
+        let table = String::from_utf8(table).unwrap();
+        let lines = table.lines().map(str::trim_end).collect::<Vec<_>>();
+        assert_eq!(
+            lines.join("\n"),
+            "5 frames, 34 bytes in 2.0 s (2.5 frames/s, 17.0 B/s)
+
+by call site:
+    frames      bytes        B/s      %  level   location
+         3         24       12.0   70.6  debug   src/sensor.rs:42 app::sensor
+         1          6        3.0   17.6  println index 2
+         1          4        2.0   11.8  info    src/main.rs:10 app
+
+by level:
+         3         24       12.0   70.6  debug
+         1          6        3.0   17.6  println
+         1          4        2.0   11.8  info
+
+by module:
+         3         24       12.0   70.6  app::sensor
+         1          6        3.0   17.6  <unknown>
+         1          4        2.0   11.8  app
+"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let report = stats().create_report(Duration::from_secs(2));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["frames"], 5);
+        assert_eq!(json["bytes"], 34);
+        assert_eq!(json["elapsed_secs"], 2.0);
+        assert_eq!(
+            json["call_sites"][0],
+            serde_json::json!({
+                "index": 1,
+                "level": "debug",
+                "file": "src/sensor.rs",
+                "line": 42,
+                "module": "app::sensor",
+                "frames": 3,
+                "bytes": 24,
+                "frames_per_sec": 1.5,
+                "bytes_per_sec": 12.0,
+            })
+        );
+        assert_eq!(
+            json["modules"][1],
+            serde_json::json!({
+                "name": "<unknown>",
+                "frames": 1,
+                "bytes": 6,
+                "frames_per_sec": 0.5,
+                "bytes_per_sec": 3.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use clap::ValueEnum;
+use defmt_decoder::{ClockSync, Frame, Table};
+use time::{macros::format_description, OffsetDateTime};
+
+/// How the timestamp of each frame is displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimestampMode {
+    /// The timestamp sent by the target, formatted as specified in `defmt::timestamp!`
+    #[default]
+    Target,
+    /// Host wall-clock time, estimated from the target timestamp
+    WallClock,
+    /// Time elapsed since the previous frame
+    Delta,
+}
+
+/// Correlates frame timestamps with host time and renders them according to a [`TimestampMode`].
+pub struct Timestamps {
+    mode: TimestampMode,
+    clock: ClockSync,
+    tick: Option<Duration>,
+    previous: Option<u64>,
+}
+
+impl Timestamps {
+    pub fn new(mode: TimestampMode, table: &Table) -> Self {
+        let tick = table.timestamp_tick_duration();
+        Self {
+            mode,
+            clock: ClockSync::new(tick),
+            tick,
+            previous: None,
+        }
+    }
+
+    /// Records that `frame` was received at `received` and returns its timestamp as it should be
+    /// displayed.
+    ///
+    /// Returns `None` if the timestamp sent by the target should be displayed as-is.
+    pub fn process(&mut self, frame: &Frame, received: OffsetDateTime) -> Option<String> {
+        let ticks = frame.timestamp_ticks()?;
+
+        let previous = self.previous.replace(ticks);
+        if matches!(previous, Some(previous) if ticks < previous) {
+            // the target clock went backwards, most likely due to a reset
+            self.clock = ClockSync::new(self.tick);
+        }
+        self.clock.add_sample(ticks, received);
+
+        match self.mode {
+            TimestampMode::Target => None,
+            TimestampMode::WallClock => {
+                let format = format_description!(
+                    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:6]Z"
+                );
+                let host_time = self.clock.estimate()?.host_time(ticks);
+                host_time.format(format).ok()
+            }
+            TimestampMode::Delta => {
+                let delta = ticks.saturating_sub(previous.unwrap_or(ticks));
+                Some(match self.tick {
+                    Some(tick) => {
+                        let micros = tick.as_nanos() * delta as u128 / 1_000;
+                        format!("+{}.{:06}", micros / 1_000_000, micros % 1_000_000)
+                    }
+                    None => format!("+{delta}"),
+                })
+            }
+        }
+    }
+
+    /// Logs the current estimate of the target clock's offset and drift.
+    pub fn report(&self) {
+        let estimate = match self.clock.estimate() {
+            Some(estimate) => estimate,
+            None => return,
+        };
+
+        let drift = estimate
+            .drift_ppm()
+            .map(|drift| format!(", drift {drift:+.1} ppm"))
+            .unwrap_or_default();
+        log::info!(
+            "target clock: {:.3} ns/tick{drift}, zero at {} (from {} samples)",
+            estimate.nanos_per_tick(),
+            estimate.offset(),
+            self.clock.samples()
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use defmt_decoder::Level;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::LocationInfo;
+
+/// Number of frames kept in memory; the oldest ones are dropped once it is exceeded.
+const MAX_RECORDS: usize = 100_000;
+
+/// A decoded frame, detached from the table it was decoded with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub level: Option<Level>,
+    pub timestamp: Option<String>,
+    pub message: String,
+    pub format: String,
+    pub args: Vec<String>,
+    pub location: LocationInfo,
+}
+
+impl Record {
+    fn module(&self) -> Option<&str> {
+        self.location.2.as_deref()
+    }
+
+    fn is_error(&self) -> bool {
+        self.level == Some(Level::Error)
+    }
+}
+
+/// Which frames are displayed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Filter {
+    /// Hide frames below this level; `println!` frames are always shown.
+    pub min_level: Option<Level>,
+    /// Only show frames logged from this module (or one of its submodules).
+    pub module: Option<String>,
+    /// Only show frames whose message contains this text (case-insensitive).
+    pub text: String,
+}
+
+impl Filter {
+    fn matches(&self, record: &Record) -> bool {
+        let level = match (self.min_level, record.level) {
+            (Some(min_level), Some(level)) => level >= min_level,
+            _ => true,
+        };
+        let module = match (&self.module, record.module()) {
+            (Some(filter), Some(module)) => {
+                module == filter || module.starts_with(&format!("{filter}::"))
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let text = self.text.is_empty()
+            || record
+                .message
+                .to_lowercase()
+                .contains(&self.text.to_lowercase());
+        level && module && text
+    }
+}
+
+/// State of the terminal UI, independent of the terminal it is drawn on.
+#[derive(Debug, Default)]
+pub struct App {
+    records: Vec<Record>,
+    /// Indices into `records` of the frames matching `filter`.
+    visible: Vec<usize>,
+    filter: Filter,
+    /// Position in `visible` of the selected frame; `None` follows the newest frame.
+    selected: Option<usize>,
+    /// First row of `visible` shown on screen; updated when drawing.
+    pub(super) scroll: usize,
+    /// Frames received while paused; they are shown when resuming.
+    paused: Option<Vec<Record>>,
+    expanded: bool,
+    /// Text filter being edited, if any.
+    input: Option<String>,
+    skipped: usize,
+    eof: bool,
+    quit: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a decoded frame.
+    pub fn push(&mut self, record: Record) {
+        if let Some(pending) = &mut self.paused {
+            pending.push(record);
+            if pending.len() > MAX_RECORDS {
+                pending.drain(..pending.len() - MAX_RECORDS + MAX_RECORDS / 10);
+            }
+            return;
+        }
+
+        if self.filter.matches(&record) {
+            self.visible.push(self.records.len());
+        }
+        self.records.push(record);
+
+        if self.records.len() > MAX_RECORDS {
+            let excess = self.records.len() - MAX_RECORDS + MAX_RECORDS / 10;
+            self.records.drain(..excess);
+            let dropped = self.visible.iter().take_while(|&&i| i < excess).count();
+            self.visible.drain(..dropped);
+            self.visible.iter_mut().for_each(|i| *i -= excess);
+            self.selected = self.selected.and_then(|pos| pos.checked_sub(dropped));
+            self.scroll = self.scroll.saturating_sub(dropped);
+        }
+    }
+
+    /// Records that a malformed frame was skipped.
+    pub fn skipped_frame(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// Records that the input stream has ended.
+    pub fn end_of_input(&mut self) {
+        self.eof = true;
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let text = self.input.take().unwrap_or_default();
+                    self.set_filter(|filter| filter.text = text);
+                }
+                KeyCode::Esc => self.input = None,
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-20),
+            KeyCode::PageDown => self.move_selection(20),
+            KeyCode::Home | KeyCode::Char('g') => self.select(Some(0)),
+            KeyCode::End | KeyCode::Char('G') => self.select(None),
+            KeyCode::Char(' ') | KeyCode::Char('p') => self.toggle_pause(),
+            KeyCode::Enter => self.expanded = !self.expanded,
+            KeyCode::Char('e') | KeyCode::Char('n') => self.jump_to_error(true),
+            KeyCode::Char('E') | KeyCode::Char('N') => self.jump_to_error(false),
+            KeyCode::Char('l') => {
+                let min_level = match self.filter.min_level {
+                    None => Some(Level::Debug),
+                    Some(Level::Trace) => Some(Level::Debug),
+                    Some(Level::Debug) => Some(Level::Info),
+                    Some(Level::Info) => Some(Level::Warn),
+                    Some(Level::Warn) => Some(Level::Error),
+                    Some(Level::Error) => None,
+                };
+                self.set_filter(|filter| filter.min_level = min_level);
+            }
+            KeyCode::Char('m') => {
+                let module = match self.filter.module {
+                    Some(_) => None,
+                    None => self
+                        .selected_record()
+                        .and_then(|record| record.module())
+                        .map(str::to_string),
+                };
+                self.set_filter(|filter| filter.module = module);
+            }
+            KeyCode::Char('/') => self.input = Some(self.filter.text.clone()),
+            KeyCode::Esc => self.set_filter(|filter| *filter = Filter::default()),
+            KeyCode::Char('c') => {
+                self.records.clear();
+                self.visible.clear();
+                self.selected = None;
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Frames matching the filter, oldest first.
+    pub fn visible(&self) -> impl ExactSizeIterator<Item = &Record> + '_ {
+        self.visible.iter().map(|&i| &self.records[i])
+    }
+
+    /// Position of the selected frame among the visible ones.
+    ///
+    /// While following the newest frame, that one is selected.
+    pub fn selected(&self) -> Option<usize> {
+        self.selected.or_else(|| self.visible.len().checked_sub(1))
+    }
+
+    pub fn selected_record(&self) -> Option<&Record> {
+        self.selected().map(|pos| &self.records[self.visible[pos]])
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.selected.is_none()
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    pub fn input(&self) -> Option<&str> {
+        self.input.as_deref()
+    }
+
+    /// Number of frames received while paused, or `None` if not paused.
+    pub fn paused(&self) -> Option<usize> {
+        self.paused.as_ref().map(Vec::len)
+    }
+
+    pub fn total(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    fn select(&mut self, pos: Option<usize>) {
+        self.selected = match pos {
+            Some(pos) if pos + 1 < self.visible.len() => Some(pos),
+            // selecting the last frame means following new frames, unless paused
+            _ if self.paused.is_some() => self.visible.len().checked_sub(1),
+            _ => None,
+        };
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let pos = match self.selected() {
+            Some(pos) => pos.saturating_add_signed(delta),
+            None => return,
+        };
+        self.select(Some(pos));
+    }
+
+    fn toggle_pause(&mut self) {
+        match self.paused.take() {
+            Some(pending) => {
+                pending.into_iter().for_each(|record| self.push(record));
+                self.select(self.selected);
+            }
+            None => {
+                self.selected = self.selected();
+                self.paused = Some(Vec::new());
+            }
+        }
+    }
+
+    /// Selects the next (or previous) error after (or before) the selected frame.
+    fn jump_to_error(&mut self, forward: bool) {
+        let current = match self.selected() {
+            Some(pos) => pos,
+            None => return,
+        };
+        let is_error = |&pos: &usize| self.records[self.visible[pos]].is_error();
+        let found = match forward {
+            true => (current + 1..self.visible.len()).find(is_error),
+            false => (0..current).rev().find(is_error),
+        };
+        if let Some(pos) = found {
+            self.select(Some(pos));
+        }
+    }
+
+    /// Changes the filter, keeping the selected frame selected if it is still visible.
+    fn set_filter(&mut self, change: impl FnOnce(&mut Filter)) {
+        let selected = self.selected.map(|pos| self.visible[pos]);
+        change(&mut self.filter);
+
+        self.visible = (0..self.records.len())
+            .filter(|&i| self.filter.matches(&self.records[i]))
+            .collect();
+        let pos = selected.map(|selected| self.visible.partition_point(|&i| i < selected));
+        self.select(pos);
+        self.scroll = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: Option<Level>, module: &str, message: &str) -> Record {
+        Record {
+            level,
+            timestamp: None,
+            message: message.to_string(),
+            format: message.to_string(),
+            args: Vec::new(),
+            location: (None, None, Some(module.to_string())),
+        }
+    }
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            app.handle_key(KeyEvent::from(code));
+        }
+    }
+
+    fn messages(app: &App) -> Vec<&str> {
+        app.visible().map(|record| &*record.message).collect()
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.push(record(Some(Level::Debug), "app", "starting"));
+        app.push(record(Some(Level::Info), "app::net", "link up"));
+        app.push(record(Some(Level::Error), "app::net", "timeout"));
+        app.push(record(None, "app", "println"));
+        app.push(record(Some(Level::Warn), "app::sensor", "out of range"));
+        app.push(record(Some(Level::Error), "app::sensor", "Timeout"));
+        app
+    }
+
+    #[test]
+    fn follows_new_frames() {
+        let mut app = app();
+        assert!(app.is_following());
+        assert_eq!(app.selected(), Some(5));
+
+        press(&mut app, "k");
+        assert!(!app.is_following());
+        assert_eq!(app.selected(), Some(4));
+        app.push(record(Some(Level::Info), "app", "new"));
+        assert_eq!(app.selected(), Some(4));
+
+        press(&mut app, "G");
+        assert_eq!(app.selected(), Some(6));
+        assert!(app.is_following());
+    }
+
+    #[test]
+    fn level_filter() {
+        let mut app = app();
+        press(&mut app, "ll");
+        assert_eq!(app.filter().min_level, Some(Level::Info));
+        assert_eq!(
+            messages(&app),
+            ["link up", "timeout", "println", "out of range", "Timeout"]
+        );
+        press(&mut app, "lll");
+        assert_eq!(app.filter().min_level, None);
+        assert_eq!(messages(&app).len(), 6);
+    }
+
+    #[test]
+    fn module_and_text_filter() {
+        let mut app = app();
+        press(&mut app, "gj");
+        press(&mut app, "m");
+        assert_eq!(app.filter().module.as_deref(), Some("app::net"));
+        assert_eq!(messages(&app), ["link up", "timeout"]);
+        assert_eq!(app.selected_record().unwrap().message, "link up");
+        press(&mut app, "m");
+        assert_eq!(messages(&app).len(), 6);
+
+        press(&mut app, "/time");
+        assert_eq!(app.input(), Some("time"));
+        assert_eq!(messages(&app).len(), 6);
+        press(&mut app, "\n");
+        assert_eq!(messages(&app), ["timeout", "Timeout"]);
+        press(&mut app, "\x1b");
+        assert_eq!(app.filter(), &Filter::default());
+    }
+
+    #[test]
+    fn jump_to_errors() {
+        let mut app = app();
+        press(&mut app, "g");
+        press(&mut app, "e");
+        assert_eq!(app.selected(), Some(2));
+        press(&mut app, "e");
+        assert_eq!(app.selected(), Some(5));
+        press(&mut app, "E");
+        assert_eq!(app.selected(), Some(2));
+    }
+
+    #[test]
+    fn pause() {
+        let mut app = app();
+        press(&mut app, " ");
+        app.push(record(Some(Level::Info), "app", "while paused"));
+        assert_eq!(app.paused(), Some(1));
+        assert_eq!(messages(&app).len(), 6);
+        assert_eq!(app.selected(), Some(5));
+
+        press(&mut app, " ");
+        assert_eq!(app.paused(), None);
+        assert_eq!(messages(&app).len(), 7);
+        assert_eq!(app.selected(), Some(5));
+
+        // the frames received while paused are capped like the others, dropping the oldest
+        press(&mut app, " ");
+        for i in 0..=MAX_RECORDS {
+            app.push(record(Some(Level::Info), "app", &i.to_string()));
+        }
+        let kept = MAX_RECORDS - MAX_RECORDS / 10;
+        assert_eq!(app.paused(), Some(kept));
+        press(&mut app, " ");
+        let messages = messages(&app);
+        assert_eq!(messages.len(), 7 + kept);
+        assert_eq!(messages[7], (MAX_RECORDS + 1 - kept).to_string());
+        assert_eq!(messages.last(), Some(&&*MAX_RECORDS.to_string()));
+    }
+}
@@ -0,0 +1,261 @@
+//! Interactive terminal UI, enabled with `--tui`.
+
+mod app;
+mod ui;
+
+use std::{
+    io::{self, Read},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use defmt_decoder::{DecodeError, Level, Locations, StreamDecoder, Table};
+use ratatui::{
+    crossterm::event::{self, Event, KeyEventKind},
+    DefaultTerminal,
+};
+use time::OffsetDateTime;
+
+use self::app::{App, Record};
+use crate::{location_info, timestamp::Timestamps, READ_BUFFER_SIZE};
+
+/// How long to wait for key presses before checking for new data.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Decodes a byte stream and feeds the frames into an [`App`].
+pub struct Session<'t> {
+    table: &'t Table,
+    locs: &'t Option<Locations>,
+    decoder: Box<dyn StreamDecoder + 't>,
+    timestamps: Timestamps,
+    current_dir: PathBuf,
+    app: App,
+}
+
+impl<'t> Session<'t> {
+    pub fn new(
+        table: &'t Table,
+        locs: &'t Option<Locations>,
+        timestamps: Timestamps,
+        current_dir: PathBuf,
+    ) -> Self {
+        Self {
+            table,
+            locs,
+            decoder: table.new_stream_decoder(),
+            timestamps,
+            current_dir,
+            app: App::new(),
+        }
+    }
+
+    /// Decodes `bytes`, which were received at `received`, and adds the complete frames to the app.
+    fn received(&mut self, bytes: &[u8], received: OffsetDateTime) -> anyhow::Result<()> {
+        self.decoder.received(bytes);
+        loop {
+            match self.decoder.decode() {
+                // a handshake with the build ID of the ELF file is not part of the log
+                Ok(frame) if frame.build_id().is_some() => {}
+                Ok(frame) => {
+                    let timestamp = self
+                        .timestamps
+                        .process(&frame, received)
+                        .or_else(|| frame.display_timestamp().map(|ts| ts.to_string()));
+                    let record = Record {
+                        level: frame.level(),
+                        timestamp,
+                        message: frame.display_message().to_string(),
+                        format: frame.format().to_string(),
+                        args: frame.display_args(),
+                        location: location_info(self.locs, &frame, &self.current_dir),
+                    };
+                    self.app.push(record);
+                }
+                Err(DecodeError::UnexpectedEof) => return Ok(()),
+                Err(e @ DecodeError::BuildIdMismatch { .. }) => match self.table.stable_ids() {
+                    // stable indices don't depend on the build, see `main`
+                    true => self.app.push(Record {
+                        level: Some(Level::Warn),
+                        timestamp: None,
+                        message: format!("(HOST) {e}"),
+                        format: String::new(),
+                        args: Vec::new(),
+                        location: (None, None, None),
+                    }),
+                    false => return Err(e.into()),
+                },
+                Err(DecodeError::Malformed) => match self.table.encoding().can_recover() {
+                    // if recovery is impossible, abort
+                    false => return Err(DecodeError::Malformed.into()),
+                    // if recovery is possible, skip the current frame and continue with new data
+                    true => self.app.skipped_frame(),
+                },
+            }
+        }
+    }
+}
+
+/// Runs the terminal UI on stdin until the user quits.
+///
+/// Key presses are read from the terminal, so stdin can be a pipe.
+pub fn run(mut session: Session) -> anyhow::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0; READ_BUFFER_SIZE];
+        // stop on EOF, on errors and when the UI has quit
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            let received = OffsetDateTime::now_utc();
+            if sender.send((buf[..n].to_vec(), received)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut session, &receiver);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    session: &mut Session,
+    receiver: &Receiver<(Vec<u8>, OffsetDateTime)>,
+) -> anyhow::Result<()> {
+    loop {
+        loop {
+            match receiver.try_recv() {
+                Ok((bytes, received)) => session.received(&bytes, received)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    session.app.end_of_input();
+                    break;
+                }
+            }
+        }
+
+        terminal.draw(|frame| ui::draw(frame, &mut session.app))?;
+
+        if event::poll(POLL_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    session.app.handle_key(key);
+                }
+            }
+        }
+        if session.app.should_quit() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use defmt_decoder::{Encoding, StringEntry, TableEntry, Tag};
+    use ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal};
+
+    use super::*;
+    use crate::timestamp::TimestampMode;
+
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| ui::draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(width as usize)
+            .map(|row| {
+                let line = row.iter().map(|cell| cell.symbol()).collect::<String>();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn headless() {
+        let entry = |tag, format: &str| {
+            TableEntry::new(StringEntry::new(tag, format.to_string()), String::new())
+        };
+        let table = Table::new(
+            [
+                entry(Tag::Info, "temperature: {=u8}°C"),
+                entry(Tag::Error, "sensor {=u8} failed"),
+            ],
+            Encoding::Raw,
+        );
+        let timestamps = Timestamps::new(TimestampMode::Target, &table);
+        let mut session = Session::new(&table, &None, timestamps, PathBuf::new());
+
+        // a recorded stream of three frames, received in chunks that don't align with frames
+        let stream = [0, 0, 21, 1, 0, 3, 0, 0, 22];
+        for chunk in stream.chunks(4) {
+            session.received(chunk, OffsetDateTime::UNIX_EPOCH).unwrap();
+        }
+        let app = &mut session.app;
+        app.end_of_input();
+
+        let screen = render(app, 40, 4);
+        assert_eq!(
+            screen[..3],
+            [
+                "INFO  temperature: 21°C",
+                "ERROR sensor 3 failed",
+                "INFO  temperature: 22°C",
+            ]
+        );
+        assert!(screen[3].starts_with("3/3 frames | end of input"));
+
+        app.handle_key(KeyCode::Char('E').into());
+        app.handle_key(KeyCode::Enter.into());
+        let screen = render(app, 40, 13);
+        assert_eq!(
+            screen[2..8],
+            [
+                " details ───────────────────────────────",
+                "location: <unknown>",
+                "module:   <unknown>",
+                "format:   \"sensor {=u8} failed\"",
+                "arg 0:    3",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_id_handshake() {
+        let entry = TableEntry::new(
+            StringEntry::new(Tag::Info, "temperature: {=u8}°C".to_string()),
+            String::new(),
+        );
+        let mut table = Table::new([], Encoding::Raw);
+        // index 0 is reserved for the handshake
+        table.insert(1, entry);
+        table.set_build_id(Some(vec![0xde, 0xfe, 0xc7]));
+        let timestamps = Timestamps::new(TimestampMode::Target, &table);
+        let mut session = Session::new(&table, &None, timestamps, PathBuf::new());
+
+        // a handshake with the build ID of the ELF file between two log frames
+        let stream = [1, 0, 21, 0, 0, 3, 0, 0, 0, 0xde, 0xfe, 0xc7, 1, 0, 22];
+        session
+            .received(&stream, OffsetDateTime::UNIX_EPOCH)
+            .unwrap();
+        let app = &mut session.app;
+        app.end_of_input();
+
+        let screen = render(app, 40, 3);
+        assert_eq!(
+            screen[..2],
+            ["INFO  temperature: 21°C", "INFO  temperature: 22°C"]
+        );
+        assert!(screen[2].starts_with("2/2 frames | end of input"));
+
+        // a handshake with a different build ID is an error
+        let error = session
+            .received(&[0, 0, 2, 0, 0, 0, 0xde, 0xad], OffsetDateTime::UNIX_EPOCH)
+            .unwrap_err();
+        assert!(error.to_string().contains("build ID dead"));
+    }
+}
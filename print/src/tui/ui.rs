@@ -0,0 +1,160 @@
+use defmt_decoder::Level;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::app::{App, Record};
+
+/// Height of the details pane, including its border.
+const DETAILS_HEIGHT: u16 = 10;
+
+const HELP: &str =
+    "q quit  space pause  ↑↓ select  enter details  e/E errors  l level  m module  / search  esc reset";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let details_height = match app.is_expanded() {
+        true => DETAILS_HEIGHT,
+        false => 0,
+    };
+    let [list, details, status] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(details_height),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_list(frame, app, list);
+    if let Some(record) = app.selected_record().filter(|_| app.is_expanded()) {
+        draw_details(frame, record, details);
+    }
+    draw_status(frame, app, status);
+}
+
+fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let height = area.height as usize;
+    let selected = app.selected();
+
+    // keep the selected frame on screen
+    if let Some(selected) = selected {
+        if selected < app.scroll {
+            app.scroll = selected;
+        } else if selected >= app.scroll + height {
+            app.scroll = selected + 1 - height;
+        }
+    }
+
+    let lines = app
+        .visible()
+        .enumerate()
+        .skip(app.scroll)
+        .take(height)
+        .map(|(pos, record)| {
+            let line = record_line(record);
+            match Some(pos) == selected && !app.is_following() {
+                true => line.add_modifier(Modifier::REVERSED),
+                false => line,
+            }
+        })
+        .collect::<Vec<_>>();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+fn record_line(record: &Record) -> Line<'_> {
+    let mut spans = Vec::new();
+    if let Some(timestamp) = &record.timestamp {
+        spans.push(Span::raw(timestamp.as_str()).dim());
+        spans.push(Span::raw(" "));
+    }
+    let level = record.level.map_or("PRINT", |level| match level {
+        Level::Trace => "TRACE",
+        Level::Debug => "DEBUG",
+        Level::Info => "INFO ",
+        Level::Warn => "WARN ",
+        Level::Error => "ERROR",
+    });
+    spans.push(Span::styled(level, level_style(record.level)));
+    spans.push(Span::raw(" "));
+    spans.push(Span::raw(record.message.as_str()));
+    Line::from(spans)
+}
+
+fn level_style(level: Option<Level>) -> Style {
+    let color = match level {
+        None => Color::Reset,
+        Some(Level::Trace) => Color::DarkGray,
+        Some(Level::Debug) => Color::White,
+        Some(Level::Info) => Color::Green,
+        Some(Level::Warn) => Color::Yellow,
+        Some(Level::Error) => Color::Red,
+    };
+    Style::new().fg(color)
+}
+
+fn draw_details(frame: &mut Frame, record: &Record, area: Rect) {
+    let (file, line, module) = &record.location;
+    let location = match (file, line) {
+        (Some(file), Some(line)) => format!("{file}:{line}"),
+        _ => "<unknown>".to_string(),
+    };
+
+    let mut lines = vec![
+        Line::from(vec!["location: ".bold(), location.into()]),
+        Line::from(vec![
+            "module:   ".bold(),
+            module.as_deref().unwrap_or("<unknown>").into(),
+        ]),
+        Line::from(vec![
+            "format:   ".bold(),
+            format!("{:?}", record.format).into(),
+        ]),
+    ];
+    for (i, arg) in record.args.iter().enumerate() {
+        lines.push(Line::from(vec![
+            format!("arg {i}:    ").bold(),
+            arg.as_str().into(),
+        ]));
+    }
+
+    let block = Block::new().borders(Borders::TOP).title(" details ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(input) = app.input() {
+        let line = Line::from(vec!["search: ".bold(), input.into(), "█".into()]);
+        frame.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    let mut status = format!("{}/{} frames", app.visible().len(), app.total());
+    let filter = app.filter();
+    if let Some(level) = filter.min_level {
+        status.push_str(&format!(" | level >= {}", level.as_str()));
+    }
+    if let Some(module) = &filter.module {
+        status.push_str(&format!(" | module {module}"));
+    }
+    if !filter.text.is_empty() {
+        status.push_str(&format!(" | search {:?}", filter.text));
+    }
+    if app.skipped() > 0 {
+        status.push_str(&format!(" | {} malformed", app.skipped()));
+    }
+    if let Some(pending) = app.paused() {
+        status.push_str(&format!(" | PAUSED ({pending} new)"));
+    }
+    if app.is_eof() {
+        status.push_str(" | end of input");
+    }
+
+    let line = Line::from(vec![
+        Span::raw(status).reversed(),
+        Span::raw("  "),
+        Span::raw(HELP).dim(),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
@@ -66,39 +66,56 @@ fn notmain() -> Result<Option<i32>, anyhow::Error> {
 
     let mut decoder = table.new_stream_decoder();
 
+    // how much data the firmware sent, reported with `QEMU_RUN_STATS`
+    let mut received = 0;
+    let mut frames = 0;
+
     let mut readbuf = [0; 256];
     let exit_code;
     loop {
         let n = stdout.read(&mut readbuf)?;
+        received += n;
         decoder.received(&readbuf[..n]);
-        decode(&mut *decoder)?;
+        frames += decode(&mut *decoder)?;
 
         if let Some(status) = child.0.try_wait()? {
             exit_code = status.code();
 
             let mut data = Vec::new();
             stdout.read_to_end(&mut data)?;
+            received += data.len();
             decoder.received(&data);
-            decode(&mut *decoder)?;
+            frames += decode(&mut *decoder)?;
 
             break;
         }
     }
 
+    if env::var_os("QEMU_RUN_STATS").is_some() {
+        eprintln!("(qemu-run) {frames} frames, {received} bytes");
+    }
+
     Ok(exit_code)
 }
 
-fn decode(decoder: &mut dyn StreamDecoder) -> Result<(), DecodeError> {
+/// Prints the frames in the `decoder` and returns how many there were.
+fn decode(decoder: &mut dyn StreamDecoder) -> Result<usize, DecodeError> {
+    let mut frames = 0;
     loop {
         match decoder.decode() {
             Ok(frame) => {
-                println!("{}", frame.display(true))
+                println!("{}", frame.display(true));
+                frames += 1;
             }
-            Err(DecodeError::UnexpectedEof) => return Ok(()),
+            Err(DecodeError::UnexpectedEof) => return Ok(frames),
             Err(DecodeError::Malformed) => {
                 eprintln!("failed to decode defmt data");
                 return Err(DecodeError::Malformed);
             }
+            Err(e @ DecodeError::BuildIdMismatch { .. }) => {
+                eprintln!("{e}");
+                return Err(e);
+            }
         }
     }
 }
@@ -0,0 +1,104 @@
+use std::{process::Command, str};
+
+use anyhow::{anyhow, Context};
+use colored::Colorize;
+
+use crate::{
+    do_test,
+    snapshot::{ALL_SNAPSHOT_TESTS, SNAPSHOT_TESTS_DIRECTORY},
+};
+
+/// How much data a snapshot test sent to the host.
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    frames: usize,
+    bytes: usize,
+}
+
+impl Stats {
+    fn bytes_per_frame(self) -> f64 {
+        self.bytes as f64 / self.frames.max(1) as f64
+    }
+}
+
+/// Compares the bytes per frame of the snapshot tests with and without the `varint` feature.
+pub fn bench_varint() {
+    println!("📏 qemu/varint");
+
+    let mut total = (Stats::default(), Stats::default());
+    println!(
+        "{:<12} {:>6} {:>14} {:>14} {:>8}",
+        "test", "frames", "fixed B/frame", "varint B/frame", "saved"
+    );
+    for test in ALL_SNAPSHOT_TESTS {
+        do_test(
+            || {
+                let fixed = measure(test, "")?;
+                let varint = measure(test, "varint")?;
+                if fixed.frames != varint.frames {
+                    return Err(anyhow!(
+                        "{test}: {} frames with fixed-width integers, {} with varints",
+                        fixed.frames,
+                        varint.frames
+                    ));
+                }
+                print_row(test, fixed, varint);
+                total.0.frames += fixed.frames;
+                total.0.bytes += fixed.bytes;
+                total.1.frames += varint.frames;
+                total.1.bytes += varint.bytes;
+                Ok(())
+            },
+            "qemu/varint",
+        );
+    }
+    print_row("total", total.0, total.1);
+}
+
+fn print_row(name: &str, fixed: Stats, varint: Stats) {
+    let saved = 1.0 - varint.bytes as f64 / fixed.bytes.max(1) as f64;
+    println!(
+        "{:<12} {:>6} {:>14.1} {:>14.1} {:>7.1}%",
+        name.bold(),
+        fixed.frames,
+        fixed.bytes_per_frame(),
+        varint.bytes_per_frame(),
+        saved * 100.0
+    );
+}
+
+/// Runs the snapshot test `name` and returns the statistics `qemu-run` reports.
+fn measure(name: &str, features: &str) -> anyhow::Result<Stats> {
+    let mut args = match name.contains("test") {
+        true => vec!["-q", "tt", name],
+        false => vec!["-q", "rb", name],
+    };
+    if !features.is_empty() {
+        args.extend_from_slice(&["--features", features]);
+    }
+
+    let output = Command::new("cargo")
+        .args(&args)
+        .env("DEFMT_LOG", "trace")
+        .env("QEMU_RUN_STATS", "1")
+        .current_dir(SNAPSHOT_TESTS_DIRECTORY)
+        .output()
+        .with_context(|| name.to_string())?;
+    let stderr = str::from_utf8(&output.stderr)?;
+
+    // "(qemu-run) 12 frames, 345 bytes"
+    let stats = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("(qemu-run) "))
+        .and_then(|line| {
+            let (frames, bytes) = line.strip_suffix(" bytes")?.split_once(" frames, ")?;
+            Some(Stats {
+                frames: frames.parse().ok()?,
+                bytes: bytes.parse().ok()?,
+            })
+        });
+    stats.ok_or_else(|| {
+        eprintln!("{}", stderr.dimmed());
+        anyhow!("{name}: `qemu-run` didn't report statistics")
+    })
+}
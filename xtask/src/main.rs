@@ -1,4 +1,5 @@
 mod backcompat;
+mod bench;
 mod snapshot;
 mod targets;
 mod utils;
@@ -32,6 +33,8 @@ struct Options {
 #[derive(Debug, Subcommand)]
 #[allow(clippy::enum_variant_names)]
 enum TestCommand {
+    /// Compare the bytes per frame of the snapshot tests with and without the `varint` feature
+    BenchVarint,
     TestAll,
     TestBackcompat,
     TestBook,
@@ -64,6 +67,7 @@ fn main() -> anyhow::Result<()> {
         cmd => {
             added_targets = Some(targets::install().expect("Error while installing required targets"));
             match cmd {
+                TestCommand::BenchVarint => bench::bench_varint(),
                 TestCommand::TestCross => test_cross(opt.deny_warnings),
                 TestCommand::TestSnapshot { overwrite, single } => {
                     test_snapshot(overwrite, single);
@@ -108,19 +112,82 @@ fn test_host(deny_warnings: bool) {
         false => vec![],
     };
 
-    for feat in ["", "unstable-test", "alloc"] {
+    for feat in ["", "unstable-test", "alloc", "std", "build-id", "fixed,half"] {
         do_test(
             || run_command("cargo", &["check", "--features", feat], None, &env),
             "host",
         );
     }
 
-    for feat in ["unstable-test", "unstable-test,alloc"] {
+    for feat in [
+        "unstable-test",
+        "unstable-test,alloc",
+        "unstable-test,varint",
+        "unstable-test,timestamp-delta",
+        "unstable-test,stable-ids",
+        "unstable-test,build-id",
+    ] {
         do_test(
             || run_command("cargo", &["test", "--features", feat], None, &env),
             "host",
         );
     }
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &[
+                    "test",
+                    "-p",
+                    "defmt",
+                    "--features",
+                    "std,alloc,fixed,half",
+                    "--test",
+                    "std",
+                ],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "-p", "defmt", "--features", "std,varint", "--test", "std"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "-p", "defmt-decoder", "--features", "unstable,tracing"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "-p", "defmt-log-bridge"],
+                Some("firmware"),
+                &env,
+            )
+        },
+        "host",
+    );
 }
 
 fn test_cross(deny_warnings: bool) {